@@ -0,0 +1,167 @@
+//! Benchmarks for the hot paths of `TimedMap` across map kinds, entry counts and expiration
+//! tick caps.
+//!
+//! Run with `cargo bench`, optionally enabling `rustc-hash` and `ahash` to include their map
+//! kinds. Reports are written to `target/criterion/report/index.html`.
+
+use std::hint::black_box;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use timed_map::{MapKind, StdClock, TimedMap};
+
+const MAP_KINDS: &[MapKind] = &[
+    MapKind::BTreeMap,
+    MapKind::HashMap,
+    MapKind::VecMap,
+    #[cfg(feature = "rustc-hash")]
+    MapKind::FxHashMap,
+    #[cfg(feature = "ahash")]
+    MapKind::AHashMap,
+];
+
+const ENTRY_COUNTS: &[u64] = &[16, 1_000, 10_000];
+
+const TICK_CAPS: &[u16] = &[1, 100];
+
+/// Upper bound of entries kept alive at once while waiting for them to expire.
+const MAX_PENDING_ENTRIES: u64 = 1_000_000;
+
+const TTL: Duration = Duration::from_secs(60);
+
+fn filled(kind: MapKind, count: u64, ttl: Duration) -> TimedMap<StdClock, u64, u64> {
+    let mut map = TimedMap::new_with_map_kind(kind);
+    for k in 0..count {
+        map.insert_expirable_unchecked(k, k, ttl);
+    }
+    map
+}
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert");
+    group.throughput(Throughput::Elements(1));
+
+    for &kind in MAP_KINDS {
+        for &count in ENTRY_COUNTS {
+            for &tick_cap in TICK_CAPS {
+                let id = BenchmarkId::new(
+                    format!("{kind:?}/tick_cap={tick_cap}"),
+                    format!("{count} entries"),
+                );
+                group.bench_function(id, |b| {
+                    let mut map = filled(kind, count, TTL).expiration_tick_cap(tick_cap);
+                    let mut k = 0;
+                    b.iter(|| {
+                        k = (k + 1) % count;
+                        black_box(map.insert_expirable(k, k, TTL));
+                    });
+                });
+            }
+        }
+    }
+
+    group.finish();
+}
+
+fn get(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get");
+    group.throughput(Throughput::Elements(1));
+
+    for &kind in MAP_KINDS {
+        for &count in ENTRY_COUNTS {
+            let id = BenchmarkId::new(format!("{kind:?}"), format!("{count} entries"));
+            group.bench_function(id, |b| {
+                let map = filled(kind, count, TTL);
+                let mut k = 0;
+                b.iter(|| {
+                    k = (k + 1) % count;
+                    black_box(map.get(&k));
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn remove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("remove");
+
+    for &kind in MAP_KINDS {
+        for &count in ENTRY_COUNTS {
+            group.throughput(Throughput::Elements(count));
+
+            let id = BenchmarkId::new(format!("{kind:?}"), format!("{count} entries"));
+            group.bench_function(id, |b| {
+                b.iter_batched_ref(
+                    || filled(kind, count, TTL),
+                    |map| {
+                        for k in 0..count {
+                            black_box(map.remove(&k));
+                        }
+                    },
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn drop_expired(c: &mut Criterion) {
+    let mut group = c.benchmark_group("drop_expired");
+    // Every sample waits for the clock to pass the deadlines, so keep the run short.
+    group
+        .sample_size(10)
+        .warm_up_time(Duration::from_secs(1))
+        .measurement_time(Duration::from_secs(1));
+
+    for &kind in MAP_KINDS {
+        for &count in ENTRY_COUNTS {
+            // Nothing is due, which is what most cleanup passes triggered by inserts see.
+            group.throughput(Throughput::Elements(1));
+            let id = BenchmarkId::new(format!("{kind:?}/idle"), format!("{count} entries"));
+            group.bench_function(id, |b| {
+                let mut map = filled(kind, count, TTL);
+                b.iter(|| map.drop_expired_entries());
+            });
+
+            // Every entry is due.
+            group.throughput(Throughput::Elements(count));
+            let id = BenchmarkId::new(format!("{kind:?}/due"), format!("{count} entries"));
+            group.bench_function(id, |b| {
+                b.iter_custom(|iters| {
+                    let chunk = (MAX_PENDING_ENTRIES / count).max(1);
+                    let mut elapsed = Duration::ZERO;
+
+                    let mut remaining = iters;
+                    while remaining > 0 {
+                        let n = remaining.min(chunk);
+                        remaining -= n;
+
+                        let mut maps: Vec<_> = (0..n)
+                            .map(|_| filled(kind, count, Duration::ZERO))
+                            .collect();
+                        // Clocks count whole seconds, so entries expire a second after insertion.
+                        thread::sleep(Duration::from_secs(1));
+
+                        let started_at = Instant::now();
+                        for map in &mut maps {
+                            map.drop_expired_entries();
+                        }
+                        elapsed += started_at.elapsed();
+                    }
+
+                    elapsed
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, insert, get, remove, drop_expired);
+criterion_main!(benches);
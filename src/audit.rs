@@ -0,0 +1,149 @@
+use super::*;
+
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::VecDeque;
+
+/// Kind of an operation recorded by the audit log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AuditOp {
+    /// Entry was inserted by one of the insert functions.
+    Insert,
+    /// Entry was removed explicitly by `TimedMap::remove` or `TimedMap::remove_unchecked`.
+    Remove,
+    /// Entry was dropped by a cleanup pass after expiring.
+    Expire,
+}
+
+/// Outcome of an operation recorded by the audit log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AuditOutcome {
+    /// A new entry was created.
+    Created,
+    /// An existing entry was overwritten.
+    Replaced,
+    /// An existing entry was removed.
+    Removed,
+}
+
+/// Operation recorded by the audit log enabled with `TimedMap::audit_log`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditRecord<K> {
+    /// Kind of the operation.
+    pub op: AuditOp,
+    /// Key the operation was applied to.
+    pub key: K,
+    /// Time of the operation, as elapsed since the creation of the map's clock.
+    pub at: Duration,
+    /// What the operation did.
+    pub outcome: AuditOutcome,
+}
+
+/// Kind of a change returned by `TimedMap::take_changes`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Change {
+    /// Key was inserted while it had no entry.
+    Inserted,
+    /// Entry of the key was overwritten.
+    Updated,
+    /// Entry of the key was removed explicitly.
+    Removed,
+    /// Entry of the key was dropped by a cleanup pass after expiring.
+    Expired,
+}
+
+impl Change {
+    /// Merges `self` with a later change of the same key.
+    ///
+    /// Updates of keys inserted since the last `TimedMap::take_changes` call are still
+    /// reported as inserts, otherwise the later change wins.
+    #[inline(always)]
+    pub(crate) fn merge(self, later: Change) -> Change {
+        match (self, later) {
+            (Change::Inserted, Change::Updated) => Change::Inserted,
+            (_, later) => later,
+        }
+    }
+}
+
+/// Bounded ring buffer holding the most recent `AuditRecord`s.
+pub(crate) struct AuditLog<K> {
+    capacity: usize,
+    records: VecDeque<AuditRecord<K>>,
+}
+
+impl<K> AuditLog<K> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            records: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Appends `record`, evicting the oldest one if the log is full.
+    pub(crate) fn push(&mut self, record: AuditRecord<K>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+
+        self.records.push_back(record);
+    }
+
+    /// Returns the maximum number of records kept.
+    #[inline(always)]
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the records from the oldest to the most recent.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &AuditRecord<K>> {
+        self.records.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_log_evicts_oldest_records() {
+        let mut log = AuditLog::new(2);
+
+        for key in 1..=3 {
+            log.push(AuditRecord {
+                op: AuditOp::Insert,
+                key,
+                at: Duration::ZERO,
+                outcome: AuditOutcome::Created,
+            });
+        }
+
+        assert_eq!(log.iter().map(|r| r.key).collect::<Vec<_>>(), [2, 3]);
+
+        let mut disabled = AuditLog::new(0);
+        disabled.push(AuditRecord {
+            op: AuditOp::Remove,
+            key: 1,
+            at: Duration::ZERO,
+            outcome: AuditOutcome::Removed,
+        });
+        assert_eq!(disabled.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_change_merge() {
+        assert_eq!(Change::Inserted.merge(Change::Updated), Change::Inserted);
+        assert_eq!(Change::Inserted.merge(Change::Removed), Change::Removed);
+        assert_eq!(Change::Removed.merge(Change::Inserted), Change::Inserted);
+        assert_eq!(Change::Updated.merge(Change::Expired), Change::Expired);
+    }
+}
@@ -0,0 +1,191 @@
+use super::*;
+use map::GenericKey;
+
+use core::cell::{Cell, Ref, RefCell, RefMut};
+use core::ops::{Deref, DerefMut};
+use std::rc::{Rc, Weak};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+
+/// Pending `setTimeout` call of `AutoCleanupTimedMap`.
+#[derive(Clone, Copy)]
+struct Timer {
+    handle: i32,
+    due: Instant,
+}
+
+/// State shared between `AutoCleanupTimedMap` and its timer callback.
+struct Shared<K, V> {
+    map: RefCell<TimedMap<StdClock, K, V>>,
+    timer: Cell<Option<Timer>>,
+    // Created once and reused by every timer, so nothing is leaked when timers are cancelled.
+    callback: Closure<dyn FnMut()>,
+}
+
+impl<K, V> Shared<K, V>
+where
+    K: GenericKey,
+{
+    /// Drops the expired entries once the timer fires, and then schedules the next one.
+    fn on_timer(&self) {
+        self.timer.set(None);
+
+        // The map may only be borrowed here if a borrow is held across an `await`, in which
+        // case the guard schedules the next timer once it's released.
+        if let Ok(mut map) = self.map.try_borrow_mut() {
+            map.drop_expired_entries();
+            drop(map);
+            self.schedule();
+        }
+    }
+
+    /// Makes sure a timer is due by the next deadline, replacing the pending one if it's due
+    /// later. Does nothing outside browsing contexts, where `window` is not available.
+    fn schedule(&self) {
+        let Some(delay) = self.map.borrow().next_cleanup_in() else {
+            self.cancel();
+            return;
+        };
+        let due = Instant::now() + delay;
+
+        if self.timer.get().is_some_and(|timer| timer.due <= due) {
+            return;
+        }
+        self.cancel();
+
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        let timeout = i32::try_from(delay.as_millis()).unwrap_or(i32::MAX);
+        if let Ok(handle) = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+            self.callback.as_ref().unchecked_ref(),
+            timeout,
+        ) {
+            self.timer.set(Some(Timer { handle, due }));
+        }
+    }
+
+    /// Cancels the pending timer, if any.
+    fn cancel(&self) {
+        if let (Some(timer), Some(window)) = (self.timer.take(), web_sys::window()) {
+            window.clear_timeout_with_handle(timer.handle);
+        }
+    }
+}
+
+/// `TimedMap` for browser apps that drops its expired entries with `setTimeout`, even when
+/// the map isn't being written to.
+///
+/// A single timer is kept aligned to the soonest deadline, and it's rescheduled whenever a
+/// mutable borrow of the map is released. The timer is cancelled once the map is dropped.
+///
+/// # Example usage:
+/// ```rs
+/// let cache = AutoCleanupTimedMap::new(TimedMap::new());
+///
+/// cache.borrow_mut().insert_expirable(key, value, Duration::from_secs(60));
+/// let value = cache.borrow().get(&key).cloned();
+/// ```
+pub struct AutoCleanupTimedMap<K, V> {
+    shared: Rc<Shared<K, V>>,
+}
+
+impl<K, V> AutoCleanupTimedMap<K, V>
+where
+    K: GenericKey + 'static,
+    V: 'static,
+{
+    /// Wraps `map` and schedules the first cleanup.
+    pub fn new(map: TimedMap<StdClock, K, V>) -> Self {
+        let shared = Rc::new_cyclic(|weak: &Weak<Shared<K, V>>| {
+            let weak = weak.clone();
+            Shared {
+                map: RefCell::new(map),
+                timer: Cell::new(None),
+                callback: Closure::new(move || {
+                    if let Some(shared) = weak.upgrade() {
+                        shared.on_timer();
+                    }
+                }),
+            }
+        });
+        shared.schedule();
+
+        Self { shared }
+    }
+
+    /// Immutably borrows the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map is currently mutably borrowed.
+    pub fn borrow(&self) -> Ref<'_, TimedMap<StdClock, K, V>> {
+        self.shared.map.borrow()
+    }
+
+    /// Mutably borrows the map. The cleanup timer is rescheduled once the returned guard is
+    /// dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map is currently borrowed.
+    pub fn borrow_mut(&self) -> AutoCleanupGuard<'_, K, V> {
+        AutoCleanupGuard {
+            map: Some(self.shared.map.borrow_mut()),
+            shared: &self.shared,
+        }
+    }
+}
+
+impl<K, V> Drop for AutoCleanupTimedMap<K, V> {
+    fn drop(&mut self) {
+        if let (Some(timer), Some(window)) = (self.shared.timer.take(), web_sys::window()) {
+            window.clear_timeout_with_handle(timer.handle);
+        }
+    }
+}
+
+/// Mutable borrow of the map of `AutoCleanupTimedMap`, which reschedules the cleanup timer
+/// once dropped.
+pub struct AutoCleanupGuard<'a, K, V>
+where
+    K: GenericKey,
+{
+    map: Option<RefMut<'a, TimedMap<StdClock, K, V>>>,
+    shared: &'a Shared<K, V>,
+}
+
+impl<K, V> Deref for AutoCleanupGuard<'_, K, V>
+where
+    K: GenericKey,
+{
+    type Target = TimedMap<StdClock, K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        self.map
+            .as_ref()
+            .expect("map is borrowed until the guard is dropped")
+    }
+}
+
+impl<K, V> DerefMut for AutoCleanupGuard<'_, K, V>
+where
+    K: GenericKey,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.map
+            .as_mut()
+            .expect("map is borrowed until the guard is dropped")
+    }
+}
+
+impl<K, V> Drop for AutoCleanupGuard<'_, K, V>
+where
+    K: GenericKey,
+{
+    fn drop(&mut self) {
+        // Release the borrow first, as scheduling reads the map.
+        self.map.take();
+        self.shared.schedule();
+    }
+}
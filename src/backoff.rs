@@ -0,0 +1,106 @@
+use super::*;
+use map::GenericKey;
+
+/// Value wrapper used by `TimedMap::insert_with_backoff` which tracks how many times
+/// the entry has been re-inserted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Backoff<V> {
+    value: V,
+    attempts: u32,
+}
+
+impl<V> Backoff<V> {
+    /// Returns the wrapped value.
+    #[inline(always)]
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    /// Returns how many consecutive times the entry has been inserted, starting from 1.
+    #[inline(always)]
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Returns owned `V` and consumes `self`.
+    #[inline(always)]
+    pub fn into_value(self) -> V {
+        self.value
+    }
+}
+
+/// Returns `base * 2^(attempts - 1)`, capped at `cap`.
+#[inline(always)]
+fn backoff_duration(base: Duration, cap: Duration, attempts: u32) -> Duration {
+    1u32.checked_shl(attempts.saturating_sub(1))
+        .and_then(|factor| base.checked_mul(factor))
+        .map_or(cap, |duration| duration.min(cap))
+}
+
+/// Exponential backoff helpers for retry suppression and circuit breakers.
+impl<C, K, V> TimedMap<C, K, Backoff<V>>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Inserts `v` with an exponentially growing duration and returns the applied duration.
+    ///
+    /// The first insert of a key expires after `base`, and every re-insert while the previous
+    /// entry is still served by `TimedMap::get_stale` doubles it, up to `cap`. Configure
+    /// `TimedMap::stale_grace_period` to keep escalating when the key is re-inserted shortly
+    /// after its previous entry expired; otherwise the backoff restarts from `base`.
+    pub fn insert_with_backoff(&mut self, k: K, v: V, base: Duration, cap: Duration) -> Duration {
+        let attempts = self
+            .get_stale(&k)
+            .map_or(1, |(previous, _)| previous.attempts.saturating_add(1));
+
+        let duration = backoff_duration(base, cap, attempts);
+        self.insert_expirable(k, Backoff { value: v, attempts }, duration);
+
+        duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_duration() {
+        let base = Duration::from_secs(1);
+        let cap = Duration::from_secs(60);
+
+        assert_eq!(backoff_duration(base, cap, 1), Duration::from_secs(1));
+        assert_eq!(backoff_duration(base, cap, 2), Duration::from_secs(2));
+        assert_eq!(backoff_duration(base, cap, 6), Duration::from_secs(32));
+        assert_eq!(backoff_duration(base, cap, 7), cap);
+        assert_eq!(backoff_duration(base, cap, u32::MAX), cap);
+    }
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_insert_with_backoff_escalates_within_grace_period() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(&clock).stale_grace_period(Duration::from_secs(120));
+
+        let base = Duration::from_secs(10);
+        let cap = Duration::from_secs(30);
+
+        assert_eq!(map.insert_with_backoff(1, "failed", base, cap), base);
+
+        // Retried after the first window passed, and failed again.
+        clock.set(1011);
+        assert_eq!(
+            map.insert_with_backoff(1, "failed", base, cap),
+            Duration::from_secs(20)
+        );
+        assert_eq!(map.insert_with_backoff(1, "failed", base, cap), cap);
+        assert_eq!(map.get(&1).map(|v| v.attempts()), Some(3));
+
+        // Long after the grace period, backoff starts over.
+        clock.set(2000);
+        assert_eq!(map.insert_with_backoff(1, "failed", base, cap), base);
+    }
+}
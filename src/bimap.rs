@@ -0,0 +1,178 @@
+use super::*;
+use map::GenericKey;
+
+/// Bidirectional map between left values of type `L` and right values of type `R`, where each
+/// pair may optionally expire after a specified duration.
+///
+/// Both directions always stay consistent: a pair expires or is removed from both sides at
+/// once, and inserting a pair replaces any existing pair sharing either of its values.
+///
+/// # Example usage:
+/// ```rs
+/// let mut sessions: TimedBiMap<StdClock, SessionId, UserId> = TimedBiMap::new();
+///
+/// sessions.insert_expirable(session_id, user_id, Duration::from_secs(3600));
+/// assert_eq!(sessions.get_by_left(&session_id), Some(&user_id));
+/// assert_eq!(sessions.get_by_right(&user_id), Some(&session_id));
+/// ```
+pub struct TimedBiMap<C, L, R> {
+    forward: TimedMap<C, L, R>,
+    reverse: BTreeMap<R, L>,
+}
+
+#[cfg(feature = "std")]
+impl<C, L, R> Default for TimedBiMap<C, L, R> {
+    fn default() -> Self {
+        Self {
+            forward: TimedMap::default(),
+            reverse: BTreeMap::default(),
+        }
+    }
+}
+
+impl<C, L, R> TimedBiMap<C, L, R>
+where
+    C: Clock,
+    L: GenericKey,
+    R: GenericKey,
+{
+    /// Creates an empty map.
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty map.
+    ///
+    /// Uses the provided `clock` to handle expiration times.
+    #[cfg(not(feature = "std"))]
+    pub const fn new(clock: C) -> Self {
+        Self {
+            forward: TimedMap::new(clock),
+            reverse: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the right value associated with `l` if present and not expired.
+    pub fn get_by_left(&self, l: &L) -> Option<&R> {
+        self.forward.get(l)
+    }
+
+    /// Returns the left value associated with `r` if present and not expired.
+    pub fn get_by_right(&self, r: &R) -> Option<&L> {
+        let l = self.reverse.get(r)?;
+        self.forward.get(l).map(|_| l)
+    }
+
+    /// Returns the remaining `Duration` of the pair of `l` if present and not expired.
+    ///
+    /// Returns `None` if the pair does not exist or is constant.
+    pub fn get_remaining_duration(&self, l: &L) -> Option<Duration> {
+        self.forward.get_remaining_duration(l)
+    }
+
+    /// Inserts a pair with an expiration duration, and then drops the expired pairs.
+    ///
+    /// Existing pairs holding either `l` or `r` are removed first.
+    pub fn insert_expirable(&mut self, l: L, r: R, duration: Duration) {
+        self.remove_conflicts(&l, &r);
+        self.forward
+            .insert_expirable_unchecked(l.clone(), r.clone(), duration);
+        self.reverse.insert(r, l);
+        self.drop_expired_entries();
+    }
+
+    /// Inserts a pair that doesn't expire, and then drops the expired pairs.
+    ///
+    /// Existing pairs holding either `l` or `r` are removed first.
+    pub fn insert_constant(&mut self, l: L, r: R) {
+        self.remove_conflicts(&l, &r);
+        self.forward.insert_constant_unchecked(l.clone(), r.clone());
+        self.reverse.insert(r, l);
+        self.drop_expired_entries();
+    }
+
+    /// Removes the pair of `l` and returns its right value if it was not expired.
+    pub fn remove_by_left(&mut self, l: &L) -> Option<R> {
+        let live = self.forward.get(l).is_some();
+        let r = self.forward.remove_unchecked(l)?;
+        self.reverse.remove(&r);
+
+        live.then_some(r)
+    }
+
+    /// Removes the pair of `r` and returns its left value if it was not expired.
+    pub fn remove_by_right(&mut self, r: &R) -> Option<L> {
+        let l = self.reverse.remove(r)?;
+        self.forward.remove(&l).map(|_| l)
+    }
+
+    /// Clears expired pairs from both sides of the map.
+    pub fn drop_expired_entries(&mut self) {
+        let reverse = &mut self.reverse;
+        self.forward.drop_expired_entries_with(|_, r| {
+            reverse.remove(&r);
+        });
+    }
+
+    /// Removes the pairs that would break the bijection once `l` and `r` are paired.
+    fn remove_conflicts(&mut self, l: &L, r: &R) {
+        if let Some(old_r) = self.forward.remove_unchecked(l) {
+            self.reverse.remove(&old_r);
+        }
+
+        if let Some(old_l) = self.reverse.remove(r) {
+            self.forward.remove_unchecked(&old_l);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_bimap_sides_stay_consistent() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedBiMap::new(&clock);
+
+        map.insert_expirable(1, "alice", Duration::from_secs(10));
+        map.insert_constant(2, "bob");
+        assert_eq!(map.get_by_left(&1), Some(&"alice"));
+        assert_eq!(map.get_by_right(&"alice"), Some(&1));
+
+        // Re-pairing a right value drops its previous pair.
+        map.insert_constant(3, "bob");
+        assert_eq!(map.get_by_left(&2), None);
+        assert_eq!(map.get_by_right(&"bob"), Some(&3));
+
+        // Expired pairs disappear from both sides, even before they are dropped.
+        clock.advance(11);
+        assert_eq!(map.get_by_left(&1), None);
+        assert_eq!(map.get_by_right(&"alice"), None);
+
+        map.drop_expired_entries();
+        assert!(!map.reverse.contains_key(&"alice"));
+
+        assert_eq!(map.remove_by_right(&"bob"), Some(3));
+        assert_eq!(map.get_by_left(&3), None);
+        assert_eq!(map.remove_by_left(&3), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_bimap_replaces_left_pairs() {
+        let mut map: TimedBiMap<StdClock, u32, &str> = TimedBiMap::new();
+
+        map.insert_expirable(1, "alice", Duration::from_secs(60));
+        map.insert_expirable(1, "bob", Duration::from_secs(60));
+
+        assert_eq!(map.get_by_left(&1), Some(&"bob"));
+        assert_eq!(map.get_by_right(&"alice"), None);
+        assert_eq!(map.remove_by_left(&1), Some("bob"));
+        assert_eq!(map.get_by_right(&"bob"), None);
+    }
+}
@@ -1,6 +1,8 @@
-#[cfg(feature = "std")]
 use super::*;
 
+#[cfg(any(test, feature = "proptest"))]
+use core::cell::Cell;
+
 /// Provides elapsed time since the creation of the implementer, in seconds.
 ///
 /// This is designed to enable `TimedMap` to work in both `std` and `no_std` environments.
@@ -30,6 +32,22 @@ pub trait Clock {
     fn elapsed_seconds_since_creation(&self) -> u64;
 }
 
+/// Allows selecting the clock at runtime (e.g., `TimedMap<Box<dyn Clock>, K, V>`) without
+/// monomorphizing the map for each clock implementation.
+impl<C: Clock + ?Sized> Clock for Box<C> {
+    #[inline(always)]
+    fn elapsed_seconds_since_creation(&self) -> u64 {
+        (**self).elapsed_seconds_since_creation()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    #[inline(always)]
+    fn elapsed_seconds_since_creation(&self) -> u64 {
+        (**self).elapsed_seconds_since_creation()
+    }
+}
+
 /// A default `Clock` implementation when `std` is enabled.
 ///
 /// When `std` is enabled, this is automatically utilized in `TimedMap`
@@ -37,6 +55,11 @@ pub trait Clock {
 #[cfg(feature = "std")]
 pub struct StdClock {
     creation: Instant,
+    creation_system_time: SystemTime,
+    // Seconds reported instead of the elapsed time, so `testing::check_ops` can drive maps
+    // deterministically on `std`.
+    #[cfg(feature = "proptest")]
+    manual_seconds: Option<AtomicU64>,
 }
 
 #[cfg(feature = "std")]
@@ -44,13 +67,92 @@ impl StdClock {
     pub(crate) fn new() -> Self {
         Self {
             creation: Instant::now(),
+            creation_system_time: SystemTime::now(),
+            #[cfg(feature = "proptest")]
+            manual_seconds: None,
         }
     }
+
+    /// Creates a clock reading `seconds` which only moves through `StdClock::advance`.
+    #[cfg(feature = "proptest")]
+    pub(crate) fn manual(seconds: u64) -> Self {
+        Self {
+            manual_seconds: Some(AtomicU64::new(seconds)),
+            ..Self::new()
+        }
+    }
+
+    /// Moves a clock created with `StdClock::manual` forward by `seconds`.
+    #[cfg(feature = "proptest")]
+    pub(crate) fn advance(&self, seconds: u64) {
+        if let Some(manual_seconds) = &self.manual_seconds {
+            manual_seconds.fetch_add(seconds, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the time left until `seconds` since the creation of the clock, or zero if it
+    /// has already passed.
+    #[cfg(feature = "wasm")]
+    pub(crate) fn duration_until(&self, seconds: u64) -> Duration {
+        (self.creation + Duration::from_secs(seconds)).saturating_duration_since(Instant::now())
+    }
+
+    /// Converts `seconds` since the creation of the clock into a `SystemTime`.
+    pub(crate) fn system_time_at(&self, seconds: u64) -> SystemTime {
+        self.creation_system_time + Duration::from_secs(seconds)
+    }
+
+    /// Converts `time` into seconds since the creation of the clock, or returns `None` if it's
+    /// before the creation.
+    pub(crate) fn seconds_at(&self, time: SystemTime) -> Option<u64> {
+        time.duration_since(self.creation_system_time)
+            .ok()
+            .map(|elapsed| elapsed.as_secs())
+    }
 }
 
 #[cfg(feature = "std")]
 impl Clock for StdClock {
     fn elapsed_seconds_since_creation(&self) -> u64 {
+        #[cfg(feature = "proptest")]
+        if let Some(manual_seconds) = &self.manual_seconds {
+            return manual_seconds.load(Ordering::Relaxed);
+        }
+
         self.creation.elapsed().as_secs()
     }
 }
+
+/// `Clock` that only moves when told to, so expiration can be tested deterministically.
+#[cfg(any(test, feature = "proptest"))]
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: Cell<u64>,
+}
+
+#[cfg(any(test, feature = "proptest"))]
+impl ManualClock {
+    /// Creates a clock reading `seconds`.
+    pub const fn new(seconds: u64) -> Self {
+        Self {
+            now: Cell::new(seconds),
+        }
+    }
+
+    /// Moves the clock forward by `seconds`.
+    pub fn advance(&self, seconds: u64) {
+        self.now.set(self.now.get() + seconds);
+    }
+
+    /// Sets the clock to read `seconds`.
+    pub fn set(&self, seconds: u64) {
+        self.now.set(seconds);
+    }
+}
+
+#[cfg(any(test, feature = "proptest"))]
+impl Clock for ManualClock {
+    fn elapsed_seconds_since_creation(&self) -> u64 {
+        self.now.get()
+    }
+}
@@ -0,0 +1,102 @@
+use super::*;
+use map::GenericKey;
+
+/// Remembers keys for a window of time to drop duplicates, e.g., redelivered messages.
+///
+/// # Example usage:
+/// ```rs
+/// let mut dedup: DedupWindow<StdClock, u64> = DedupWindow::new();
+///
+/// if dedup.is_first_seen(message_id, Duration::from_secs(300)) {
+///     handle(message);
+/// }
+/// ```
+pub struct DedupWindow<C, K> {
+    seen: TimedSet<C, K>,
+}
+
+#[cfg(feature = "std")]
+impl<C, K> Default for DedupWindow<C, K> {
+    fn default() -> Self {
+        Self {
+            seen: TimedSet::default(),
+        }
+    }
+}
+
+impl<C, K> DedupWindow<C, K>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Creates an empty window.
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty window based on the chosen map implementation specified by `MapKind`.
+    #[cfg(feature = "std")]
+    pub fn new_with_map_kind(map_kind: MapKind) -> Self {
+        Self {
+            seen: TimedSet::new_with_map_kind(map_kind),
+        }
+    }
+
+    /// Creates an empty window.
+    ///
+    /// Uses the provided `clock` to handle expiration times.
+    #[cfg(not(feature = "std"))]
+    pub const fn new(clock: C) -> Self {
+        Self {
+            seen: TimedSet::new(clock),
+        }
+    }
+
+    /// Returns `true` and remembers `k` for `window` if it wasn't seen within its previous
+    /// window, otherwise returns `false`.
+    ///
+    /// Seeing a key again doesn't extend its window.
+    pub fn is_first_seen(&mut self, k: K, window: Duration) -> bool {
+        if self.seen.contains(&k) {
+            return false;
+        }
+
+        self.seen.insert_expirable(k, window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_is_first_seen_within_window() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut dedup = DedupWindow::new(&clock);
+        let window = Duration::from_secs(10);
+
+        assert!(dedup.is_first_seen(1, window));
+        assert!(dedup.is_first_seen(2, window));
+
+        clock.set(1005);
+        assert!(!dedup.is_first_seen(1, window));
+
+        // Seeing a key again doesn't extend its window.
+        clock.set(1011);
+        assert!(dedup.is_first_seen(1, window));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_is_first_seen() {
+        let mut dedup: DedupWindow<StdClock, &str> = DedupWindow::new();
+
+        assert!(dedup.is_first_seen("a", Duration::from_secs(60)));
+        assert!(!dedup.is_first_seen("a", Duration::from_secs(60)));
+        assert!(dedup.is_first_seen("b", Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,141 @@
+use super::*;
+use map::GenericKey;
+
+/// Differences between two maps, as returned by `TimedMap::diff`.
+///
+/// Keys are sorted within each list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MapDiff<K> {
+    /// Keys only present in the other map.
+    pub added: Vec<K>,
+    /// Keys only present in this map.
+    pub removed: Vec<K>,
+    /// Keys present in both maps with different values.
+    pub changed: Vec<K>,
+    /// Keys present in both maps with equal values but different remaining durations.
+    pub ttl_changed: Vec<K>,
+}
+
+impl<K> Default for MapDiff<K> {
+    fn default() -> Self {
+        Self {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+            ttl_changed: Vec::new(),
+        }
+    }
+}
+
+impl<K> MapDiff<K> {
+    /// Returns `true` if the maps hold the same entries with the same remaining durations.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.ttl_changed.is_empty()
+    }
+}
+
+/// Reconciliation helpers for comparing a map against a source of truth.
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+    V: PartialEq,
+{
+    /// Returns the keys that were added, removed or changed in `other` compared to this map,
+    /// considering only non-expired entries.
+    ///
+    /// Remaining durations are compared in seconds, each relative to its own map's clock.
+    /// Constant entries only match other constant entries.
+    pub fn diff<C2>(&self, other: &TimedMap<C2, K, V>) -> MapDiff<K>
+    where
+        C2: Clock,
+    {
+        let mut diff = MapDiff::default();
+
+        for (k, v, duration) in self.iter_with_remaining_durations() {
+            match other.get(k) {
+                None => diff.removed.push(k.clone()),
+                Some(other_v) if other_v != v => diff.changed.push(k.clone()),
+                Some(_) if other.get_remaining_duration(k) != duration => {
+                    diff.ttl_changed.push(k.clone())
+                }
+                Some(_) => {}
+            }
+        }
+
+        for (k, _, _) in other.iter_with_remaining_durations() {
+            if self.get(k).is_none() {
+                diff.added.push(k.clone());
+            }
+        }
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff.ttl_changed.sort();
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_diff() {
+        use crate::clock::ManualClock;
+
+        let local_clock = ManualClock::new(1000);
+        let truth_clock = ManualClock::new(0);
+
+        let mut local = TimedMap::new(&local_clock);
+        local.insert_constant_unchecked(1, "same");
+        local.insert_expirable_unchecked(2, "stale", Duration::from_secs(60));
+        local.insert_expirable_unchecked(3, "same", Duration::from_secs(60));
+        local.insert_constant_unchecked(4, "removed");
+        local.insert_expirable_unchecked(6, "expired", Duration::from_secs(1));
+
+        let mut truth = TimedMap::new(&truth_clock);
+        truth.insert_constant_unchecked(1, "same");
+        truth.insert_expirable_unchecked(2, "fresh", Duration::from_secs(60));
+        truth.insert_expirable_unchecked(3, "same", Duration::from_secs(30));
+        truth.insert_constant_unchecked(5, "added");
+
+        local_clock.set(1002);
+        truth_clock.set(2);
+
+        assert_eq!(
+            local.diff(&truth),
+            MapDiff {
+                added: Vec::from([5]),
+                removed: Vec::from([4]),
+                changed: Vec::from([2]),
+                ttl_changed: Vec::from([3]),
+            }
+        );
+        assert!(local.diff(&local).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_diff() {
+        let mut local: TimedMap<StdClock, u32, u32> = TimedMap::new();
+        let mut truth: TimedMap<StdClock, u32, u32> = TimedMap::new();
+
+        local.insert_constant(1, 1);
+        local.insert_constant(2, 2);
+        truth.insert_constant(2, 20);
+        truth.insert_constant(3, 3);
+
+        let diff = local.diff(&truth);
+        assert_eq!(diff.added, [3]);
+        assert_eq!(diff.removed, [1]);
+        assert_eq!(diff.changed, [2]);
+        assert!(diff.ttl_changed.is_empty());
+    }
+}
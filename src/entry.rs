@@ -1,15 +1,17 @@
 use super::*;
 
-#[derive(Clone, Copy)]
+/// Enum representing the status of an entry in the map, as returned by `TimedMap::status`.
+///
+/// - `Constant`: Entry is not expirable and remains accessible until removed.
+/// - `ExpiresAtSeconds`: Entry will expire once reached to the given time, in seconds of the
+///   map's `Clock`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum EntryStatus {
     Constant,
     ExpiresAtSeconds(u64),
 }
 
-/// Enum representing the status of an entry in the map.
-///
-/// - `Constant`: Entry is not expirable and remains accessible until removed.
-/// - `ExpiresAtSeconds`: Entry will expire once reached to the given time.
 impl EntryStatus {
     /// Creates expirable or constant entry based on `expires_at`.
     ///
@@ -24,11 +26,52 @@ impl EntryStatus {
     }
 }
 
+/// Values that carry their own lifetime (e.g., DNS records, JWTs, leases), used by
+/// `TimedMap::insert_auto` to derive the expiration from the value itself.
+///
+/// # Example usage:
+/// ```rs
+/// struct Lease {
+///     ttl_seconds: u64,
+/// }
+///
+/// impl ExpirableValue for Lease {
+///     fn ttl(&self) -> Option<Duration> {
+///         Some(Duration::from_secs(self.ttl_seconds))
+///     }
+/// }
+///
+/// map.insert_auto(1, Lease { ttl_seconds: 60 });
+/// ```
+pub trait ExpirableValue {
+    /// Returns how long the value stays valid, or `None` if it never expires.
+    fn ttl(&self) -> Option<Duration>;
+}
+
+/// Freshness of an entry, as returned by `TimedMap::get_with_freshness`.
+///
+/// - `Fresh`: Entry is not expired. Holds the remaining duration, or `None` if the entry is constant.
+/// - `Stale`: Entry is expired but not dropped from the map yet. Holds how long ago it expired.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Freshness {
+    Fresh(Option<Duration>),
+    Stale(Duration),
+}
+
 /// The entry holds a value `V` and an associated `EntryStatus` which determines
 /// whether the entry is constant or expirable.
 pub(crate) struct ExpirableEntry<V> {
     value: V,
     status: EntryStatus,
+
+    #[cfg(feature = "versions")]
+    version: u64,
+
+    #[cfg(feature = "metadata")]
+    created_at: u64,
+    #[cfg(feature = "metadata")]
+    last_accessed: AtomicU64,
 }
 
 impl<V> ExpirableEntry<V> {
@@ -40,19 +83,111 @@ impl<V> ExpirableEntry<V> {
         Self {
             value: v,
             status: EntryStatus::new(expires_at),
+
+            #[cfg(feature = "versions")]
+            version: 1,
+
+            #[cfg(feature = "metadata")]
+            created_at: 0,
+            #[cfg(feature = "metadata")]
+            last_accessed: AtomicU64::new(0),
+        }
+    }
+
+    /// Replaces the value with the one `f` computes from it, keeping the rest of the entry.
+    #[inline(always)]
+    pub(crate) fn map_value<F>(self, f: F) -> Self
+    where
+        F: FnOnce(V) -> V,
+    {
+        Self {
+            value: f(self.value),
+            ..self
         }
     }
 
+    /// Sets both the creation and the last access time to `now_seconds`.
+    #[cfg(feature = "metadata")]
+    #[inline(always)]
+    pub(crate) fn with_created_at(mut self, now_seconds: u64) -> Self {
+        self.created_at = now_seconds;
+        *self.last_accessed.get_mut() = now_seconds;
+        self
+    }
+
+    #[cfg(feature = "metadata")]
+    #[inline(always)]
+    pub(crate) fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    #[cfg(feature = "metadata")]
+    #[inline(always)]
+    pub(crate) fn last_accessed(&self) -> u64 {
+        self.last_accessed.load(Ordering::Relaxed)
+    }
+
+    /// Records an access at `now_seconds`.
+    #[inline(always)]
+    pub(crate) fn touch(&self, now_seconds: u64) {
+        #[cfg(feature = "metadata")]
+        self.last_accessed.store(now_seconds, Ordering::Relaxed);
+
+        #[cfg(not(feature = "metadata"))]
+        let _ = now_seconds;
+    }
+
+    #[cfg(feature = "versions")]
+    #[inline(always)]
+    pub(crate) fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Bumps the version after the value or the lifetime of the entry is updated in place.
+    #[inline(always)]
+    pub(crate) fn bump_version(&mut self) {
+        #[cfg(feature = "versions")]
+        {
+            self.version = self.version.saturating_add(1);
+        }
+    }
+
+    /// Continues the version of `old`, which this entry replaces.
+    #[cfg(feature = "versions")]
+    #[inline(always)]
+    pub(crate) fn replacing(mut self, old: &Self) -> Self {
+        self.version = old.version.saturating_add(1);
+        self
+    }
+
+    /// Continues the version of `old`, which this entry replaces.
+    #[cfg(not(feature = "versions"))]
+    #[inline(always)]
+    pub(crate) fn replacing(self, _old: &Self) -> Self {
+        self
+    }
+
     #[inline(always)]
     pub(crate) fn status(&self) -> &EntryStatus {
         &self.status
     }
 
+    /// Makes the entry expirable at `expires_at` or constant if it's `None`.
+    #[inline(always)]
+    pub(crate) fn set_expires_at(&mut self, expires_at: Option<u64>) {
+        self.status = EntryStatus::new(expires_at);
+    }
+
     #[inline(always)]
     pub(crate) fn value(&self) -> &V {
         &self.value
     }
 
+    #[inline(always)]
+    pub(crate) fn value_mut(&mut self) -> &mut V {
+        &mut self.value
+    }
+
     /// Returns owned `V` and consumes `self`.
     #[inline(always)]
     pub(crate) fn owned_value(self) -> V {
@@ -68,6 +203,22 @@ impl<V> ExpirableEntry<V> {
         }
     }
 
+    /// Returns the `Freshness` of the entry based on the current time.
+    #[inline(always)]
+    pub(crate) fn freshness(&self, now_seconds: u64) -> Freshness {
+        match self.status {
+            EntryStatus::Constant => Freshness::Fresh(None),
+            EntryStatus::ExpiresAtSeconds(expires_at_seconds)
+                if now_seconds > expires_at_seconds =>
+            {
+                Freshness::Stale(Duration::from_secs(now_seconds - expires_at_seconds))
+            }
+            EntryStatus::ExpiresAtSeconds(expires_at_seconds) => {
+                Freshness::Fresh(Some(Duration::from_secs(expires_at_seconds - now_seconds)))
+            }
+        }
+    }
+
     /// Returns the remaining `Duration` before entry expires if it's expirable,
     /// or `None` if it's constant.
     #[inline(always)]
@@ -84,20 +235,11 @@ impl<V> ExpirableEntry<V> {
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    struct MockClock {
-        current_time: u64,
-    }
-
-    impl Clock for MockClock {
-        fn elapsed_seconds_since_creation(&self) -> u64 {
-            self.current_time
-        }
-    }
+    use crate::clock::ManualClock;
 
     #[test]
     fn test_entry_status() {
-        let clock = MockClock { current_time: 1000 };
+        let clock = ManualClock::new(1000);
 
         let entry_status = EntryStatus::new(None);
         assert!(matches!(entry_status, EntryStatus::Constant));
@@ -111,7 +253,7 @@ mod tests {
 
     #[test]
     fn test_constant_entry() {
-        let clock = MockClock { current_time: 1000 };
+        let clock = ManualClock::new(1000);
         let entry = ExpirableEntry::new("constant value", None);
 
         assert_eq!(entry.value(), &"constant value");
@@ -121,7 +263,7 @@ mod tests {
 
     #[test]
     fn test_expirable_entry() {
-        let clock = MockClock { current_time: 1000 };
+        let clock = ManualClock::new(1000);
         let duration = Duration::from_secs(60);
         let entry = ExpirableEntry::new(
             "expirable value",
@@ -138,7 +280,7 @@ mod tests {
 
     #[test]
     fn test_expirable_entry_is_expired() {
-        let clock = MockClock { current_time: 1000 };
+        let clock = ManualClock::new(1000);
         let duration = Duration::from_secs(60);
         let entry = ExpirableEntry::new(
             "expirable value",
@@ -149,13 +291,13 @@ mod tests {
         assert!(!entry.is_expired(clock.elapsed_seconds_since_creation()));
 
         // Simulate time passing
-        let clock = MockClock { current_time: 1070 };
+        clock.advance(70);
         assert!(entry.is_expired(clock.elapsed_seconds_since_creation()));
     }
 
     #[test]
     fn test_remaining_duration_for_expires_at_seconds() {
-        let clock = MockClock { current_time: 1000 };
+        let clock = ManualClock::new(1000);
         let duration = Duration::from_secs(60);
         let entry = ExpirableEntry::new(
             "expirable value",
@@ -169,7 +311,7 @@ mod tests {
         );
 
         // Simulate time passing
-        let clock = MockClock { current_time: 1050 };
+        clock.advance(50);
         assert!(!entry.is_expired(clock.elapsed_seconds_since_creation()));
         assert_eq!(
             entry.remaining_duration(clock.elapsed_seconds_since_creation()),
@@ -177,7 +319,7 @@ mod tests {
         );
 
         // Time passed beyond expiration
-        let clock = MockClock { current_time: 1070 };
+        clock.set(1070);
         assert!(entry.is_expired(clock.elapsed_seconds_since_creation()));
         assert_eq!(
             entry.remaining_duration(clock.elapsed_seconds_since_creation()),
@@ -185,9 +327,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_freshness() {
+        let entry = ExpirableEntry::new("expirable value", Some(1060));
+
+        assert_eq!(
+            entry.freshness(1000),
+            Freshness::Fresh(Some(Duration::from_secs(60)))
+        );
+        assert_eq!(
+            entry.freshness(1060),
+            Freshness::Fresh(Some(Duration::ZERO))
+        );
+        assert_eq!(
+            entry.freshness(1070),
+            Freshness::Stale(Duration::from_secs(10))
+        );
+
+        let entry = ExpirableEntry::new("constant value", None);
+        assert_eq!(entry.freshness(1000), Freshness::Fresh(None));
+    }
+
     #[test]
     fn test_remaining_duration_for_constant() {
-        let clock = MockClock { current_time: 1000 };
+        let clock = ManualClock::new(1000);
         let entry = ExpirableEntry::new("constant value", None);
 
         assert_eq!(
@@ -0,0 +1,276 @@
+use super::*;
+use map::{GenericKey, GenericQuery};
+
+/// View into a single entry of `TimedMap`, looked up by a borrowed form `Q` of the key, as
+/// returned by `TimedMap::entry_ref`.
+///
+/// The owned key is only built from `Q` when a vacant entry gets inserted, so lookups that
+/// mostly hit existing entries don't pay for constructing keys.
+///
+/// Expired entries are treated as vacant.
+///
+/// # Example usage:
+/// ```rs
+/// let mut counters: TimedMap<StdClock, String, u64> = TimedMap::new();
+///
+/// *counters
+///     .entry_ref("requests")
+///     .or_insert_expirable(0, Duration::from_secs(60)) += 1;
+/// ```
+pub enum EntryRef<'a, 'q, C, K, V, Q>
+where
+    Q: ?Sized,
+{
+    Occupied(OccupiedEntryRef<'a, 'q, C, K, V, Q>),
+    Vacant(VacantEntryRef<'a, 'q, C, K, V, Q>),
+}
+
+/// Non-expired entry of `EntryRef`.
+pub struct OccupiedEntryRef<'a, 'q, C, K, V, Q>
+where
+    Q: ?Sized,
+{
+    map: &'a mut TimedMap<C, K, V>,
+    key: &'q Q,
+}
+
+/// Missing or expired entry of `EntryRef`.
+pub struct VacantEntryRef<'a, 'q, C, K, V, Q>
+where
+    Q: ?Sized,
+{
+    map: &'a mut TimedMap<C, K, V>,
+    key: &'q Q,
+}
+
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Returns the entry of `k` for in-place manipulation, without building an owned key
+    /// unless a vacant entry gets inserted.
+    pub fn entry_ref<'a, 'q, Q>(&'a mut self, k: &'q Q) -> EntryRef<'a, 'q, C, K, V, Q>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ToOwned<Owned = K> + ?Sized,
+    {
+        if self.contains_key(k) {
+            EntryRef::Occupied(OccupiedEntryRef { map: self, key: k })
+        } else {
+            EntryRef::Vacant(VacantEntryRef { map: self, key: k })
+        }
+    }
+}
+
+impl<'a, C, K, V, Q> EntryRef<'a, '_, C, K, V, Q>
+where
+    C: Clock,
+    K: GenericKey + Borrow<Q>,
+    Q: GenericQuery + ToOwned<Owned = K> + ?Sized,
+{
+    /// Returns the borrowed key of the entry.
+    pub fn key(&self) -> &Q {
+        match self {
+            Self::Occupied(entry) => entry.key(),
+            Self::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Returns the value of the entry, inserting `v` with an expiration duration first if
+    /// the entry is vacant.
+    pub fn or_insert_expirable(self, v: V, duration: Duration) -> &'a mut V {
+        self.or_insert_with_expirable(|| v, duration)
+    }
+
+    /// Returns the value of the entry, inserting `v` that doesn't expire first if the entry
+    /// is vacant.
+    pub fn or_insert_constant(self, v: V) -> &'a mut V {
+        self.or_insert_with_constant(|| v)
+    }
+
+    /// Same as `EntryRef::or_insert_expirable`, computing the value with `f` only if the
+    /// entry is vacant.
+    pub fn or_insert_with_expirable<F>(self, f: F, duration: Duration) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert_expirable(f(), duration),
+        }
+    }
+
+    /// Same as `EntryRef::or_insert_constant`, computing the value with `f` only if the
+    /// entry is vacant.
+    pub fn or_insert_with_constant<F>(self, f: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert_constant(f()),
+        }
+    }
+
+    /// Calls `f` with the value if the entry is occupied, and then notifies the registered
+    /// observers about the update.
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        if let Self::Occupied(entry) = &mut self {
+            f(entry
+                .map
+                .get_unchecked_mut(entry.key)
+                .expect("occupied entry must exist"));
+            entry.map.notify_updated(entry.key);
+        }
+
+        self
+    }
+}
+
+impl<'a, C, K, V, Q> OccupiedEntryRef<'a, '_, C, K, V, Q>
+where
+    C: Clock,
+    K: GenericKey + Borrow<Q>,
+    Q: GenericQuery + ToOwned<Owned = K> + ?Sized,
+{
+    /// Returns the borrowed key of the entry.
+    pub fn key(&self) -> &Q {
+        self.key
+    }
+
+    /// Returns the value of the entry.
+    pub fn get(&self) -> &V {
+        self.map
+            .get_unchecked(self.key)
+            .expect("occupied entry must exist")
+    }
+
+    /// Returns mutable access to the value of the entry.
+    ///
+    /// The version of the entry is bumped and the entry is recorded as updated, but the
+    /// registered hooks don't see changes made through the returned reference, unlike with
+    /// `and_modify`.
+    pub fn get_mut(&mut self) -> &mut V {
+        self.map.mark_updated(self.key);
+        self.map
+            .get_unchecked_mut(self.key)
+            .expect("occupied entry must exist")
+    }
+
+    /// Converts the entry into mutable access to its value, bound to the map's lifetime.
+    ///
+    /// Marks the entry as updated like `get_mut`.
+    pub fn into_mut(self) -> &'a mut V {
+        self.map.mark_updated(self.key);
+        self.map
+            .get_unchecked_mut(self.key)
+            .expect("occupied entry must exist")
+    }
+
+    /// Returns the remaining `Duration` of the entry, or `None` if it is constant.
+    pub fn remaining_duration(&self) -> Option<Duration> {
+        self.map.get_remaining_duration(self.key)
+    }
+
+    /// Removes the entry from the map and returns its value.
+    pub fn remove(self) -> V {
+        self.map
+            .remove_unchecked(self.key)
+            .expect("occupied entry must exist")
+    }
+}
+
+impl<'a, C, K, V, Q> VacantEntryRef<'a, '_, C, K, V, Q>
+where
+    C: Clock,
+    K: GenericKey + Borrow<Q>,
+    Q: GenericQuery + ToOwned<Owned = K> + ?Sized,
+{
+    /// Returns the borrowed key of the entry.
+    pub fn key(&self) -> &Q {
+        self.key
+    }
+
+    /// Inserts `v` with an expiration duration under the owned form of the key, and returns
+    /// mutable access to it.
+    pub fn insert_expirable(self, v: V, duration: Duration) -> &'a mut V {
+        self.map.insert_expirable(self.key.to_owned(), v, duration);
+        self.into_inserted()
+    }
+
+    /// Inserts `v` that doesn't expire under the owned form of the key, and returns mutable
+    /// access to it.
+    pub fn insert_constant(self, v: V) -> &'a mut V {
+        self.map.insert_constant(self.key.to_owned(), v);
+        self.into_inserted()
+    }
+
+    /// Converts the entry into mutable access to the value that was just inserted.
+    fn into_inserted(self) -> &'a mut V {
+        self.map
+            .get_unchecked_mut(self.key)
+            .expect("entry must exist after insertion")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_entry_ref() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(&clock);
+
+        *map.entry_ref(&1)
+            .or_insert_expirable(0, Duration::from_secs(10)) += 1;
+        *map.entry_ref(&1)
+            .or_insert_expirable(0, Duration::from_secs(10)) += 1;
+        assert_eq!(map.get(&1), Some(&2));
+
+        // Occupied entries keep their expiration.
+        match map.entry_ref(&1) {
+            EntryRef::Occupied(entry) => {
+                assert_eq!(entry.remaining_duration(), Some(Duration::from_secs(10)));
+            }
+            EntryRef::Vacant(_) => panic!("entry must be occupied"),
+        }
+
+        // Expired entries are vacant.
+        clock.set(1011);
+        map.entry_ref(&1)
+            .and_modify(|v| *v += 10)
+            .or_insert_constant(5);
+        assert_eq!(map.get(&1), Some(&5));
+        assert_eq!(map.get_remaining_duration(&1), None);
+
+        match map.entry_ref(&1) {
+            EntryRef::Occupied(entry) => assert_eq!(entry.remove(), 5),
+            EntryRef::Vacant(_) => panic!("entry must be occupied"),
+        }
+        assert_eq!(map.get(&1), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_entry_ref_with_borrowed_keys() {
+        use std::string::String;
+
+        let mut map: TimedMap<StdClock, String, u32> = TimedMap::new();
+
+        map.entry_ref("counter").or_insert_constant(0);
+        map.entry_ref("counter")
+            .and_modify(|v| *v += 1)
+            .or_insert_constant(0);
+        assert_eq!(map.get("counter"), Some(&1));
+        assert_eq!(map.entry_ref("counter").key(), "counter");
+        assert!(matches!(map.entry_ref("missing"), EntryRef::Vacant(_)));
+    }
+}
@@ -0,0 +1,27 @@
+/// Errors returned by the `try_*` variants of `TimedMap` operations, telling apart why an
+/// entry could not be used.
+///
+/// The map grows without a fixed bound, so there is no capacity error; allocation failures
+/// abort like they do for the standard collections.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TimedMapError {
+    /// There is no entry for the key.
+    EntryNotFound,
+    /// The entry of the key is expired, even if it's not dropped from the map yet.
+    EntryExpired,
+    /// The duration is too long to be represented as an expiration time from now.
+    InvalidDuration,
+}
+
+impl core::fmt::Display for TimedMapError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::EntryNotFound => write!(f, "entry not found"),
+            Self::EntryExpired => write!(f, "entry expired"),
+            Self::InvalidDuration => write!(f, "invalid duration"),
+        }
+    }
+}
+
+impl core::error::Error for TimedMapError {}
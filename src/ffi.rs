@@ -0,0 +1,166 @@
+//! C-compatible API over `TimedMap<StdClock, Vec<u8>, Vec<u8>>` for C and C++ services.
+//!
+//! Maps are exposed as opaque `TimedMapHandle` pointers and keys/values as byte slices.
+//! Headers can be generated with `cbindgen`, and the library built with
+//! `cargo rustc --release --features ffi --crate-type staticlib` (or `cdylib`).
+
+use super::*;
+
+use core::ptr;
+use core::slice;
+
+/// Opaque handle of a map created by `timed_map_new`.
+pub struct TimedMapHandle {
+    inner: TimedMap<StdClock, Vec<u8>, Vec<u8>>,
+}
+
+/// Copies `len` bytes starting from `ptr` into an owned buffer.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads of `len` bytes, or `len` must be 0.
+#[inline(always)]
+unsafe fn to_bytes(ptr: *const u8, len: usize) -> Vec<u8> {
+    if len == 0 {
+        return Vec::new();
+    }
+
+    slice::from_raw_parts(ptr, len).to_vec()
+}
+
+/// Creates an empty map. The returned handle must be released with `timed_map_free`.
+#[no_mangle]
+pub extern "C" fn timed_map_new() -> *mut TimedMapHandle {
+    Box::into_raw(Box::new(TimedMapHandle {
+        inner: TimedMap::new(),
+    }))
+}
+
+/// Releases a map created by `timed_map_new`. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `map` must be null or a handle returned by `timed_map_new` that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn timed_map_free(map: *mut TimedMapHandle) {
+    if !map.is_null() {
+        drop(Box::from_raw(map));
+    }
+}
+
+/// Inserts a key-value pair which expires after `ttl_seconds`, or never if `ttl_seconds` is 0.
+///
+/// Returns `true` if an existing value was replaced.
+///
+/// # Safety
+///
+/// `map` must be a live handle returned by `timed_map_new`, and `key`/`value` must be valid
+/// for reads of `key_len`/`value_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn timed_map_insert(
+    map: *mut TimedMapHandle,
+    key: *const u8,
+    key_len: usize,
+    value: *const u8,
+    value_len: usize,
+    ttl_seconds: u64,
+) -> bool {
+    let map = &mut (*map).inner;
+    let k = to_bytes(key, key_len);
+    let v = to_bytes(value, value_len);
+
+    let old = match ttl_seconds {
+        0 => map.insert_constant(k, v),
+        ttl_seconds => map.insert_expirable(k, v, Duration::from_secs(ttl_seconds)),
+    };
+
+    old.is_some()
+}
+
+/// Looks up a non-expired value and writes its length into `value_len`.
+///
+/// Returns null if the key is absent or expired. The returned pointer is owned by the map and
+/// stays valid only until the next mutation of the map.
+///
+/// # Safety
+///
+/// `map` must be a live handle returned by `timed_map_new`, `key` must be valid for reads of
+/// `key_len` bytes and `value_len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn timed_map_get(
+    map: *const TimedMapHandle,
+    key: *const u8,
+    key_len: usize,
+    value_len: *mut usize,
+) -> *const u8 {
+    match (*map).inner.get(&to_bytes(key, key_len)) {
+        Some(v) => {
+            *value_len = v.len();
+            v.as_ptr()
+        }
+        None => {
+            *value_len = 0;
+            ptr::null()
+        }
+    }
+}
+
+/// Removes a key-value pair. Returns `true` if a non-expired value was removed.
+///
+/// # Safety
+///
+/// `map` must be a live handle returned by `timed_map_new` and `key` must be valid for reads
+/// of `key_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn timed_map_remove(
+    map: *mut TimedMapHandle,
+    key: *const u8,
+    key_len: usize,
+) -> bool {
+    (*map).inner.remove(&to_bytes(key, key_len)).is_some()
+}
+
+/// Clears expired entries from the map.
+///
+/// # Safety
+///
+/// `map` must be a live handle returned by `timed_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn timed_map_drop_expired(map: *mut TimedMapHandle) {
+    (*map).inner.drop_expired_entries();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffi_roundtrip() {
+        let key = b"key";
+        let value = b"value";
+
+        unsafe {
+            let map = timed_map_new();
+
+            assert!(!timed_map_insert(
+                map,
+                key.as_ptr(),
+                key.len(),
+                value.as_ptr(),
+                value.len(),
+                60
+            ));
+
+            let mut len = 0;
+            let ptr = timed_map_get(map, key.as_ptr(), key.len(), &mut len);
+            assert_eq!(slice::from_raw_parts(ptr, len), value);
+
+            assert!(timed_map_remove(map, key.as_ptr(), key.len()));
+            assert!(timed_map_get(map, key.as_ptr(), key.len(), &mut len).is_null());
+            assert_eq!(len, 0);
+
+            timed_map_drop_expired(map);
+            timed_map_free(map);
+        }
+    }
+}
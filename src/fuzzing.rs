@@ -0,0 +1,124 @@
+use super::*;
+use map::GenericKey;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// Longest lifetime generated for expirable entries, in seconds.
+const MAX_SEED_TTL_SECONDS: u32 = 3600;
+
+/// Highest expiration tick cap generated.
+const MAX_SEED_TICK_CAP: u16 = 64;
+
+/// Description of a map state, generated by `Arbitrary` and turned into a map by
+/// `TimedMap::from_seed`, so fuzz targets can start from realistic maps.
+///
+/// # Example usage:
+/// ```rs
+/// fuzz_target!(|seed: MapSeed<u8, u32>| {
+///     let mut map: TimedMap<StdClock, u8, u32> = TimedMap::from_seed(seed);
+///     map.drop_expired_entries();
+/// });
+/// ```
+#[derive(Clone, Debug)]
+pub struct MapSeed<K, V> {
+    /// Entries with their lifetimes in seconds, `None` for constant entries.
+    ///
+    /// Generated lifetimes are at most an hour.
+    pub entries: Vec<(K, V, Option<u32>)>,
+    /// Inner map implementation.
+    pub map_kind: MapKind,
+    /// Value for `TimedMap::expiration_tick_cap`.
+    pub expiration_tick_cap: u16,
+}
+
+impl<'a, K, V> Arbitrary<'a> for MapSeed<K, V>
+where
+    K: Arbitrary<'a>,
+    V: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let map_kind = *u.choose(&[
+            MapKind::BTreeMap,
+            MapKind::HashMap,
+            MapKind::VecMap,
+            #[cfg(feature = "rustc-hash")]
+            MapKind::FxHashMap,
+            #[cfg(feature = "ahash")]
+            MapKind::AHashMap,
+        ])?;
+        let expiration_tick_cap = u.int_in_range(1..=MAX_SEED_TICK_CAP)?;
+
+        let entries = u
+            .arbitrary_iter::<(K, V, Option<u32>)>()?
+            .map(|entry| {
+                let (k, v, ttl) = entry?;
+                Ok((k, v, ttl.map(|ttl| ttl % (MAX_SEED_TTL_SECONDS + 1))))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            entries,
+            map_kind,
+            expiration_tick_cap,
+        })
+    }
+}
+
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Creates a map holding the entries described by `seed`, with lifetimes counted from now.
+    ///
+    /// Later entries replace earlier ones with the same key.
+    pub fn from_seed(seed: MapSeed<K, V>) -> Self {
+        let mut map =
+            Self::new_with_map_kind(seed.map_kind).expiration_tick_cap(seed.expiration_tick_cap);
+
+        for (k, v, ttl) in seed.entries {
+            match ttl {
+                Some(ttl) => map.insert_expirable_unchecked(k, v, Duration::from_secs(ttl.into())),
+                None => map.insert_constant_unchecked(k, v),
+            };
+        }
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn std_from_seed() {
+        let mut state: u64 = 42;
+        let bytes: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+
+        let mut u = Unstructured::new(&bytes);
+        while !u.is_empty() {
+            let seed: MapSeed<u8, u16> = u.arbitrary().unwrap();
+            let map: TimedMap<StdClock, u8, u16> = TimedMap::from_seed(seed.clone());
+            assert!(seed.expiration_tick_cap >= 1);
+
+            // Only the last entry of each key survives.
+            for (i, (k, v, ttl)) in seed.entries.iter().enumerate() {
+                if seed.entries[i + 1..].iter().any(|(other, _, _)| other == k) {
+                    continue;
+                }
+
+                assert_eq!(map.get(k), Some(v));
+                assert!(ttl.is_none_or(|ttl| ttl <= MAX_SEED_TTL_SECONDS));
+                assert_eq!(map.get_remaining_duration(k).is_some(), ttl.is_some());
+            }
+        }
+    }
+}
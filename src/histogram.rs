@@ -0,0 +1,119 @@
+use super::*;
+
+/// Number of non-expired expirable entries per remaining lifetime bucket, enabled with
+/// `TimedMap::ttl_histogram_buckets`.
+///
+/// Counts are kept relative to the time of the last `TtlHistogram::advance`. Entries only
+/// move towards shorter buckets as time passes, so advancing only visits the deadlines that
+/// crossed a bucket bound since then.
+pub(crate) struct TtlHistogram {
+    // Inclusive upper bounds of the buckets in seconds, in ascending order.
+    bounds: Vec<u64>,
+    // One more bucket than `bounds` for the entries outliving the last bound.
+    counts: Vec<usize>,
+    at: u64,
+}
+
+impl TtlHistogram {
+    /// Creates a histogram with the given bucket bounds, counting the deadlines of `expiries`
+    /// at `now`.
+    pub(crate) fn new<K>(
+        bounds: &[Duration],
+        now: u64,
+        expiries: &BTreeMap<u64, BTreeSet<K>>,
+    ) -> Self {
+        let mut bounds: Vec<u64> = bounds.iter().map(Duration::as_secs).collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        let mut histogram = Self {
+            counts: core::iter::repeat_n(0, bounds.len() + 1).collect(),
+            bounds,
+            at: now,
+        };
+        histogram.rebuild(now, expiries);
+
+        histogram
+    }
+
+    /// Returns a histogram with the same buckets counting no entries at `now`.
+    pub(crate) fn cleared(&self, now: u64) -> Self {
+        Self {
+            bounds: self.bounds.clone(),
+            counts: core::iter::repeat_n(0, self.counts.len()).collect(),
+            at: now,
+        }
+    }
+
+    /// Returns the counts per bucket.
+    #[inline(always)]
+    pub(crate) fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// Returns the index of the bucket holding entries with `remaining` seconds left.
+    #[inline(always)]
+    fn bucket(&self, remaining: u64) -> usize {
+        self.bounds.partition_point(|bound| *bound < remaining)
+    }
+
+    /// Counts `n` entries indexed under `expires_at`.
+    #[inline(always)]
+    pub(crate) fn add(&mut self, expires_at: u64, n: usize) {
+        if expires_at >= self.at {
+            let bucket = self.bucket(expires_at - self.at);
+            self.counts[bucket] += n;
+        }
+    }
+
+    /// Stops counting `n` entries that were indexed under `expires_at`.
+    #[inline(always)]
+    pub(crate) fn remove(&mut self, expires_at: u64, n: usize) {
+        if expires_at >= self.at {
+            let bucket = self.bucket(expires_at - self.at);
+            self.counts[bucket] -= n;
+        }
+    }
+
+    /// Moves the counts forward to `now`, given the current `expiries`.
+    pub(crate) fn advance<K>(&mut self, now: u64, expiries: &BTreeMap<u64, BTreeSet<K>>) {
+        if now < self.at {
+            self.rebuild(now, expiries);
+            return;
+        }
+
+        let count = |from: u64, to: u64| -> usize {
+            expiries
+                .range((Bound::Excluded(from), Bound::Included(to)))
+                .map(|(_, keys)| keys.len())
+                .sum()
+        };
+
+        // Deadlines crossing several bounds go through each bucket in between, so the bounds
+        // are crossed from the longest one to keep every count from underflowing.
+        for (i, bound) in self.bounds.iter().enumerate().rev() {
+            let crossed = count(self.at.saturating_add(*bound), now.saturating_add(*bound));
+            self.counts[i + 1] -= crossed;
+            self.counts[i] += crossed;
+        }
+
+        // Deadlines before `now` are expired.
+        let expired: usize = expiries
+            .range(self.at..now)
+            .map(|(_, keys)| keys.len())
+            .sum();
+        self.counts[0] -= expired;
+        self.at = now;
+    }
+
+    /// Recounts the deadlines of `expiries` at `now` from scratch, for bulk changes of the
+    /// index.
+    pub(crate) fn rebuild<K>(&mut self, now: u64, expiries: &BTreeMap<u64, BTreeSet<K>>) {
+        self.counts.iter_mut().for_each(|count| *count = 0);
+        self.at = now;
+
+        for (expires_at, keys) in expiries.range(now..) {
+            self.add(*expires_at, keys.len());
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use super::*;
+
+/// Receives notifications about mutations of a `TimedMap`, so they can be mirrored into an
+/// external store (e.g., Redis or disk) to use the map as the in-memory tier of a write-through
+/// cache.
+///
+/// All methods have no-op default implementations, so implementers only need to override the
+/// events they are interested in.
+///
+/// Entries updated in place (e.g., by `TimedMap::map_values_in_place`, `TimedMap::extend_all`,
+/// `Entry::and_modify` or when a `LeaseGuard` is dropped) are reported through
+/// `StorageHook::on_insert` once the update is done. Changes made through mutable references
+/// handed out by the map (e.g., `TimedMap::get_mut_and_refresh`, `OccupiedEntry::get_mut` or
+/// `TimedMap::par_iter_mut`) can't be observed, so they are not reported, and neither are pins.
+///
+/// # Example usage:
+/// ```rs
+/// struct DiskMirror;
+///
+/// impl StorageHook<u32, String> for DiskMirror {
+///     fn on_insert(&mut self, k: &u32, v: &String, ttl: Option<Duration>) {
+///         // Write the entry to disk.
+///     }
+///
+///     fn on_remove(&mut self, k: &u32, v: &String) {
+///         // Delete the entry from disk.
+///     }
+/// }
+///
+/// let mut map: TimedMap<StdClock, u32, String> = TimedMap::new().storage_hook(DiskMirror);
+/// ```
+pub trait StorageHook<K, V> {
+    /// Called after an entry is inserted, replaced or updated in place.
+    ///
+    /// `ttl` is the lifetime of the entry, or `None` if it's constant.
+    fn on_insert(&mut self, _k: &K, _v: &V, _ttl: Option<Duration>) {}
+
+    /// Called after an entry is explicitly removed from the map.
+    fn on_remove(&mut self, _k: &K, _v: &V) {}
+
+    /// Called after an expired entry is dropped from the map.
+    fn on_expire(&mut self, _k: &K, _v: &V) {}
+}
+
+/// Expiration of an entry, as passed to the hook configured by `TimedMap::insert_hook`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Expiry {
+    /// Entry is constant.
+    Never,
+    /// Entry expires after the duration.
+    After(Duration),
+}
+
+impl Expiry {
+    /// Converts the remaining lifetime of an entry, which is `None` for constant entries.
+    #[inline(always)]
+    pub(crate) fn from_remaining(remaining: Option<Duration>) -> Self {
+        match remaining {
+            Some(duration) => Self::After(duration),
+            None => Self::Never,
+        }
+    }
+}
+
+/// Second-tier store consulted by `TimedMap::get_or_load` on misses, which turns `TimedMap`
+/// into a read-through two-level cache.
+///
+/// # Example usage:
+/// ```rs
+/// struct Database;
+///
+/// impl SecondaryStore<u32, String> for Database {
+///     fn load(&mut self, k: &u32) -> Option<(String, Option<Duration>)> {
+///         // Fetch the record and keep it in memory for 5 minutes.
+///         fetch_record(*k).map(|v| (v, Some(Duration::from_secs(300))))
+///     }
+/// }
+///
+/// let mut map: TimedMap<StdClock, u32, String> = TimedMap::new().secondary_store(Database);
+/// let record = map.get_or_load(&1);
+/// ```
+pub trait SecondaryStore<K, V> {
+    /// Loads the value of `k`, along with how long it should be kept in memory.
+    ///
+    /// Returning `None` as the duration promotes the value as a constant entry.
+    fn load(&mut self, k: &K) -> Option<(V, Option<Duration>)>;
+}
+
+/// Produces fresh values for entries that are about to expire, used by
+/// `TimedMap::get_with_refresh` to implement refresh-ahead caching.
+///
+/// Implemented for closures of `FnMut(&K, &V) -> Option<(V, Duration)>`.
+///
+/// # Example usage:
+/// ```rs
+/// let mut map: TimedMap<StdClock, u32, String> = TimedMap::new().refresh_ahead(
+///     Duration::from_secs(10),
+///     |k: &u32, _: &String| fetch_record(*k).map(|v| (v, Duration::from_secs(60))),
+/// );
+/// ```
+pub trait Refresher<K, V> {
+    /// Returns the refreshed value of `k` along with its new lifetime, or `None` to keep
+    /// the current entry as is.
+    fn refresh(&mut self, k: &K, v: &V) -> Option<(V, Duration)>;
+}
+
+impl<K, V, F> Refresher<K, V> for F
+where
+    F: FnMut(&K, &V) -> Option<(V, Duration)>,
+{
+    #[inline(always)]
+    fn refresh(&mut self, k: &K, v: &V) -> Option<(V, Duration)> {
+        self(k, v)
+    }
+}
+
+/// Notable events reported to `MetricsSink`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MetricEvent {
+    /// A lookup found a non-expired value.
+    Hit,
+    /// A lookup found nothing. Also reported after every `MetricEvent::ExpiredOnRead`.
+    Miss,
+    /// A lookup found an expired value which wasn't reaped yet.
+    ExpiredOnRead,
+    /// An entry was inserted. Also reported before every `MetricEvent::Replacement`.
+    Insert,
+    /// An insert overwrote an existing entry.
+    Replacement,
+    /// An entry was removed explicitly.
+    Removal,
+    /// An expired entry was removed by a cleanup pass.
+    Reaped,
+}
+
+impl MetricEvent {
+    /// Returns the name of the event, suitable as a metric label.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Hit => "hit",
+            Self::Miss => "miss",
+            Self::ExpiredOnRead => "expired_on_read",
+            Self::Insert => "insert",
+            Self::Replacement => "replacement",
+            Self::Removal => "removal",
+            Self::Reaped => "reaped",
+        }
+    }
+}
+
+/// Receives notable events of a `TimedMap` so they can be exported to a metrics system
+/// (e.g., Prometheus) without wiring each counter manually.
+///
+/// Unlike `StorageHook`, it is also notified about lookups, so it only gets a shared
+/// reference and should use interior mutability (e.g., atomics) to record events.
+///
+/// # Example usage:
+/// ```rs
+/// struct Counters([AtomicU64; 7]);
+///
+/// impl MetricsSink for Counters {
+///     fn on_event(&self, _map_name: Option<&'static str>, event: MetricEvent) {
+///         self.0[event as usize].fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let mut map: TimedMap<StdClock, u32, String> = TimedMap::new().metrics_sink(Counters::default());
+/// ```
+pub trait MetricsSink {
+    /// Called on every notable event of the map, along with the name configured by
+    /// `TimedMap::name`.
+    fn on_event(&self, _map_name: Option<&'static str>, _event: MetricEvent) {}
+}
+
+/// `MetricsSink` which increments the `timed_map_events_total` counter of the `metrics` crate,
+/// labeled with the name configured by `TimedMap::name` and `MetricEvent::name`.
+///
+/// # Example usage:
+/// ```rs
+/// let mut map: TimedMap<StdClock, u32, String> =
+///     TimedMap::new().name("sessions").metrics_sink(MetricsCrateSink);
+/// ```
+#[cfg(feature = "metrics")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MetricsCrateSink;
+
+#[cfg(feature = "metrics")]
+impl MetricsSink for MetricsCrateSink {
+    fn on_event(&self, map_name: Option<&'static str>, event: MetricEvent) {
+        metrics::counter!(
+            "timed_map_events_total",
+            "map" => map_name.unwrap_or("unnamed"),
+            "event" => event.name()
+        )
+        .increment(1);
+    }
+}
@@ -0,0 +1,251 @@
+use super::*;
+use map::GenericKey;
+
+/// Bounds applied to the durations derived from HTTP caching headers.
+///
+/// # Example usage:
+/// ```rs
+/// let policy = HttpCachePolicy::new()
+///     .shared(true)
+///     .max_ttl(Duration::from_secs(3600))
+///     .default_ttl(Duration::from_secs(30));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HttpCachePolicy {
+    min_ttl: Duration,
+    max_ttl: Option<Duration>,
+    default_ttl: Option<Duration>,
+    shared: bool,
+}
+
+impl Default for HttpCachePolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpCachePolicy {
+    /// Creates a private-cache policy without bounds and without a fallback duration.
+    pub const fn new() -> Self {
+        Self {
+            min_ttl: Duration::ZERO,
+            max_ttl: None,
+            default_ttl: None,
+            shared: false,
+        }
+    }
+
+    /// Raises derived durations to at least `min_ttl`.
+    pub const fn min_ttl(mut self, min_ttl: Duration) -> Self {
+        self.min_ttl = min_ttl;
+        self
+    }
+
+    /// Caps derived durations at `max_ttl`.
+    pub const fn max_ttl(mut self, max_ttl: Duration) -> Self {
+        self.max_ttl = Some(max_ttl);
+        self
+    }
+
+    /// Duration used for responses without any freshness information.
+    pub const fn default_ttl(mut self, default_ttl: Duration) -> Self {
+        self.default_ttl = Some(default_ttl);
+        self
+    }
+
+    /// Marks the cache as shared, which makes `s-maxage` take precedence over `max-age`
+    /// and `private` responses uncacheable.
+    pub const fn shared(mut self, shared: bool) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// Derives a duration from the `Cache-Control` and `Expires` header values.
+    ///
+    /// Returns `None` if the response must not be cached (e.g., `no-store`, `no-cache` or
+    /// an already stale response).
+    pub fn ttl(&self, cache_control: Option<&str>, expires: Option<&str>) -> Option<Duration> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+
+        self.ttl_at(cache_control, expires, now)
+    }
+
+    /// Same as `HttpCachePolicy::ttl`, with the current time given as the duration since
+    /// the Unix epoch.
+    fn ttl_at(
+        &self,
+        cache_control: Option<&str>,
+        expires: Option<&str>,
+        now: Duration,
+    ) -> Option<Duration> {
+        let mut max_age = None;
+        let mut s_maxage = None;
+
+        for directive in cache_control.unwrap_or_default().split(',') {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (directive.trim(), None),
+            };
+
+            if name.eq_ignore_ascii_case("no-store")
+                || name.eq_ignore_ascii_case("no-cache")
+                || (self.shared && name.eq_ignore_ascii_case("private"))
+            {
+                return None;
+            }
+
+            let seconds = value.and_then(|v| v.parse::<u64>().ok());
+            if name.eq_ignore_ascii_case("max-age") {
+                max_age = seconds;
+            } else if name.eq_ignore_ascii_case("s-maxage") {
+                s_maxage = seconds;
+            }
+        }
+
+        let ttl = match (self.shared.then_some(s_maxage).flatten(), max_age) {
+            (Some(seconds), _) | (None, Some(seconds)) => Duration::from_secs(seconds),
+            (None, None) => match expires {
+                // Invalid dates must be treated as already expired.
+                Some(expires) => httpdate::parse_http_date(expires)
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .and_then(|t| t.checked_sub(now))
+                    .unwrap_or_default(),
+                None => self.default_ttl?,
+            },
+        };
+
+        if ttl.is_zero() {
+            return None;
+        }
+
+        let ttl = ttl.max(self.min_ttl);
+        Some(self.max_ttl.map_or(ttl, |max_ttl| ttl.min(max_ttl)))
+    }
+}
+
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Inserts a key-value pair with the duration derived by `HttpCachePolicy::ttl` from the
+    /// `Cache-Control` and `Expires` header values of an HTTP response.
+    ///
+    /// Returns the applied duration, or `None` without inserting anything if the response
+    /// must not be cached.
+    pub fn insert_with_cache_headers(
+        &mut self,
+        k: K,
+        v: V,
+        policy: &HttpCachePolicy,
+        cache_control: Option<&str>,
+        expires: Option<&str>,
+    ) -> Option<Duration> {
+        let duration = policy.ttl(cache_control, expires)?;
+        self.insert_expirable(k, v, duration);
+
+        Some(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sun, 06 Nov 1994 08:49:37 GMT
+    const NOW: Duration = Duration::from_secs(784111777);
+
+    #[test]
+    fn test_cache_control_directives() {
+        let policy = HttpCachePolicy::new();
+        let shared = HttpCachePolicy::new().shared(true);
+
+        assert_eq!(
+            policy.ttl_at(Some("public, max-age=60"), None, NOW),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            policy.ttl_at(Some("max-age=60, s-maxage=600"), None, NOW),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            shared.ttl_at(Some("max-age=60, s-maxage=600"), None, NOW),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(
+            policy.ttl_at(Some("private, max-age=60"), None, NOW),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(shared.ttl_at(Some("private, max-age=60"), None, NOW), None);
+        assert_eq!(policy.ttl_at(Some("no-store"), None, NOW), None);
+        assert_eq!(policy.ttl_at(Some("No-Cache, max-age=60"), None, NOW), None);
+        assert_eq!(policy.ttl_at(Some("max-age=0"), None, NOW), None);
+    }
+
+    #[test]
+    fn test_expires_header() {
+        let policy = HttpCachePolicy::new();
+
+        assert_eq!(
+            policy.ttl_at(None, Some("Sun, 06 Nov 1994 08:50:37 GMT"), NOW),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            policy.ttl_at(
+                Some("max-age=10"),
+                Some("Sun, 06 Nov 1994 08:50:37 GMT"),
+                NOW
+            ),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            policy.ttl_at(None, Some("Sun, 06 Nov 1994 08:48:37 GMT"), NOW),
+            None
+        );
+        assert_eq!(policy.ttl_at(None, Some("0"), NOW), None);
+    }
+
+    #[test]
+    fn test_policy_bounds() {
+        let policy = HttpCachePolicy::new()
+            .min_ttl(Duration::from_secs(5))
+            .max_ttl(Duration::from_secs(300))
+            .default_ttl(Duration::from_secs(30));
+
+        assert_eq!(
+            policy.ttl_at(Some("max-age=1"), None, NOW),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            policy.ttl_at(Some("max-age=86400"), None, NOW),
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(
+            policy.ttl_at(None, None, NOW),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(HttpCachePolicy::new().ttl_at(None, None, NOW), None);
+    }
+
+    #[test]
+    fn test_insert_with_cache_headers() {
+        let mut map: TimedMap<StdClock, u32, &str> = TimedMap::new();
+        let policy = HttpCachePolicy::new();
+
+        assert_eq!(
+            map.insert_with_cache_headers(1, "cached", &policy, Some("max-age=60"), None),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(map.get(&1), Some(&"cached"));
+        assert!(map.get_remaining_duration(&1).is_some());
+
+        assert_eq!(
+            map.insert_with_cache_headers(2, "uncached", &policy, Some("no-store"), None),
+            None
+        );
+        assert_eq!(map.get(&2), None);
+    }
+}
@@ -0,0 +1,229 @@
+use super::*;
+use map::GenericKey;
+
+/// Extracts the secondary index of a value of `IndexedTimedMap`.
+type IndexFn<V, I> = dyn Fn(&V) -> I + Send + Sync;
+
+/// Map whose entries can also be looked up by a secondary index of type `I` extracted from
+/// their values, e.g., sessions by user id. Each entry may optionally expire after a specified
+/// duration.
+///
+/// The index always stays consistent with the map: entries leave it as soon as they are
+/// removed, replaced or dropped for being expired.
+///
+/// # Example usage:
+/// ```rs
+/// let mut sessions: IndexedTimedMap<StdClock, SessionId, Session, UserId> =
+///     IndexedTimedMap::new(|session: &Session| session.user_id);
+///
+/// sessions.insert_expirable(session_id, session, Duration::from_secs(3600));
+/// for (session_id, session) in sessions.get_by_index(&user_id) {
+///     // ...
+/// }
+/// ```
+pub struct IndexedTimedMap<C, K, V, I> {
+    inner: TimedMap<C, K, V>,
+    index: BTreeMap<I, BTreeSet<K>>,
+    index_fn: Box<IndexFn<V, I>>,
+}
+
+impl<C, K, V, I> IndexedTimedMap<C, K, V, I>
+where
+    C: Clock,
+    K: GenericKey,
+    I: Ord,
+{
+    /// Creates an empty map which indexes values by `index_fn`.
+    #[cfg(feature = "std")]
+    pub fn new<F>(index_fn: F) -> Self
+    where
+        F: Fn(&V) -> I + Send + Sync + 'static,
+    {
+        Self {
+            inner: TimedMap::new(),
+            index: BTreeMap::new(),
+            index_fn: Box::new(index_fn),
+        }
+    }
+
+    /// Creates an empty map which indexes values by `index_fn`.
+    ///
+    /// Uses the provided `clock` to handle expiration times.
+    #[cfg(not(feature = "std"))]
+    pub fn new<F>(clock: C, index_fn: F) -> Self
+    where
+        F: Fn(&V) -> I + Send + Sync + 'static,
+    {
+        Self {
+            inner: TimedMap::new(clock),
+            index: BTreeMap::new(),
+            index_fn: Box::new(index_fn),
+        }
+    }
+
+    /// Returns the associated value if present and not expired.
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.inner.get(k)
+    }
+
+    /// Returns the entries whose values are indexed by `i`, skipping the expired ones.
+    pub fn get_by_index<'a>(&'a self, i: &I) -> impl Iterator<Item = (&'a K, &'a V)> + 'a {
+        self.index
+            .get(i)
+            .into_iter()
+            .flatten()
+            .filter_map(|k| self.inner.get(k).map(|v| (k, v)))
+    }
+
+    /// Returns the remaining `Duration` of the entry of `k` if present and not expired.
+    ///
+    /// Returns `None` if the entry does not exist or is constant.
+    pub fn get_remaining_duration(&self, k: &K) -> Option<Duration> {
+        self.inner.get_remaining_duration(k)
+    }
+
+    /// Inserts a key-value pair with an expiration duration, and then drops the
+    /// expired entries.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned if it was not expired.
+    pub fn insert_expirable(&mut self, k: K, v: V, duration: Duration) -> Option<V> {
+        let live = self.inner.get(&k).is_some();
+        self.index_entry(&k, &v);
+
+        let old = self
+            .inner
+            .insert_expirable_unchecked(k.clone(), v, duration);
+        self.replace_indexed(&k, old, live)
+    }
+
+    /// Inserts a key-value pair that doesn't expire, and then drops the expired entries.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned if it was not expired.
+    pub fn insert_constant(&mut self, k: K, v: V) -> Option<V> {
+        let live = self.inner.get(&k).is_some();
+        self.index_entry(&k, &v);
+
+        let old = self.inner.insert_constant_unchecked(k.clone(), v);
+        self.replace_indexed(&k, old, live)
+    }
+
+    /// Removes a key-value pair from the map and returns the associated value if present
+    /// and not expired.
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        let live = self.inner.get(k).is_some();
+        let v = self.inner.remove_unchecked(k)?;
+        unindex(&mut self.index, &self.index_fn, k, &v);
+
+        live.then_some(v)
+    }
+
+    /// Clears expired entries from both the map and the index.
+    pub fn drop_expired_entries(&mut self) {
+        let index = &mut self.index;
+        let index_fn = &self.index_fn;
+        self.inner.drop_expired_entries_with(|k, v| {
+            unindex(index, index_fn, &k, &v);
+        });
+    }
+
+    /// Adds `k` to the index of `v`.
+    fn index_entry(&mut self, k: &K, v: &V) {
+        self.index
+            .entry((self.index_fn)(v))
+            .or_default()
+            .insert(k.clone());
+    }
+
+    /// Drops the replaced value of `k` from the index unless it's indexed the same as the new
+    /// one, and then drops the expired entries.
+    fn replace_indexed(&mut self, k: &K, old: Option<V>, live: bool) -> Option<V> {
+        if let Some(old) = old.as_ref() {
+            let old_i = (self.index_fn)(old);
+            let new_i = self.inner.get_unchecked(k).map(|v| (self.index_fn)(v));
+
+            if new_i.as_ref() != Some(&old_i) {
+                unindex(&mut self.index, &self.index_fn, k, old);
+            }
+        }
+
+        self.drop_expired_entries();
+        old.filter(|_| live)
+    }
+}
+
+/// Removes `k` from the index of `v`, dropping the index once it has no keys left.
+fn unindex<K, V, I, F>(index: &mut BTreeMap<I, BTreeSet<K>>, index_fn: &F, k: &K, v: &V)
+where
+    K: Ord,
+    I: Ord,
+    F: Fn(&V) -> I + ?Sized,
+{
+    let i = index_fn(v);
+    if let Some(keys) = index.get_mut(&i) {
+        keys.remove(k);
+
+        if keys.is_empty() {
+            index.remove(&i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_index_follows_expiration() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut sessions = IndexedTimedMap::new(&clock, |(user, _): &(&str, u32)| *user);
+
+        sessions.insert_expirable(1, ("alice", 10), Duration::from_secs(10));
+        sessions.insert_constant(2, ("alice", 20));
+        sessions.insert_constant(3, ("bob", 30));
+        assert_eq!(sessions.get_by_index(&"alice").count(), 2);
+
+        // Expired entries are skipped right away and leave the index once dropped.
+        clock.set(1011);
+        assert_eq!(
+            sessions.get_by_index(&"alice").collect::<Vec<_>>(),
+            [(&2, &("alice", 20))]
+        );
+
+        sessions.drop_expired_entries();
+        assert_eq!(sessions.index.get(&"alice").map(BTreeSet::len), Some(1));
+
+        // Replacing a value moves its key to the new index.
+        assert_eq!(
+            sessions.insert_constant(2, ("bob", 21)),
+            Some(("alice", 20))
+        );
+        assert!(!sessions.index.contains_key(&"alice"));
+        assert_eq!(sessions.get_by_index(&"bob").count(), 2);
+
+        assert_eq!(sessions.remove(&3), Some(("bob", 30)));
+        assert_eq!(
+            sessions.get_by_index(&"bob").collect::<Vec<_>>(),
+            [(&2, &("bob", 21))]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_get_by_index() {
+        let mut map: IndexedTimedMap<StdClock, u32, &str, usize> =
+            IndexedTimedMap::new(|v: &&str| v.len());
+
+        map.insert_expirable(1, "one", Duration::from_secs(60));
+        map.insert_constant(2, "two");
+        map.insert_constant(3, "three");
+
+        assert_eq!(map.get_by_index(&3).count(), 2);
+        assert_eq!(map.get_by_index(&5).collect::<Vec<_>>(), [(&3, &"three")]);
+        assert_eq!(map.get_by_index(&4).count(), 0);
+    }
+}
@@ -0,0 +1,69 @@
+use super::*;
+
+use std::string::String;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsValue;
+
+/// `TimedMap` exported to JavaScript with string keys, arbitrary values and millisecond
+/// durations, so JavaScript callers in the same WASM module can share the cache.
+///
+/// Durations are truncated to whole seconds, which is the resolution of the underlying clock.
+#[wasm_bindgen(js_name = TimedMap)]
+pub struct JsTimedMap {
+    inner: TimedMap<StdClock, String, JsValue>,
+}
+
+#[wasm_bindgen(js_class = TimedMap)]
+impl JsTimedMap {
+    /// Creates an empty map.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            inner: TimedMap::new(),
+        }
+    }
+
+    /// Returns the associated value if present and not expired.
+    pub fn get(&self, key: &str) -> Option<JsValue> {
+        self.inner.get(&String::from(key)).cloned()
+    }
+
+    /// Returns the remaining time in milliseconds before the entry expires, or `undefined`
+    /// for absent and constant entries.
+    #[wasm_bindgen(js_name = remainingMs)]
+    pub fn remaining_ms(&self, key: &str) -> Option<f64> {
+        self.inner
+            .get_remaining_duration(&String::from(key))
+            .map(|d| d.as_millis() as f64)
+    }
+
+    /// Inserts a key-value pair with the given duration in milliseconds and returns the
+    /// previous value if present.
+    pub fn insert(&mut self, key: String, value: JsValue, ttl_ms: f64) -> Option<JsValue> {
+        let duration = Duration::from_millis(ttl_ms.max(0.0) as u64);
+        self.inner.insert_expirable(key, value, duration)
+    }
+
+    /// Inserts a key-value pair that never expires and returns the previous value if present.
+    #[wasm_bindgen(js_name = insertConstant)]
+    pub fn insert_constant(&mut self, key: String, value: JsValue) -> Option<JsValue> {
+        self.inner.insert_constant(key, value)
+    }
+
+    /// Removes a key-value pair and returns its value if it wasn't expired.
+    pub fn remove(&mut self, key: &str) -> Option<JsValue> {
+        self.inner.remove(&String::from(key))
+    }
+
+    /// Clears expired entries from the map.
+    #[wasm_bindgen(js_name = dropExpiredEntries)]
+    pub fn drop_expired_entries(&mut self) {
+        self.inner.drop_expired_entries();
+    }
+}
+
+impl Default for JsTimedMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
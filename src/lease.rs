@@ -0,0 +1,162 @@
+use super::*;
+use map::GenericKey;
+
+use core::ops::{Deref, DerefMut};
+
+/// Guard returned by `TimedMap::lease` which gives access to a value and keeps its entry
+/// alive while in use.
+///
+/// The entry is pinned while the guard is held, and expires after the lease duration from
+/// the moment the guard is dropped, which is when the registered observers get notified
+/// about the update. Constant entries stay constant.
+pub struct LeaseGuard<'a, C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    map: &'a mut TimedMap<C, K, V>,
+    key: K,
+    // `None` for constant entries, which stay constant once the lease ends.
+    duration: Option<Duration>,
+    was_pinned: bool,
+}
+
+impl<C, K, V> Deref for LeaseGuard<'_, C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.map
+            .get_unchecked(&self.key)
+            .expect("leased entry must exist while the guard is held")
+    }
+}
+
+impl<C, K, V> DerefMut for LeaseGuard<'_, C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    fn deref_mut(&mut self) -> &mut V {
+        self.map
+            .get_unchecked_mut(&self.key)
+            .expect("leased entry must exist while the guard is held")
+    }
+}
+
+impl<C, K, V> Drop for LeaseGuard<'_, C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    fn drop(&mut self) {
+        self.map.unpin_with_duration(&self.key, self.duration);
+        self.map.notify_updated(&self.key);
+
+        // Entries pinned before the lease stay pinned, resuming with the refreshed duration.
+        if self.was_pinned {
+            self.map.pin(&self.key);
+        }
+    }
+}
+
+/// RAII helpers keeping entries alive while they are in use, e.g., connections or sessions.
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Leases the entry of `k` if present and not expired, returning a guard which derefs to
+    /// its value.
+    ///
+    /// The entry doesn't expire while the guard is held, and expires after `duration` from
+    /// the moment the guard is dropped, unless it's constant.
+    pub fn lease(&mut self, k: &K, duration: Duration) -> Option<LeaseGuard<'_, C, K, V>> {
+        let was_pinned = self.is_pinned(k);
+        if !self.pin(k) {
+            return None;
+        }
+        let duration = self.pinned_remaining(k).flatten().map(|_| duration);
+
+        Some(LeaseGuard {
+            map: self,
+            key: k.clone(),
+            duration,
+            was_pinned,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_lease_refreshes_on_drop() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(&clock);
+        map.insert_expirable(1, 0, Duration::from_secs(10));
+
+        {
+            let mut conn = map.lease(&1, Duration::from_secs(30)).unwrap();
+            *conn += 1;
+
+            // The entry outlives its deadline while leased.
+            clock.set(1020);
+            assert_eq!(*conn, 1);
+        }
+
+        assert_eq!(map.get(&1), Some(&1));
+        assert_eq!(
+            map.get_remaining_duration(&1),
+            Some(Duration::from_secs(30))
+        );
+        assert!(!map.is_pinned(&1));
+
+        clock.set(1051);
+        assert!(map.lease(&1, Duration::from_secs(30)).is_none());
+    }
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_lease_keeps_constant_entries_constant() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(&clock);
+        map.insert_constant(1, "config");
+
+        drop(map.lease(&1, Duration::from_secs(30)));
+        assert_eq!(map.status(&1), Some(EntryStatus::Constant));
+        assert!(!map.is_pinned(&1));
+
+        // Even when the constant entry was pinned before the lease.
+        map.pin(&1);
+        drop(map.lease(&1, Duration::from_secs(30)));
+        assert!(map.unpin(&1));
+        assert_eq!(map.status(&1), Some(EntryStatus::Constant));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_lease_keeps_existing_pin() {
+        let mut map: TimedMap<StdClock, u32, &str> = TimedMap::new();
+        map.insert_expirable(1, "session", Duration::from_secs(10));
+        map.pin(&1);
+
+        drop(map.lease(&1, Duration::from_secs(60)));
+        assert!(map.is_pinned(&1));
+
+        map.unpin(&1);
+        assert_eq!(
+            map.get_remaining_duration(&1),
+            Some(Duration::from_secs(60))
+        );
+    }
+}
@@ -56,7 +56,15 @@
 //!
 //! #### Customizing the Internal Map
 //!
-//! By default, `TimedMap` uses `BTreeMap` to store data, but you can switch to `FxHashMap` or `HashMap`.
+//! By default, `TimedMap` uses `BTreeMap` to store data, but you can switch to `FxHashMap`, `AHashMap`
+//! or `HashMap`.
+//!
+//! For maps that almost always hold only a handful of entries, `VecMap` stores them in a plain
+//! vector and looks them up with a linear scan, which beats both tree and hash lookups at
+//! small sizes. Once it grows past a small threshold, it is promoted to a `HashMap` automatically.
+//!
+//! `AHashMap` is a middle ground between `FxHashMap` and `HashMap`: it is faster than SipHash while
+//! still being resistant to collision attacks, which makes it a good fit for maps keyed by untrusted input.
 //!
 //! This is only available on `std` environments.
 //!
@@ -66,6 +74,15 @@
 //! let mut map: TimedMap<StdClock, _, _> = TimedMap::new_with_map_kind(MapKind::FxHashMap);
 //! ```
 //!
+//! `map_kind` tells which implementation a map is using, and `convert_to` moves the entries of an existing
+//! map into another one in place, keeping their lifetimes and the map's configuration:
+//!
+//! ```rs
+//! if map.map_kind() == MapKind::BTreeMap && map.len() > 100_000 {
+//!     map.convert_to(MapKind::FxHashMap);
+//! }
+//! ```
+//!
 //! #### Manual Expiration Control
 //!
 //! To have fully control over expired entries, use the `*_unchecked` functions and `drop_expired_entries` to handle expiration manually.
@@ -83,6 +100,13 @@
 //! map.drop_expired_entries();
 //! ```
 //!
+//! To act on the expired entries as they are dropped (e.g., logging expired lease holders), use
+//! `drop_expired_entries_with`, which hands each of them to a closure without collecting them first.
+//!
+//! ```rs
+//! map.drop_expired_entries_with(|holder, lease| log_expired(holder, lease));
+//! ```
+//!
 //! #### Setting Expiration Check Frequency
 //!
 //! In cases where inserts are frequent, `expiration_tick_cap` can be set to control how often expired entries are removed. For instance,
@@ -94,12 +118,577 @@
 //!
 //! let mut map: TimedMap<StdClock, _, _> = TimedMap::new().expiration_tick_cap(500);
 //! ```
+//!
+//! #### Returning Memory
+//!
+//! Hash based map kinds keep their capacity after entries are removed. Expiration passes and bulk removals
+//! shrink them automatically once less than a quarter of the capacity is in use, and `compact` can be called
+//! to shrink them right away, e.g., after a traffic spike.
+//!
+//! ```rs
+//! map.clear_expirable();
+//! map.compact();
+//! ```
+//!
+//! #### Benchmarks
+//!
+//! The `benches/` suite measures `insert`, `get`, `remove` and `drop_expired_entries` for each map kind,
+//! entry count and expiration tick cap, so you can compare them on your own hardware before tuning. Enable
+//! `rustc-hash` and `ahash` to include their map kinds:
+//!
+//! ```sh
+//! cargo bench --features rustc-hash,ahash
+//! ```
+//!
+//! Reports are written to `target/criterion/report/index.html`.
+//!
+//! #### Parallel Iteration
+//!
+//! With the `rayon` feature, `par_iter` and `par_iter_mut` process the non-expired entries on all cores, which
+//! pays off for CPU-bound work on every entry of large maps. The clock is read once per call.
+//!
+//! ```rs
+//! use rayon::prelude::*;
+//!
+//! map.par_iter_mut().for_each(|(_, v)| v.compress());
+//! let total: u64 = map.par_iter().map(|(_, v)| v.score()).sum();
+//! ```
+//!
+//! #### Serialization
+//!
+//! With the `serde` feature enabled, `TimedMap` implements `Serialize` (and `Deserialize` on `std`
+//! environments). Expired entries are skipped and the remaining durations of expirable entries are
+//! preserved, so they are re-anchored to the clock of the deserialized map. Since `no_std` maps can't be
+//! created without a clock, they are deserialized with `TimedMap::deserialize_with_clock` instead.
+//!
+//! ```rs
+//! let json = serde_json::to_string(&map).unwrap();
+//! let restored: TimedMap<StdClock, u32, String> = serde_json::from_str(&json).unwrap();
+//! ```
+//!
+//! `serialize_with` writes a subset of the entries instead, selected by `SerializeOptions`: expired entries that
+//! aren't dropped yet can be included, and entries can be limited to constant ones or the ones matching a
+//! predicate. Lifetimes can be written as absolute deadlines with `TtlFormat::Deadline`.
+//!
+//! ```rs
+//! let options = SerializeOptions::new().constant_only().ttl_format(TtlFormat::Deadline);
+//! let json = serde_json::to_string(&map.serialize_with(options)).unwrap();
+//! ```
+//!
+//! #### Snapshots
+//!
+//! On `std` environments, `export` captures live entries with their absolute deadlines as `SystemTime`,
+//! and `restore` inserts them back, dropping the ones whose deadline passed in the meantime. This allows
+//! handing the state between processes or storing it externally.
+//!
+//! ```rs
+//! let snapshot = map.export();
+//!
+//! let mut restored: TimedMap<StdClock, _, _> = TimedMap::new();
+//! restored.restore(snapshot);
+//! ```
+//!
+//! #### Binary Snapshots
+//!
+//! For very large maps, the `bincode` feature provides a compact binary snapshot format with the same
+//! absolute deadline semantics as `export`/`restore`. Snapshots carry a format version, so the ones taken
+//! by older versions of this crate can still be loaded after upgrades.
+//!
+//! ```rs
+//! let bytes = map.to_snapshot_bytes().unwrap();
+//! let restored: TimedMap<StdClock, u32, String> = TimedMap::from_snapshot_bytes(&bytes).unwrap();
+//!
+//! // Or stream it without materializing the whole snapshot in memory.
+//! map.save_to(BufWriter::new(File::create("cache.snapshot")?))?;
+//! let restored: TimedMap<StdClock, u32, String> = TimedMap::load_from(BufReader::new(File::open("cache.snapshot")?))?;
+//! ```
+//!
+//! #### Browser Persistence
+//!
+//! With the `wasm-storage` feature, the map can be persisted into `localStorage` and restored after a
+//! page reload. Deadlines are stored as absolute timestamps, so entries keep expiring on time.
+//!
+//! ```rs
+//! map.save_to_local_storage("cache").unwrap();
+//! let restored: Option<TimedMap<StdClock, u32, String>> = TimedMap::load_from_local_storage("cache").unwrap();
+//! ```
+//!
+//! #### Browser Auto Cleanup
+//!
+//! With the `wasm` feature, `AutoCleanupTimedMap` drops expired entries with `setTimeout`, so browser apps
+//! reclaim them even when the map isn't being written to. A single timer follows the soonest deadline and is
+//! rescheduled whenever a mutable borrow of the map is released.
+//!
+//! ```rs
+//! let cache = AutoCleanupTimedMap::new(TimedMap::new());
+//!
+//! cache.borrow_mut().insert_expirable(key, value, Duration::from_secs(60));
+//! let value = cache.borrow().get(&key).cloned();
+//! ```
+//!
+//! #### Write-Through Hooks
+//!
+//! A `StorageHook` can be registered to mirror inserts, removals and expirations into an external store,
+//! turning `TimedMap` into the in-memory tier of a write-through cache. Values and lifetimes updated in
+//! place (e.g., by `map_values_in_place`, `extend_all` or `and_modify`) are mirrored as inserts, except
+//! for changes made through mutable references the map hands out, such as `get_mut_and_refresh`.
+//!
+//! ```rs
+//! let mut map: TimedMap<StdClock, u32, String> = TimedMap::new().storage_hook(RedisMirror::new());
+//! ```
+//!
+//! #### Read-Through Tiering
+//!
+//! A `SecondaryStore` can be registered as the second tier of the cache. On misses, `get_or_load`
+//! consults it and promotes the loaded value into memory with the duration the store returns.
+//!
+//! ```rs
+//! let mut map: TimedMap<StdClock, u32, String> = TimedMap::new().secondary_store(Database::connect());
+//! let record = map.get_or_load(&1);
+//! ```
+//!
+//! #### Refresh-Ahead
+//!
+//! A `Refresher` can be registered along with a threshold. When `get_with_refresh` observes an entry that
+//! expires within the threshold, it is refreshed in place so hot entries never actually expire.
+//!
+//! ```rs
+//! let mut map: TimedMap<StdClock, u32, String> = TimedMap::new().refresh_ahead(
+//!     Duration::from_secs(10),
+//!     |k: &u32, _: &String| fetch_record(*k).map(|v| (v, Duration::from_secs(60))),
+//! );
+//!
+//! let record = map.get_with_refresh(&1);
+//! ```
+//!
+//! #### Serving Stale Entries
+//!
+//! A grace period can be configured to keep expired entries around for a while. During that window,
+//! `get_stale` still returns them marked as stale, so callers can serve stale data while refreshing it
+//! instead of hitting a latency spike at every TTL boundary.
+//!
+//! ```rs
+//! let mut map: TimedMap<StdClock, _, _> = TimedMap::new().stale_grace_period(Duration::from_secs(30));
+//!
+//! if let Some((record, is_stale)) = map.get_stale(&1) {
+//!     if is_stale {
+//!         // Trigger a refresh in the background.
+//!     }
+//! }
+//! ```
+//!
+//! #### Migrating From Standard Maps
+//!
+//! On `std` environments, `HashMap` and `BTreeMap` can be converted into `TimedMap` directly, holding all entries
+//! as constant. To give them a lifetime instead, use `extend_expirable`.
+//!
+//! ```rs
+//! let map: TimedMap<StdClock, _, _> = hash_map.into();
+//!
+//! let mut map: TimedMap<StdClock, _, _> = TimedMap::new_with_map_kind(MapKind::HashMap);
+//! map.extend_expirable(hash_map, Duration::from_secs(60));
+//! ```
+//!
+//! #### C FFI
+//!
+//! With the `ffi` feature, a C-compatible API is exposed in the `ffi` module. Maps are handled through opaque
+//! pointers and store byte slices as keys and values, so C and C++ services can share the same expiration logic.
+//!
+//! ```c
+//! TimedMapHandle *map = timed_map_new();
+//! timed_map_insert(map, key, key_len, value, value_len, 60);
+//! timed_map_free(map);
+//! ```
+//!
+//! #### JavaScript Bindings
+//!
+//! With the `wasm` feature, `JsTimedMap` is exported through `wasm-bindgen` as `TimedMap`, with string keys,
+//! arbitrary JavaScript values and durations in milliseconds (truncated to whole seconds).
+//!
+//! ```js
+//! const map = new TimedMap();
+//! map.insert("session", { user: 1 }, 60_000);
+//! map.get("session");
+//! ```
+//!
+//! #### Session Store
+//!
+//! With the `tower-sessions` feature, `TimedMapSessionStore` implements the `tower-sessions` `SessionStore`
+//! trait, so web services can use `TimedMap` as an in-memory session backend. Session expiry dates are
+//! mapped to entry durations.
+//!
+//! ```rs
+//! let session_layer = SessionManagerLayer::new(TimedMapSessionStore::new());
+//! ```
+//!
+//! #### HTTP Caching Headers
+//!
+//! With the `http-cache` feature, `HttpCachePolicy` derives entry durations from the `Cache-Control`
+//! (`max-age`, `s-maxage`) and `Expires` headers of HTTP responses and clamps them to the configured bounds.
+//! Responses that must not be cached are skipped by `insert_with_cache_headers`.
+//!
+//! ```rs
+//! let policy = HttpCachePolicy::new().shared(true).max_ttl(Duration::from_secs(3600));
+//!
+//! map.insert_with_cache_headers(url, body, &policy, Some("public, max-age=600"), None);
+//! ```
+//!
+//! #### Statistics
+//!
+//! With the `stats` feature, the map counts hits, misses, expired-on-read lookups, inserts, replacements,
+//! removals and reaped entries. Counters are atomic, so `stats` and `reset_stats` only need a shared reference.
+//! On targets without native 64-bit atomics (e.g., `thumbv6m`), enable `portable-atomic` as well.
+//!
+//! ```rs
+//! let stats = map.stats();
+//! println!("hit rate: {:?}", stats.hit_rate());
+//!
+//! map.reset_stats();
+//! ```
+//!
+//! Regardless of the feature, `count_by_status` returns how many entries are constant, live expirable and
+//! expired but not dropped yet. The constant and expirable totals are maintained as entries come and go, while
+//! expirations happen as time passes, so the expired entries are counted by walking the part of the expiration
+//! index past its deadlines, which is O(expired) like `len`. `len_unchecked`, which counts the expired entries
+//! as well, is O(1).
+//!
+//! #### Metrics Export
+//!
+//! A `MetricsSink` can be registered to receive hits, misses, inserts, removals and expirations as
+//! `MetricEvent`s. With the `metrics` feature, `MetricsCrateSink` forwards them to the `metrics` crate,
+//! so any of its exporters (e.g., Prometheus) can publish cache metrics. The `metrics` crate requires `std`,
+//! so the feature enables it as well.
+//!
+//! ```rs
+//! let mut map: TimedMap<StdClock, u32, String> =
+//!     TimedMap::new().name("sessions").metrics_sink(MetricsCrateSink);
+//! ```
+//!
+//! #### Tracing
+//!
+//! With the `tracing` feature, cleanup passes emit `debug` events with the number of dropped entries and
+//! the time spent, while individual inserts and expirations are emitted at the `trace` level. Keys are left
+//! out of the events, so enabling the feature doesn't put any extra bounds on them.
+//!
+//! #### Logging
+//!
+//! For projects not using `tracing`, the `log` feature emits warnings for anomalies such as the clock going
+//! backwards or a cleanup pass dropping an unusually large batch of entries.
+//!
+//! #### Audit Log
+//!
+//! An audit log can be enabled to keep the most recent inserts, removals and expirations in a bounded
+//! ring buffer, which helps with investigating incidents like unexpectedly deleted entries.
+//!
+//! ```rs
+//! let mut map: TimedMap<StdClock, u32, String> = TimedMap::new().audit_log(1024);
+//!
+//! for record in map.recent_ops() {
+//!     println!("{:?} {:?} at {:?}: {:?}", record.op, record.key, record.at, record.outcome);
+//! }
+//! ```
+//!
+//! #### Change Tracking
+//!
+//! With change tracking enabled, the map records the keys inserted, updated, removed or expired since the
+//! last `take_changes` call, so a background task can synchronize deltas to an external system instead of
+//! diffing full snapshots.
+//!
+//! ```rs
+//! let mut map: TimedMap<StdClock, u32, String> = TimedMap::new().track_changes();
+//!
+//! for (key, change) in map.take_changes() {
+//!     // Push the change to the external system.
+//! }
+//! ```
+//!
+//! #### Versioned Inserts
+//!
+//! With the `versions` feature, each entry keeps a version which is bumped whenever its value or lifetime
+//! is updated, whether it's replaced or updated in place. `get_versioned` returns it along with the value,
+//! and `insert_if_version` only writes if it's still the same, for optimistic concurrency between workers
+//! updating the same records.
+//!
+//! ```rs
+//! let (record, version) = map.get_versioned(&1).unwrap();
+//! let updated = refresh(record);
+//!
+//! if map.insert_if_version(1, updated, version).is_err() {
+//!     // Another worker updated the record in the meantime.
+//! }
+//! ```
+//!
+//! #### Entry Metadata
+//!
+//! With the `metadata` feature, each entry keeps the time it was inserted and last read, available through
+//! `created_at` and `last_accessed` for age-based policies. Access times are updated with atomics, so reads
+//! still only need a shared reference, but every hit through `get`, `get_stale` or `get_versioned` costs
+//! an extra atomic store. On targets without native 64-bit atomics (e.g., `thumbv6m`), enable
+//! `portable-atomic` as well.
+//!
+//! ```rs
+//! let age = map.created_at(&1).map(|created_at| now - created_at);
+//! let idle = map.last_accessed(&1).map(|last_accessed| now - last_accessed);
+//! ```
+//!
+//! `oldest_entry` and `newest_entry` return the entries inserted longest ago and most recently, which shows
+//! whether the map is actually turning over or holding on to the same entries forever.
+//!
+//! `iter_by_recency` walks the live entries from the most to the least recently accessed, for exporting
+//! the hot set to pre-warm a replica.
+//!
+//! #### Sets
+//!
+//! `TimedSet` is a thin wrapper over `TimedMap` with `()` values and a set-like API, which fits things like
+//! recently seen message IDs.
+//!
+//! ```rs
+//! let mut seen: TimedSet<StdClock, u64> = TimedSet::new();
+//!
+//! if seen.insert_expirable(message_id, Duration::from_secs(60)) {
+//!     // First time seeing this message within the last minute.
+//! }
+//! ```
+//!
+//! `DedupWindow` builds on it to drop duplicates within a window with a single call.
+//!
+//! ```rs
+//! let mut dedup: DedupWindow<StdClock, u64> = DedupWindow::new();
+//!
+//! if dedup.is_first_seen(message_id, Duration::from_secs(300)) {
+//!     handle(message);
+//! }
+//! ```
+//!
+//! #### Bidirectional Maps
+//!
+//! `TimedBiMap` keeps expiring pairs that can be looked up from both sides, such as session IDs and user IDs.
+//! Both directions always stay consistent as pairs expire or get removed from both sides at once.
+//!
+//! ```rs
+//! let mut sessions: TimedBiMap<StdClock, u64, u32> = TimedBiMap::new();
+//!
+//! sessions.insert_expirable(session_id, user_id, Duration::from_secs(3600));
+//! assert_eq!(sessions.get_by_right(&user_id), Some(&session_id));
+//! ```
+//!
+//! #### Delay Queues
+//!
+//! `ExpiringQueue` is a delay queue built on the same clock abstraction: items are pushed with a delay and
+//! popped once it has passed. With the `tokio` feature, `next` waits for the next item asynchronously.
+//!
+//! ```rs
+//! let mut queue: ExpiringQueue<StdClock, Job> = ExpiringQueue::new();
+//! queue.push(job, Duration::from_secs(30));
+//!
+//! while let Some(job) = queue.pop_due() {
+//!     job.run();
+//! }
+//! ```
+//!
+//! #### Rate Limiting
+//!
+//! Maps holding `TokenBucket`s can be used as per-key rate limiters through `try_acquire`. Buckets expire once
+//! they would be full again, so idle keys don't pile up in the map.
+//!
+//! ```rs
+//! let mut limiter: TimedMap<StdClock, IpAddr, TokenBucket> = TimedMap::new();
+//!
+//! if !limiter.try_acquire(client_ip, 10, 1) {
+//!     // Too many requests.
+//! }
+//! ```
+//!
+//! #### Idle and Absolute Timeouts
+//!
+//! `SessionMap` expires entries after an idle timeout which is reset on every access, and after a maximum
+//! absolute lifetime at the latest, which is what real session policies usually need.
+//!
+//! ```rs
+//! let mut sessions: SessionMap<StdClock, u64, User> =
+//!     SessionMap::new(Duration::from_secs(15 * 60), Duration::from_secs(12 * 60 * 60));
+//!
+//! sessions.insert(session_id, user);
+//! let user = sessions.get(&session_id);
+//! ```
+//!
+//! #### Secondary Indexes
+//!
+//! `IndexedTimedMap` indexes entries by a key extracted from their values, so they can be looked up both by
+//! their key and by the index, such as sessions by session ID and by user ID. The index stays consistent as
+//! entries expire, get replaced or removed.
+//!
+//! ```rs
+//! let mut sessions: IndexedTimedMap<StdClock, u64, Session, u32> =
+//!     IndexedTimedMap::new(|session: &Session| session.user_id);
+//!
+//! sessions.insert_expirable(session_id, session, Duration::from_secs(3600));
+//! let user_sessions = sessions.get_by_index(&user_id).count();
+//! ```
+//!
+//! #### Tags
+//!
+//! `TaggedTimedMap` attaches tags to entries when they are inserted, so groups of entries can be removed or
+//! expired at once with `remove_by_tag` and `expire_by_tag`, such as everything cached for a tenant.
+//!
+//! ```rs
+//! let mut cache: TaggedTimedMap<StdClock, String, Page, u32> = TaggedTimedMap::new();
+//!
+//! cache.insert_expirable(url, page, Duration::from_secs(300), [tenant_id]);
+//! cache.remove_by_tag(&tenant_id);
+//! ```
+//!
+//! #### Namespaces
+//!
+//! `NamespacedTimedMap` hosts many logically separate maps on top of a single clock and expiration index, so
+//! apps needing dozens of small maps don't run a cleanup pass per map. Each namespace can be cleared on its own
+//! and, with the `stats` feature, keeps its own counters.
+//!
+//! ```rs
+//! let mut maps: NamespacedTimedMap<StdClock, &str, u64, String> = NamespacedTimedMap::new();
+//!
+//! maps.insert_expirable("tokens", user_id, token, Duration::from_secs(60));
+//! maps.clear(&"tokens");
+//! ```
+//!
+//! #### Weak References
+//!
+//! `WeakTimedMap` holds its values as `Weak` references, so it acts as a registry of live shared objects with
+//! expiration as a safety net. `get` upgrades them, and entries whose objects were dropped everywhere else are
+//! treated as expired and reaped by `drop_expired_entries`.
+//!
+//! ```rs
+//! let mut connections: WeakTimedMap<StdClock, u64, Connection> = WeakTimedMap::new();
+//!
+//! connections.insert_expirable(id, &connection, Duration::from_secs(300));
+//! let connection: Option<Arc<Connection>> = connections.get(&id);
+//! ```
+//!
+//! #### Secrets
+//!
+//! With the `zeroize` feature, `ZeroizingTimedMap` wipes its values with `Zeroize` when expired entries are
+//! dropped and when the map is cleared or dropped. Replaced and removed values are returned as `Zeroizing`,
+//! so they are wiped as soon as the caller drops them.
+//!
+//! ```rs
+//! let mut tokens: ZeroizingTimedMap<StdClock, u64, String> = ZeroizingTimedMap::new();
+//!
+//! tokens.insert_expirable(user_id, token, Duration::from_secs(60));
+//! ```
+//!
+//! #### Fuzzing
+//!
+//! With the `arbitrary` feature, `MapSeed` implements `Arbitrary` and describes a map state (entries with
+//! their lifetimes, `MapKind` and expiration tick cap), which `TimedMap::from_seed` turns into a map.
+//!
+//! ```rs
+//! fuzz_target!(|seed: MapSeed<u8, u32>| {
+//!     let mut map: TimedMap<StdClock, u8, u32> = TimedMap::from_seed(seed);
+//!     map.drop_expired_entries();
+//! });
+//! ```
+//!
+//! #### Property Testing
+//!
+//! With the `proptest` feature, the `testing` module provides strategies generating random operation
+//! sequences, and `check_ops` which replays them against a map driven by a manual clock and a reference
+//! model. Useful for property-testing code built on `TimedMap`.
+//!
+//! ```rs
+//! use timed_map::testing::{check_ops, ops};
+//!
+//! proptest! {
+//!     #[test]
+//!     fn map_matches_model(ops in ops(0u8..8, any::<u32>(), 0..64)) {
+//!         check_ops(&ops)?;
+//!     }
+//! }
+//! ```
+//!
+//! #### Redis-Style Commands
+//!
+//! The `redis` module provides familiar commands such as `set_ex`, `ttl`, `expire`, `persist` and `get_del` on
+//! top of `TimedMap`, which keeps call sites unchanged while migrating an in-process cache off Redis.
+//!
+//! ```rs
+//! use timed_map::redis::Commands;
+//!
+//! map.set_ex(key, value, 60);
+//! if map.ttl(&key) == -1 {
+//!     // The key never expires.
+//! }
+//! ```
+//!
+//! #### Global Maps
+//!
+//! On `std` environments, `StaticTimedMap` can be placed in a `static` directly. The inner map is
+//! created lazily on first access and guarded by a `Mutex`.
+//!
+//! ```rs
+//! use timed_map::{MapKind, StaticTimedMap};
+//!
+//! static SESSIONS: StaticTimedMap<u64, &str> = StaticTimedMap::new_with_map_kind(MapKind::HashMap);
+//!
+//! SESSIONS.lock().insert_expirable(1, "session", Duration::from_secs(60));
+//! assert_eq!(SESSIONS.lock().get(&1), Some(&"session"));
+//! ```
+//!
+//! On `no_std` environments, `TimedMap::new` is a `const fn`, so the map can be created in a `static`
+//! initializer and wrapped by the synchronization primitive of your platform (e.g., `critical_section::Mutex`).
 
 #![no_std]
 
+mod audit;
+#[cfg(all(feature = "std", feature = "wasm"))]
+mod auto_cleanup;
+mod backoff;
+mod bimap;
 mod clock;
+mod dedup;
+mod diff;
 mod entry;
+mod entry_ref;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "arbitrary")]
+mod fuzzing;
+mod histogram;
+mod hook;
+#[cfg(feature = "http-cache")]
+mod http_cache;
+mod index;
+#[cfg(all(feature = "std", feature = "wasm"))]
+mod js_map;
+mod lease;
 mod map;
+mod map_entry;
+mod namespace;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod queue;
+pub mod redis;
+mod sample;
+#[cfg(feature = "serde")]
+mod serialization;
+mod session;
+#[cfg(feature = "tower-sessions")]
+mod session_store;
+mod set;
+#[cfg(feature = "bincode")]
+mod snapshot;
+#[cfg(feature = "std")]
+mod static_map;
+mod stats;
+mod tag;
+#[cfg(feature = "proptest")]
+pub mod testing;
+mod token_bucket;
+mod weak;
+#[cfg(feature = "wasm-storage")]
+mod web_storage;
+#[cfg(feature = "zeroize")]
+mod zeroizing;
 
 macro_rules! cfg_std_feature {
     ($($item:item)*) => {
@@ -124,33 +713,116 @@ cfg_std_feature! {
 
     use std::marker::PhantomData;
     use std::time::Duration;
-    use std::collections::{BTreeMap, HashMap};
+    use std::collections::{btree_map, hash_map, BTreeMap, BTreeSet, HashMap};
     use std::hash::Hash;
+    use std::vec::{self, Vec};
+    use std::borrow::ToOwned;
+    use std::boxed::Box;
     use clock::Clock;
 
     #[cfg(not(feature = "wasm"))]
-    use std::time::Instant;
+    use std::time::{Instant, SystemTime};
 
     #[cfg(feature = "wasm")]
-    use web_time::Instant;
+    use web_time::{Instant, SystemTime};
 
     pub use clock::StdClock;
     pub use map::MapKind;
+    pub use static_map::StaticTimedMap;
+
+    #[cfg(feature = "arbitrary")]
+    pub use fuzzing::MapSeed;
+
+    #[cfg(feature = "bincode")]
+    pub use snapshot::SnapshotError;
+
+    #[cfg(feature = "http-cache")]
+    pub use http_cache::HttpCachePolicy;
+
+    #[cfg(feature = "wasm")]
+    pub use auto_cleanup::{AutoCleanupGuard, AutoCleanupTimedMap};
+
+    #[cfg(feature = "wasm")]
+    pub use js_map::JsTimedMap;
+
+    #[cfg(feature = "tower-sessions")]
+    pub use session_store::TimedMapSessionStore;
 }
 
 cfg_not_std_feature! {
     extern crate alloc;
 
     use core::time::Duration;
-    use alloc::collections::BTreeMap;
+    use alloc::borrow::ToOwned;
+    use alloc::boxed::Box;
+    use alloc::collections::{btree_map, BTreeMap, BTreeSet};
+    use alloc::vec::Vec;
 
     pub use clock::Clock;
 }
 
-use entry::EntryStatus;
+use audit::AuditLog;
+use core::borrow::Borrow;
+use core::convert::Infallible;
+use core::ops::Bound;
 use entry::ExpirableEntry;
+use histogram::TtlHistogram;
+use stats::StatsCounters;
+
+pub use audit::{AuditOp, AuditOutcome, AuditRecord, Change};
+pub use backoff::Backoff;
+pub use bimap::TimedBiMap;
+pub use dedup::DedupWindow;
+pub use diff::MapDiff;
+pub use entry::{EntryStatus, ExpirableValue, Freshness};
+pub use entry_ref::{EntryRef, OccupiedEntryRef, VacantEntryRef};
+pub use error::TimedMapError;
+
+#[cfg(all(
+    any(
+        feature = "stats",
+        feature = "metadata",
+        all(feature = "std", feature = "proptest")
+    ),
+    not(feature = "portable-atomic")
+))]
+use core::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(all(
+    any(
+        feature = "stats",
+        feature = "metadata",
+        all(feature = "std", feature = "proptest")
+    ),
+    feature = "portable-atomic"
+))]
+use portable_atomic::{AtomicU64, Ordering};
 
 #[cfg(all(feature = "std", feature = "rustc-hash"))]
 use rustc_hash::FxHashMap;
 
+#[cfg(all(feature = "std", feature = "ahash"))]
+use ahash::AHashMap;
+
+#[cfg(feature = "metrics")]
+pub use hook::MetricsCrateSink;
+pub use hook::{Expiry, MetricEvent, MetricsSink, Refresher, SecondaryStore, StorageHook};
+pub use index::IndexedTimedMap;
+pub use lease::LeaseGuard;
 pub use map::TimedMap;
+pub use map_entry::{Entry, OccupiedEntry, VacantEntry};
+pub use namespace::NamespacedTimedMap;
+pub use queue::ExpiringQueue;
+pub use sample::RandomSource;
+#[cfg(feature = "serde")]
+pub use serialization::{SerializeOptions, TtlFormat};
+pub use session::SessionMap;
+pub use set::TimedSet;
+#[cfg(feature = "stats")]
+pub use stats::Stats;
+pub use stats::{EntryCounts, ExpiryIndexStats, InvariantViolation, MapSummary};
+pub use tag::TaggedTimedMap;
+pub use token_bucket::TokenBucket;
+pub use weak::WeakTimedMap;
+#[cfg(feature = "zeroize")]
+pub use zeroizing::ZeroizingTimedMap;
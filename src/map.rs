@@ -32,14 +32,69 @@ cfg_std_feature! {
     impl<T: Clone + Eq + Ord + Hash> GenericKey for T {}
 }
 
+cfg_not_std_feature! {
+    /// Generic trait for `no_std` borrowed forms of keys used in lookups (e.g., `str` for
+    /// `String` keys) that is gated by the `std` feature and handled at compile time.
+    pub trait GenericQuery: Eq + Ord {}
+    impl<T: Eq + Ord + ?Sized> GenericQuery for T {}
+}
+
+cfg_std_feature! {
+    /// Generic trait for `std` borrowed forms of keys used in lookups (e.g., `str` for
+    /// `String` keys) that is gated by the `std` feature and handled at compile time.
+    pub trait GenericQuery: Eq + Ord + Hash {}
+    impl<T: Eq + Ord + Hash + ?Sized> GenericQuery for T {}
+}
+
+/// Maximum number of entries `GenericMap::VecMap` holds before it gets promoted
+/// to `GenericMap::HashMap`.
+#[cfg(feature = "std")]
+const VEC_MAP_PROMOTION_THRESHOLD: usize = 16;
+
+/// Capacity below which `GenericMap` is never considered sparse, so small maps don't
+/// reallocate back and forth.
+#[cfg(feature = "std")]
+const MIN_COMPACTION_CAPACITY: usize = 64;
+
+/// `GenericMap` is considered sparse once less than `1 / SPARSE_OCCUPANCY_DIVISOR` of its
+/// capacity is in use.
+#[cfg(feature = "std")]
+const SPARSE_OCCUPANCY_DIVISOR: usize = 4;
+
+/// Returns `true` if `len` entries only fill a small part of `capacity`.
+#[cfg(feature = "std")]
+#[inline(always)]
+fn is_sparse(len: usize, capacity: usize) -> bool {
+    capacity > MIN_COMPACTION_CAPACITY && len < capacity / SPARSE_OCCUPANCY_DIVISOR
+}
+
+/// Returns the expiration time `duration` after `now`, or `TimedMapError::InvalidDuration` if
+/// it does not fit in the clock's seconds.
+#[inline(always)]
+fn expires_at_after(now: u64, duration: Duration) -> Result<u64, TimedMapError> {
+    now.checked_add(duration.as_secs())
+        .ok_or(TimedMapError::InvalidDuration)
+}
+
+/// Returns the time `duration` after `now`, saturating at the latest time the clock's seconds
+/// can represent.
+#[inline(always)]
+fn saturating_after(now: u64, duration: Duration) -> u64 {
+    now.saturating_add(duration.as_secs())
+}
+
 /// Wraps different map implementations and provides a single interface to access them.
 #[allow(clippy::enum_variant_names)]
-enum GenericMap<K, V> {
+pub(crate) enum GenericMap<K, V> {
     BTreeMap(BTreeMap<K, V>),
     #[cfg(feature = "std")]
     HashMap(HashMap<K, V>),
+    #[cfg(feature = "std")]
+    VecMap(Vec<(K, V)>),
     #[cfg(all(feature = "std", feature = "rustc-hash"))]
     FxHashMap(FxHashMap<K, V>),
+    #[cfg(all(feature = "std", feature = "ahash"))]
+    AHashMap(AHashMap<K, V>),
 }
 
 impl<K, V> Default for GenericMap<K, V> {
@@ -53,440 +108,5538 @@ where
     K: GenericKey,
 {
     #[inline(always)]
-    fn get(&self, k: &K) -> Option<&V> {
+    fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
         match self {
             Self::BTreeMap(inner) => inner.get(k),
             #[cfg(feature = "std")]
             Self::HashMap(inner) => inner.get(k),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => inner
+                .iter()
+                .find(|(key, _)| key.borrow() == k)
+                .map(|(_, v)| v),
             #[cfg(all(feature = "std", feature = "rustc-hash"))]
             Self::FxHashMap(inner) => inner.get(k),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => inner.get(k),
         }
     }
 
     #[inline(always)]
-    fn insert(&mut self, k: K, v: V) -> Option<V> {
+    fn get_key_value<Q>(&self, k: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
         match self {
-            Self::BTreeMap(inner) => inner.insert(k, v),
+            Self::BTreeMap(inner) => inner.get_key_value(k),
             #[cfg(feature = "std")]
-            Self::HashMap(inner) => inner.insert(k, v),
+            Self::HashMap(inner) => inner.get_key_value(k),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => inner
+                .iter()
+                .find(|(key, _)| key.borrow() == k)
+                .map(|(key, v)| (key, v)),
             #[cfg(all(feature = "std", feature = "rustc-hash"))]
-            Self::FxHashMap(inner) => inner.insert(k, v),
+            Self::FxHashMap(inner) => inner.get_key_value(k),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => inner.get_key_value(k),
         }
     }
 
     #[inline(always)]
-    fn remove(&mut self, k: &K) -> Option<V> {
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
         match self {
-            Self::BTreeMap(inner) => inner.remove(k),
+            Self::BTreeMap(inner) => inner.get_mut(k),
             #[cfg(feature = "std")]
-            Self::HashMap(inner) => inner.remove(k),
+            Self::HashMap(inner) => inner.get_mut(k),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => inner
+                .iter_mut()
+                .find(|(key, _)| (*key).borrow() == k)
+                .map(|(_, v)| v),
             #[cfg(all(feature = "std", feature = "rustc-hash"))]
-            Self::FxHashMap(inner) => inner.remove(k),
+            Self::FxHashMap(inner) => inner.get_mut(k),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => inner.get_mut(k),
         }
     }
-}
-
-/// Specifies the inner map implementation for `TimedMap`.
-#[cfg(feature = "std")]
-#[allow(clippy::enum_variant_names)]
-pub enum MapKind {
-    BTreeMap,
-    HashMap,
-    #[cfg(feature = "rustc-hash")]
-    FxHashMap,
-}
 
-/// Associates keys of type `K` with values of type `V`. Each entry may optionally expire after a
-/// specified duration.
-///
-/// Mutable functions automatically clears expired entries when called.
-///
-/// If no expiration is set, the entry remains constant.
-pub struct TimedMap<C, K, V> {
+    /// Creates an empty map of the implementation specified by `map_kind`.
     #[cfg(feature = "std")]
-    clock: StdClock,
+    fn with_kind(map_kind: MapKind) -> Self {
+        match map_kind {
+            MapKind::BTreeMap => Self::BTreeMap(BTreeMap::default()),
+            MapKind::HashMap => Self::HashMap(HashMap::default()),
+            MapKind::VecMap => Self::VecMap(Vec::default()),
+            #[cfg(feature = "rustc-hash")]
+            MapKind::FxHashMap => Self::FxHashMap(FxHashMap::default()),
+            #[cfg(feature = "ahash")]
+            MapKind::AHashMap => Self::AHashMap(AHashMap::default()),
+        }
+    }
+
+    /// Returns the implementation in use.
     #[cfg(feature = "std")]
-    marker: PhantomData<C>,
+    #[inline(always)]
+    fn kind(&self) -> MapKind {
+        match self {
+            Self::BTreeMap(_) => MapKind::BTreeMap,
+            Self::HashMap(_) => MapKind::HashMap,
+            Self::VecMap(_) => MapKind::VecMap,
+            #[cfg(feature = "rustc-hash")]
+            Self::FxHashMap(_) => MapKind::FxHashMap,
+            #[cfg(feature = "ahash")]
+            Self::AHashMap(_) => MapKind::AHashMap,
+        }
+    }
 
-    #[cfg(not(feature = "std"))]
-    clock: C,
+    #[inline(always)]
+    fn len(&self) -> usize {
+        match self {
+            Self::BTreeMap(inner) => inner.len(),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.len(),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => inner.len(),
+            #[cfg(all(feature = "std", feature = "rustc-hash"))]
+            Self::FxHashMap(inner) => inner.len(),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => inner.len(),
+        }
+    }
 
-    map: GenericMap<K, ExpirableEntry<V>>,
-    expiries: BTreeMap<u64, K>,
+    /// Shrinks the capacity of the hash and vector based implementations as much as possible.
+    ///
+    /// `BTreeMap` frees its nodes as entries are removed, so there is nothing to shrink.
+    #[inline(always)]
+    fn shrink_to_fit(&mut self) {
+        match self {
+            Self::BTreeMap(_) => {}
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.shrink_to_fit(),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => inner.shrink_to_fit(),
+            #[cfg(all(feature = "std", feature = "rustc-hash"))]
+            Self::FxHashMap(inner) => inner.shrink_to_fit(),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => inner.shrink_to_fit(),
+        }
+    }
 
-    expiration_tick: u16,
-    expiration_tick_cap: u16,
-}
+    /// Returns `true` if only a small part of the allocated capacity is in use.
+    #[inline(always)]
+    fn is_sparse(&self) -> bool {
+        match self {
+            Self::BTreeMap(_) => false,
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => is_sparse(inner.len(), inner.capacity()),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => is_sparse(inner.len(), inner.capacity()),
+            #[cfg(all(feature = "std", feature = "rustc-hash"))]
+            Self::FxHashMap(inner) => is_sparse(inner.len(), inner.capacity()),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => is_sparse(inner.len(), inner.capacity()),
+        }
+    }
 
-#[cfg(feature = "std")]
-impl<C, K, V> Default for TimedMap<C, K, V> {
-    fn default() -> Self {
-        Self {
-            clock: StdClock::new(),
-            map: GenericMap::default(),
-            expiries: BTreeMap::default(),
-            marker: PhantomData,
+    /// Calls `f` on every entry with mutable access to the value, stopping at the first error.
+    #[inline(always)]
+    fn try_for_each_mut<E, F>(&mut self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&K, &mut V) -> Result<(), E>,
+    {
+        self.iter_mut().try_for_each(|(k, v)| f(k, v))
+    }
 
-            expiration_tick: 0,
-            expiration_tick_cap: 1,
+    /// Looks up the entry of `k`, keeping `k` only if the entry is vacant.
+    #[inline(always)]
+    fn entry(&mut self, k: K) -> GenericEntry<'_, K, V> {
+        #[cfg(feature = "std")]
+        let vec_index = match self {
+            Self::VecMap(inner) => match inner.iter().position(|(key, _)| key == &k) {
+                Some(i) => Some(i),
+                None => return GenericEntry::Vacant(GenericVacantEntry::VecMap(self, k)),
+            },
+            _ => None,
+        };
+
+        match self {
+            Self::BTreeMap(inner) => match inner.entry(k) {
+                btree_map::Entry::Occupied(entry) => {
+                    GenericEntry::Occupied(GenericOccupiedEntry::BTreeMap(entry))
+                }
+                btree_map::Entry::Vacant(entry) => {
+                    GenericEntry::Vacant(GenericVacantEntry::BTreeMap(entry))
+                }
+            },
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => hash_map_entry(inner.entry(k)),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => GenericEntry::Occupied(GenericOccupiedEntry::VecMap(
+                inner,
+                vec_index.expect("vacant entries of vector maps return early"),
+            )),
+            #[cfg(all(feature = "std", feature = "rustc-hash"))]
+            Self::FxHashMap(inner) => hash_map_entry(inner.entry(k)),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => hash_map_entry(inner.entry(k)),
         }
     }
-}
 
-impl<C, K, V> TimedMap<C, K, V>
-where
-    C: Clock,
-    K: GenericKey,
-{
-    /// Creates an empty map.
-    #[cfg(feature = "std")]
-    pub fn new() -> Self {
-        Self::default()
+    /// Takes all entries, leaving an empty map of the same implementation behind.
+    #[inline(always)]
+    fn take(&mut self) -> Self {
+        #[cfg(feature = "std")]
+        let empty = Self::with_kind(self.kind());
+        #[cfg(not(feature = "std"))]
+        let empty = Self::default();
+
+        core::mem::replace(self, empty)
     }
 
-    /// Creates an empty map based on the chosen map implementation specified by `MapKind`.
+    /// Replaces a vector map with a hash map holding the same entries.
     #[cfg(feature = "std")]
-    pub fn new_with_map_kind(map_kind: MapKind) -> Self {
-        let map = match map_kind {
-            MapKind::BTreeMap => GenericMap::<K, ExpirableEntry<V>>::BTreeMap(BTreeMap::default()),
-            MapKind::HashMap => GenericMap::HashMap(HashMap::default()),
-            #[cfg(feature = "rustc-hash")]
-            MapKind::FxHashMap => GenericMap::FxHashMap(FxHashMap::default()),
-        };
+    #[inline(always)]
+    fn promote_vec_map(&mut self) {
+        if let Self::VecMap(inner) = self {
+            // Linear scans stop paying off at this size, promote to a hash map.
+            *self = Self::HashMap(inner.drain(..).collect());
+        }
+    }
 
-        Self {
-            map,
-            clock: StdClock::new(),
-            expiries: BTreeMap::default(),
+    #[inline(always)]
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        match self {
+            Self::BTreeMap(inner) => inner.insert(k, v),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.insert(k, v),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => {
+                if let Some((_, old)) = inner.iter_mut().find(|(key, _)| key == &k) {
+                    return Some(core::mem::replace(old, v));
+                }
+
+                if inner.len() < VEC_MAP_PROMOTION_THRESHOLD {
+                    inner.push((k, v));
+                    return None;
+                }
+
+                self.promote_vec_map();
+                self.insert(k, v)
+            }
+            #[cfg(all(feature = "std", feature = "rustc-hash"))]
+            Self::FxHashMap(inner) => inner.insert(k, v),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => inner.insert(k, v),
+        }
+    }
 
+    #[inline(always)]
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        self.remove_entry(k).map(|(_, v)| v)
+    }
+
+    #[inline(always)]
+    fn remove_entry<Q>(&mut self, k: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        match self {
+            Self::BTreeMap(inner) => inner.remove_entry(k),
             #[cfg(feature = "std")]
-            marker: PhantomData,
-            expiration_tick: 0,
-            expiration_tick_cap: 1,
+            Self::HashMap(inner) => inner.remove_entry(k),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => inner
+                .iter()
+                .position(|(key, _)| key.borrow() == k)
+                .map(|i| inner.swap_remove(i)),
+            #[cfg(all(feature = "std", feature = "rustc-hash"))]
+            Self::FxHashMap(inner) => inner.remove_entry(k),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => inner.remove_entry(k),
         }
     }
+}
 
-    /// Creates an empty `TimedMap`.
-    ///
-    /// Uses the provided `clock` to handle expiration times.
-    #[cfg(not(feature = "std"))]
-    pub fn new(clock: C) -> Self {
-        Self {
-            clock,
-            map: GenericMap::default(),
-            expiries: BTreeMap::default(),
-            expiration_tick: 0,
-            expiration_tick_cap: 1,
+impl<K, V> GenericMap<K, V> {
+    #[inline(always)]
+    fn iter(&self) -> GenericMapIter<'_, K, V> {
+        match self {
+            Self::BTreeMap(inner) => GenericMapIter::BTreeMap(inner.iter()),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => GenericMapIter::HashMap(inner.iter()),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => GenericMapIter::VecMap(inner.iter()),
+            #[cfg(all(feature = "std", feature = "rustc-hash"))]
+            Self::FxHashMap(inner) => GenericMapIter::HashMap(inner.iter()),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => GenericMapIter::HashMap(inner.iter()),
         }
     }
 
-    /// Configures `expiration_tick_cap`, which sets how often `TimedMap::drop_expired_entries`
-    /// is automatically called. The default value is 1.
-    ///
-    /// On each insert (excluding `unchecked` ones), an internal counter `expiration_tick` is incremented.
-    /// When `expiration_tick` meets or exceeds `expiration_tick_cap`, `TimedMap::drop_expired_entries` is
-    /// triggered to remove expired entries.
-    ///
-    /// Use this to control cleanup frequency and optimize performance. For example, if your workload
-    /// involves about 100 inserts within couple seconds, setting `expiration_tick_cap` to 100 can improve
-    /// the performance significantly.
     #[inline(always)]
-    pub fn expiration_tick_cap(mut self, expiration_tick_cap: u16) -> Self {
-        self.expiration_tick_cap = expiration_tick_cap;
-        self
+    fn iter_mut(&mut self) -> GenericMapIterMut<'_, K, V> {
+        match self {
+            Self::BTreeMap(inner) => GenericMapIterMut::BTreeMap(inner.iter_mut()),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => GenericMapIterMut::HashMap(inner.iter_mut()),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => GenericMapIterMut::VecMap(inner.iter_mut()),
+            #[cfg(all(feature = "std", feature = "rustc-hash"))]
+            Self::FxHashMap(inner) => GenericMapIterMut::HashMap(inner.iter_mut()),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => GenericMapIterMut::HashMap(inner.iter_mut()),
+        }
     }
 
-    /// Returns the associated value if present and not expired.
-    ///
-    /// To retrieve the value without checking expiration, use `TimedMap::get_unchecked`.
-    pub fn get(&self, k: &K) -> Option<&V> {
-        self.map
-            .get(k)
-            .filter(|v| !v.is_expired(self.clock.elapsed_seconds_since_creation()))
-            .map(|v| v.value())
+    /// Returns the entries from `start` onwards in order if the underlying implementation is
+    /// sorted, or all entries otherwise, along with whether they are sorted.
+    #[inline(always)]
+    fn iter_from<Q>(&self, start: &Q) -> (GenericMapIter<'_, K, V>, bool)
+    where
+        K: Borrow<Q> + Ord,
+        Q: Ord + ?Sized,
+    {
+        match self {
+            Self::BTreeMap(inner) => (
+                GenericMapIter::BTreeRange(
+                    inner.range::<Q, _>((Bound::Included(start), Bound::Unbounded)),
+                ),
+                true,
+            ),
+            #[cfg(feature = "std")]
+            _ => (self.iter(), false),
+        }
     }
+}
+
+/// Iterator over the entries of `GenericMap`, in the order of the underlying implementation.
+#[allow(clippy::enum_variant_names)]
+enum GenericMapIter<'a, K, V> {
+    BTreeMap(btree_map::Iter<'a, K, V>),
+    BTreeRange(btree_map::Range<'a, K, V>),
+    #[cfg(feature = "std")]
+    HashMap(hash_map::Iter<'a, K, V>),
+    #[cfg(feature = "std")]
+    VecMap(core::slice::Iter<'a, (K, V)>),
+}
+
+impl<'a, K, V> Iterator for GenericMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
 
-    /// Returns the associated value if present, regardless of whether it is expired.
-    ///
-    /// If you only want non-expired entries, use `TimedMap::get` instead.
     #[inline(always)]
-    pub fn get_unchecked(&self, k: &K) -> Option<&V> {
-        self.map.get(k).map(|v| v.value())
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::BTreeMap(inner) => inner.next(),
+            Self::BTreeRange(inner) => inner.next(),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.next(),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => inner.next().map(|(k, v)| (k, v)),
+        }
     }
+}
 
-    /// Returns the associated value's `Duration` if present and not expired.
-    ///
-    /// Returns `None` if the entry does not exist or is constant.
-    pub fn get_remaining_duration(&self, k: &K) -> Option<Duration> {
-        match self.map.get(k) {
-            Some(v) => {
-                let now = self.clock.elapsed_seconds_since_creation();
-                if v.is_expired(now) {
-                    return None;
-                }
+/// Iterator over the entries of `GenericMap` with mutable access to the values, in the order
+/// of the underlying implementation.
+#[allow(clippy::enum_variant_names)]
+enum GenericMapIterMut<'a, K, V> {
+    BTreeMap(btree_map::IterMut<'a, K, V>),
+    #[cfg(feature = "std")]
+    HashMap(hash_map::IterMut<'a, K, V>),
+    #[cfg(feature = "std")]
+    VecMap(core::slice::IterMut<'a, (K, V)>),
+}
 
-                v.remaining_duration(now)
-            }
-            None => None,
+impl<'a, K, V> Iterator for GenericMapIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::BTreeMap(inner) => inner.next(),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.next(),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => inner.next().map(|(k, v)| (&*k, v)),
         }
     }
+}
+
+impl<K, V> IntoIterator for GenericMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = GenericMapIntoIter<K, V>;
 
-    /// Inserts a key-value pair with an expiration duration. If duration is `None`,
-    /// entry will be stored in a non-expirable way.
-    ///
-    /// If a value already exists for the given key, it will be updated and then
-    /// the old one will be returned.
     #[inline(always)]
-    fn insert(&mut self, k: K, v: V, expires_at: Option<u64>) -> Option<V> {
-        let entry = ExpirableEntry::new(v, expires_at);
-        self.map.insert(k, entry).map(|v| v.owned_value())
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            Self::BTreeMap(inner) => GenericMapIntoIter::BTreeMap(inner.into_iter()),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => GenericMapIntoIter::HashMap(inner.into_iter()),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => GenericMapIntoIter::VecMap(inner.into_iter()),
+            #[cfg(all(feature = "std", feature = "rustc-hash"))]
+            Self::FxHashMap(inner) => GenericMapIntoIter::HashMap(inner.into_iter()),
+            #[cfg(all(feature = "std", feature = "ahash"))]
+            Self::AHashMap(inner) => GenericMapIntoIter::HashMap(inner.into_iter()),
+        }
     }
+}
 
-    /// Inserts a key-value pair with an expiration duration, and then drops the
-    /// expired entries.
-    ///
-    /// If a value already exists for the given key, it will be updated and then
-    /// the old one will be returned.
-    ///
-    /// If you don't want to the check expired entries, consider using `TimedMap::insert_expirable_unchecked`
-    /// instead.
+/// Owning iterator over the entries of `GenericMap`, in the order of the underlying implementation.
+#[allow(clippy::enum_variant_names)]
+pub(crate) enum GenericMapIntoIter<K, V> {
+    BTreeMap(btree_map::IntoIter<K, V>),
+    #[cfg(feature = "std")]
+    HashMap(hash_map::IntoIter<K, V>),
+    #[cfg(feature = "std")]
+    VecMap(vec::IntoIter<(K, V)>),
+}
+
+impl<K, V> Iterator for GenericMapIntoIter<K, V> {
+    type Item = (K, V);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::BTreeMap(inner) => inner.next(),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.next(),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner) => inner.next(),
+        }
+    }
+}
+
+/// Entry of `GenericMap`, resolved by a single lookup of the key.
+pub(crate) enum GenericEntry<'a, K, V> {
+    Occupied(GenericOccupiedEntry<'a, K, V>),
+    Vacant(GenericVacantEntry<'a, K, V>),
+}
+
+impl<K, V> GenericEntry<'_, K, V>
+where
+    K: GenericKey,
+{
+    #[inline(always)]
+    pub(crate) fn key(&self) -> &K {
+        match self {
+            Self::Occupied(entry) => entry.key(),
+            Self::Vacant(entry) => entry.key(),
+        }
+    }
+}
+
+/// Occupied entry of `GenericMap`.
+#[allow(clippy::enum_variant_names)]
+pub(crate) enum GenericOccupiedEntry<'a, K, V> {
+    BTreeMap(btree_map::OccupiedEntry<'a, K, V>),
+    #[cfg(feature = "std")]
+    HashMap(hash_map::OccupiedEntry<'a, K, V>),
+    #[cfg(feature = "std")]
+    VecMap(&'a mut Vec<(K, V)>, usize),
+}
+
+impl<'a, K, V> GenericOccupiedEntry<'a, K, V>
+where
+    K: GenericKey,
+{
+    #[inline(always)]
+    pub(crate) fn key(&self) -> &K {
+        match self {
+            Self::BTreeMap(inner) => inner.key(),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.key(),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner, i) => &inner[*i].0,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn get(&self) -> &V {
+        match self {
+            Self::BTreeMap(inner) => inner.get(),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.get(),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner, i) => &inner[*i].1,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_mut(&mut self) -> &mut V {
+        match self {
+            Self::BTreeMap(inner) => inner.get_mut(),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.get_mut(),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner, i) => &mut inner[*i].1,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn into_mut(self) -> &'a mut V {
+        match self {
+            Self::BTreeMap(inner) => inner.into_mut(),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.into_mut(),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner, i) => &mut inner[i].1,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn remove_entry(self) -> (K, V) {
+        match self {
+            Self::BTreeMap(inner) => inner.remove_entry(),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.remove_entry(),
+            #[cfg(feature = "std")]
+            Self::VecMap(inner, i) => inner.swap_remove(i),
+        }
+    }
+}
+
+/// Vacant entry of `GenericMap`.
+#[allow(clippy::enum_variant_names)]
+pub(crate) enum GenericVacantEntry<'a, K, V> {
+    BTreeMap(btree_map::VacantEntry<'a, K, V>),
+    #[cfg(feature = "std")]
+    HashMap(hash_map::VacantEntry<'a, K, V>),
+    // Keeps the whole map, since inserting may promote it to a hash map.
+    #[cfg(feature = "std")]
+    VecMap(&'a mut GenericMap<K, V>, K),
+}
+
+impl<'a, K, V> GenericVacantEntry<'a, K, V>
+where
+    K: GenericKey,
+{
+    #[inline(always)]
+    pub(crate) fn key(&self) -> &K {
+        match self {
+            Self::BTreeMap(inner) => inner.key(),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.key(),
+            #[cfg(feature = "std")]
+            Self::VecMap(_, k) => k,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn into_key(self) -> K {
+        match self {
+            Self::BTreeMap(inner) => inner.into_key(),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.into_key(),
+            #[cfg(feature = "std")]
+            Self::VecMap(_, k) => k,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn insert(self, v: V) -> &'a mut V {
+        match self {
+            Self::BTreeMap(inner) => inner.insert(v),
+            #[cfg(feature = "std")]
+            Self::HashMap(inner) => inner.insert(v),
+            #[cfg(feature = "std")]
+            Self::VecMap(map, k) => {
+                if map.len() >= VEC_MAP_PROMOTION_THRESHOLD {
+                    map.promote_vec_map();
+                }
+
+                match map {
+                    GenericMap::VecMap(inner) => {
+                        inner.push((k, v));
+                        &mut inner.last_mut().expect("entry was just pushed").1
+                    }
+                    GenericMap::HashMap(inner) => inner.entry(k).or_insert(v),
+                    _ => unreachable!("vector maps are only promoted to hash maps"),
+                }
+            }
+        }
+    }
+}
+
+/// Wraps an entry of the hash map implementations.
+#[cfg(feature = "std")]
+#[inline(always)]
+fn hash_map_entry<K, V>(entry: hash_map::Entry<'_, K, V>) -> GenericEntry<'_, K, V> {
+    match entry {
+        hash_map::Entry::Occupied(entry) => {
+            GenericEntry::Occupied(GenericOccupiedEntry::HashMap(entry))
+        }
+        hash_map::Entry::Vacant(entry) => GenericEntry::Vacant(GenericVacantEntry::HashMap(entry)),
+    }
+}
+
+/// Specifies the inner map implementation for `TimedMap`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum MapKind {
+    BTreeMap,
+    HashMap,
+    /// Linear-scan vector optimized for small maps, promoted to `HashMap`
+    /// once it grows past 16 entries.
+    VecMap,
+    #[cfg(feature = "rustc-hash")]
+    FxHashMap,
+    #[cfg(feature = "ahash")]
+    AHashMap,
+}
+
+/// Number of entries dropped by a single cleanup pass above which a warning is logged.
+#[cfg(feature = "log")]
+const LARGE_CLEANUP_BATCH: usize = 10_000;
+
+/// Computes the duration of entries inserted with `TimedMap::insert`.
+type TtlFn<K, V> = dyn Fn(&K, &V) -> Option<Duration> + Send + Sync;
+
+/// Gets notified after entries are inserted or updated, configured by `TimedMap::insert_hook`.
+type InsertHook<K, V> = dyn FnMut(&K, &V, Expiry) + Send + Sync;
+
+/// Runs once the entry it's attached to by `TimedMap::insert_expirable_with_callback` is
+/// expired, removed or replaced.
+type EntryCallback<K, V> = dyn FnOnce(&K, &V) + Send + Sync;
+
+/// Matches keys of entries inserted with `TimedMap::insert` to the duration they get.
+type TtlRule<K> = (Box<dyn Fn(&K) -> bool + Send + Sync>, Duration);
+
+/// Optional state of `TimedMap`, boxed so maps that don't use any of it stay small.
+struct Extensions<K, V> {
+    pinned: BTreeMap<K, Option<u64>>,
+    callbacks: BTreeMap<K, Box<EntryCallback<K, V>>>,
+
+    ttl_fn: Option<Box<TtlFn<K, V>>>,
+    ttl_rules: Vec<TtlRule<K>>,
+    insert_hook: Option<Box<InsertHook<K, V>>>,
+    storage_hook: Option<Box<dyn StorageHook<K, V> + Send + Sync>>,
+    secondary_store: Option<Box<dyn SecondaryStore<K, V> + Send + Sync>>,
+    refresher: Option<(Duration, Box<dyn Refresher<K, V> + Send + Sync>)>,
+
+    name: Option<&'static str>,
+    audit_log: Option<AuditLog<K>>,
+    changes: Option<BTreeMap<K, Change>>,
+    metrics_sink: Option<Box<dyn MetricsSink + Send + Sync>>,
+    ttl_histogram: Option<TtlHistogram>,
+}
+
+impl<K: GenericKey, V> Extensions<K, V> {
+    /// Returns the duration the TTL rules or the TTL function give to `v` stored for `k`.
+    #[inline(always)]
+    fn ttl_for(&self, k: &K, v: &V) -> Option<Duration> {
+        self.ttl_rules
+            .iter()
+            .find(|(predicate, _)| predicate(k))
+            .map(|(_, duration)| *duration)
+            .or_else(|| self.ttl_fn.as_ref().and_then(|ttl_fn| ttl_fn(k, v)))
+    }
+
+    /// Records a change of `k` if change tracking is enabled.
+    #[inline(always)]
+    fn track_change(&mut self, k: &K, change: Change) {
+        if let Some(changes) = self.changes.as_mut() {
+            match changes.get_mut(k) {
+                Some(previous) => *previous = previous.merge(change),
+                None => {
+                    changes.insert(k.clone(), change);
+                }
+            }
+        }
+    }
+}
+
+impl<K, V> Default for Extensions<K, V> {
+    fn default() -> Self {
+        Self {
+            pinned: BTreeMap::new(),
+            callbacks: BTreeMap::new(),
+
+            ttl_fn: None,
+            ttl_rules: Vec::new(),
+            insert_hook: None,
+            storage_hook: None,
+            secondary_store: None,
+            refresher: None,
+
+            name: None,
+            audit_log: None,
+            changes: None,
+            metrics_sink: None,
+            ttl_histogram: None,
+        }
+    }
+}
+
+/// Associates keys of type `K` with values of type `V`. Each entry may optionally expire after a
+/// specified duration.
+///
+/// Mutable functions automatically clears expired entries when called.
+///
+/// If no expiration is set, the entry remains constant.
+pub struct TimedMap<C, K, V> {
+    #[cfg(feature = "std")]
+    clock: StdClock,
+    #[cfg(feature = "std")]
+    marker: PhantomData<C>,
+
+    #[cfg(not(feature = "std"))]
+    clock: C,
+
+    map: GenericMap<K, ExpirableEntry<V>>,
+    expiries: BTreeMap<u64, BTreeSet<K>>,
+    // Number of keys in `expiries`, so entries can be counted without walking the index.
+    expirable_len: usize,
+
+    expiration_tick: u16,
+    expiration_tick_cap: u16,
+    stale_grace_seconds: u64,
+    expiration_suspended_at: Option<u64>,
+
+    // Only allocated once a map uses pins, callbacks, hooks or any other optional feature.
+    ext: Option<Box<Extensions<K, V>>>,
+    stats: StatsCounters,
+
+    #[cfg(feature = "log")]
+    last_cleanup_seconds: u64,
+}
+
+#[cfg(feature = "std")]
+impl<C, K, V> Default for TimedMap<C, K, V> {
+    fn default() -> Self {
+        Self {
+            clock: StdClock::new(),
+            map: GenericMap::default(),
+            expiries: BTreeMap::default(),
+            expirable_len: 0,
+            marker: PhantomData,
+
+            expiration_tick: 0,
+            expiration_tick_cap: 1,
+            stale_grace_seconds: 0,
+            expiration_suspended_at: None,
+
+            ext: None,
+            stats: StatsCounters::new(),
+
+            #[cfg(feature = "log")]
+            last_cleanup_seconds: 0,
+        }
+    }
+}
+
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Creates an empty map.
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty map based on the chosen map implementation specified by `MapKind`.
+    #[cfg(feature = "std")]
+    pub fn new_with_map_kind(map_kind: MapKind) -> Self {
+        Self {
+            map: GenericMap::with_kind(map_kind),
+            clock: StdClock::new(),
+            expiries: BTreeMap::default(),
+            expirable_len: 0,
+
+            #[cfg(feature = "std")]
+            marker: PhantomData,
+            expiration_tick: 0,
+            expiration_tick_cap: 1,
+            stale_grace_seconds: 0,
+            expiration_suspended_at: None,
+
+            ext: None,
+            stats: StatsCounters::new(),
+
+            #[cfg(feature = "log")]
+            last_cleanup_seconds: 0,
+        }
+    }
+
+    /// Creates an empty map whose clock reads `seconds` and only moves through
+    /// `TimedMap::advance_clock`, so `testing::check_ops` can run on `std`.
+    #[cfg(all(feature = "std", feature = "proptest"))]
+    pub(crate) fn with_manual_clock(seconds: u64) -> Self {
+        Self {
+            clock: StdClock::manual(seconds),
+            ..Self::default()
+        }
+    }
+
+    /// Moves the clock of a map created with `TimedMap::with_manual_clock` forward by
+    /// `seconds`.
+    #[cfg(all(feature = "std", feature = "proptest"))]
+    pub(crate) fn advance_clock(&self, seconds: u64) {
+        self.clock.advance(seconds);
+    }
+
+    /// Creates an empty `TimedMap`.
+    ///
+    /// Uses the provided `clock` to handle expiration times.
+    ///
+    /// This is a `const fn`, so the map can be created directly in `static` initializers.
+    #[cfg(not(feature = "std"))]
+    pub const fn new(clock: C) -> Self {
+        Self {
+            clock,
+            map: GenericMap::BTreeMap(BTreeMap::new()),
+            expiries: BTreeMap::new(),
+            expirable_len: 0,
+            expiration_tick: 0,
+            expiration_tick_cap: 1,
+            stale_grace_seconds: 0,
+            expiration_suspended_at: None,
+
+            ext: None,
+            stats: StatsCounters::new(),
+
+            #[cfg(feature = "log")]
+            last_cleanup_seconds: 0,
+        }
+    }
+
+    /// Configures `expiration_tick_cap`, which sets how often `TimedMap::drop_expired_entries`
+    /// is automatically called. The default value is 1.
+    ///
+    /// On each insert (excluding `unchecked` ones), an internal counter `expiration_tick` is incremented.
+    /// When `expiration_tick` meets or exceeds `expiration_tick_cap`, `TimedMap::drop_expired_entries` is
+    /// triggered to remove expired entries.
+    ///
+    /// Use this to control cleanup frequency and optimize performance. For example, if your workload
+    /// involves about 100 inserts within couple seconds, setting `expiration_tick_cap` to 100 can improve
+    /// the performance significantly.
+    #[inline(always)]
+    pub fn expiration_tick_cap(mut self, expiration_tick_cap: u16) -> Self {
+        self.expiration_tick_cap = expiration_tick_cap;
+        self
+    }
+
+    /// Configures a grace period during which expired entries are kept in the map and still
+    /// served by `TimedMap::get_stale`, marked as stale (stale-while-revalidate).
+    ///
+    /// This avoids latency spikes at TTL boundaries by letting callers serve stale data while
+    /// the value is being refreshed. `TimedMap::get` keeps treating expired entries as absent.
+    /// The default is no grace period.
+    #[inline(always)]
+    pub fn stale_grace_period(mut self, grace_period: Duration) -> Self {
+        self.stale_grace_seconds = grace_period.as_secs();
+        self
+    }
+
+    /// Configures the function which computes the duration of entries inserted with
+    /// `TimedMap::insert`, so the TTL policy lives in one place instead of being scattered
+    /// across call sites.
+    ///
+    /// Returning `None` stores the entry as constant.
+    pub fn ttl_fn<F>(mut self, ttl_fn: F) -> Self
+    where
+        F: Fn(&K, &V) -> Option<Duration> + Send + Sync + 'static,
+    {
+        self.ext_mut().ttl_fn = Some(Box::new(ttl_fn));
+        self
+    }
+
+    /// Registers a rule which gives entries inserted with `TimedMap::insert` the `duration`
+    /// if their key matches `predicate`.
+    ///
+    /// Rules are checked in the order they are registered and the first match wins. Entries
+    /// matching no rule fall back to the function configured by `TimedMap::ttl_fn`.
+    pub fn ttl_rule<P>(mut self, predicate: P, duration: Duration) -> Self
+    where
+        P: Fn(&K) -> bool + Send + Sync + 'static,
+    {
+        self.ext_mut()
+            .ttl_rules
+            .push((Box::new(predicate), duration));
+        self
+    }
+
+    /// Registers a rule which gives entries inserted with `TimedMap::insert` the `duration`
+    /// if their key starts with `prefix`, e.g., `img:` or `api:`.
+    ///
+    /// See `TimedMap::ttl_rule` for how rules are applied.
+    pub fn ttl_prefix_rule<P>(self, prefix: P, duration: Duration) -> Self
+    where
+        K: AsRef<str>,
+        P: AsRef<str> + Send + Sync + 'static,
+    {
+        self.ttl_rule(
+            move |k: &K| k.as_ref().starts_with(prefix.as_ref()),
+            duration,
+        )
+    }
+
+    /// Configures a hook which gets the key, the value and the expiration of every entry right
+    /// after it's inserted, replaced or updated in place, so derived structures (e.g., search
+    /// indexes or size accounting) can be kept in lockstep with the map.
+    ///
+    /// Like `StorageHook::on_insert`, it's not called for changes made through mutable
+    /// references handed out by the map.
+    pub fn insert_hook<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(&K, &V, Expiry) + Send + Sync + 'static,
+    {
+        self.ext_mut().insert_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Registers a `StorageHook` which gets notified about inserts, removals and expirations
+    /// so they can be mirrored into an external store.
+    pub fn storage_hook<H>(mut self, hook: H) -> Self
+    where
+        H: StorageHook<K, V> + Send + Sync + 'static,
+    {
+        self.ext_mut().storage_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Registers a `SecondaryStore` which `TimedMap::get_or_load` consults on misses.
+    pub fn secondary_store<S>(mut self, store: S) -> Self
+    where
+        S: SecondaryStore<K, V> + Send + Sync + 'static,
+    {
+        self.ext_mut().secondary_store = Some(Box::new(store));
+        self
+    }
+
+    /// Registers a `Refresher` which `TimedMap::get_with_refresh` calls for entries that
+    /// expire within `threshold`.
+    pub fn refresh_ahead<R>(mut self, threshold: Duration, refresher: R) -> Self
+    where
+        R: Refresher<K, V> + Send + Sync + 'static,
+    {
+        self.ext_mut().refresher = Some((threshold, Box::new(refresher)));
+        self
+    }
+
+    /// Names the map, so it can be told apart in `Debug` output, tracing events, log lines and
+    /// metrics when many maps are in use.
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.ext_mut().name = Some(name);
+        self
+    }
+
+    /// Returns the name configured by `TimedMap::name`.
+    pub fn get_name(&self) -> Option<&'static str> {
+        self.ext.as_ref().and_then(|ext| ext.name)
+    }
+
+    /// Returns the name configured by `TimedMap::name`, or `"unnamed"` if there is none.
+    #[cfg(any(feature = "tracing", feature = "log"))]
+    #[inline(always)]
+    fn label(&self) -> &'static str {
+        self.get_name().unwrap_or("unnamed")
+    }
+
+    /// Enables an audit log which keeps the last `capacity` inserts, removals and expirations,
+    /// retrievable with `TimedMap::recent_ops`.
+    pub fn audit_log(mut self, capacity: usize) -> Self {
+        self.ext_mut().audit_log = Some(AuditLog::new(capacity));
+        self
+    }
+
+    /// Enables a histogram of the remaining lifetimes of the non-expired expirable entries,
+    /// retrievable with `TimedMap::ttl_histogram`.
+    ///
+    /// `bounds` are the inclusive upper bounds of the buckets, and the histogram holds one
+    /// more bucket at the end for the entries outliving the last bound. Constant entries are
+    /// not counted.
+    pub fn ttl_histogram_buckets(mut self, bounds: &[Duration]) -> Self {
+        let histogram = TtlHistogram::new(bounds, self.expiration_now(), &self.expiries);
+        self.ext_mut().ttl_histogram = Some(histogram);
+        self
+    }
+
+    /// Returns the operations recorded by the audit log from the oldest to the most recent,
+    /// or nothing if it's not enabled with `TimedMap::audit_log`.
+    pub fn recent_ops(&self) -> impl Iterator<Item = &AuditRecord<K>> {
+        self.ext
+            .iter()
+            .flat_map(|ext| ext.audit_log.iter().flat_map(AuditLog::iter))
+    }
+
+    /// Enables change tracking, which records the keys mutated since the last
+    /// `TimedMap::take_changes` call.
+    pub fn track_changes(mut self) -> Self {
+        self.ext_mut().changes = Some(BTreeMap::new());
+        self
+    }
+
+    /// Returns the keys inserted, updated, removed or expired since the previous call, so
+    /// the deltas can be synchronized to an external system.
+    ///
+    /// Multiple changes of a key are merged into the latest one, except updates of newly
+    /// inserted keys which are still reported as inserts. Returns nothing if change tracking
+    /// isn't enabled with `TimedMap::track_changes`.
+    pub fn take_changes(&mut self) -> BTreeMap<K, Change> {
+        self.ext
+            .as_mut()
+            .and_then(|ext| ext.changes.as_mut())
+            .map(core::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Registers a `MetricsSink` which gets notified about lookups, inserts, removals
+    /// and expirations.
+    pub fn metrics_sink<S>(mut self, sink: S) -> Self
+    where
+        S: MetricsSink + Send + Sync + 'static,
+    {
+        self.ext_mut().metrics_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Returns a snapshot of the hit, miss, insert, removal and expiration counters.
+    ///
+    /// Lookups are counted by `TimedMap::get` and the functions built on top of it, while
+    /// `*_unchecked` lookups are not counted.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    /// Resets all counters reported by `TimedMap::stats` to zero.
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&self) {
+        self.stats.reset();
+    }
+
+    /// Returns the optional state of the map, allocating it on first use.
+    #[inline(always)]
+    fn ext_mut(&mut self) -> &mut Extensions<K, V> {
+        self.ext.get_or_insert_with(Box::default)
+    }
+
+    /// Returns the current time of the clock, in seconds.
+    #[inline(always)]
+    pub(crate) fn now_seconds(&self) -> u64 {
+        self.clock.elapsed_seconds_since_creation()
+    }
+
+    /// Returns how long until an expiration pass would drop the soonest expiring entry, or
+    /// `None` if there is no expirable entry or expiration is suspended.
+    #[cfg(all(feature = "std", feature = "wasm"))]
+    pub(crate) fn next_cleanup_in(&self) -> Option<Duration> {
+        if self.expiration_suspended_at.is_some() {
+            return None;
+        }
+
+        // Entries are expired once the clock passes their deadline and the grace period.
+        let (expires_at, _) = self.expiries.first_key_value()?;
+        Some(
+            self.clock
+                .duration_until(expires_at + self.stale_grace_seconds + 1),
+        )
+    }
+
+    /// Returns the time entries are checked against for expiration, in seconds, which is held
+    /// at the moment expiration was suspended until it's resumed.
+    #[inline(always)]
+    pub(crate) fn expiration_now(&self) -> u64 {
+        let now = self.clock.elapsed_seconds_since_creation();
+
+        match self.expiration_suspended_at {
+            Some(suspended_at) => now.min(suspended_at),
+            None => now,
+        }
+    }
+
+    /// Suspends expiration, e.g., during a maintenance window: entries whose deadlines pass
+    /// from now on are still returned by reads, and cleanups are paused. Entries which have
+    /// already expired stay expired.
+    ///
+    /// Deadlines of new and updated entries are still based on the clock.
+    pub fn suspend_expiration(&mut self) {
+        if self.expiration_suspended_at.is_none() {
+            self.expiration_suspended_at = Some(self.clock.elapsed_seconds_since_creation());
+        }
+    }
+
+    /// Resumes expiration suspended by `TimedMap::suspend_expiration`, and drops the entries
+    /// whose deadlines passed in the meantime.
+    pub fn resume_expiration(&mut self) {
+        self.expiration_suspended_at = None;
+        self.drop_expired_entries();
+    }
+
+    /// Returns `true` if expiration is suspended by `TimedMap::suspend_expiration`.
+    pub fn is_expiration_suspended(&self) -> bool {
+        self.expiration_suspended_at.is_some()
+    }
+
+    /// Moves the deadline of the entry of `k` to `expires_at`, or makes it constant if it's
+    /// `None`, keeping the expiration index in sync.
+    ///
+    /// Returns `false` if there is no entry for `k`.
+    pub(crate) fn set_expires_at(&mut self, k: &K, expires_at: Option<u64>) -> bool {
+        let Some(entry) = self.map.get_mut(k) else {
+            return false;
+        };
+
+        let previous = *entry.status();
+        entry.set_expires_at(expires_at);
+
+        let mut state = self.state();
+        state.take_pin(k);
+        state.reindex(k, Some(previous), expires_at);
+
+        true
+    }
+
+    /// Same as `TimedMap::set_expires_at`, notifying the registered observers about the
+    /// update.
+    pub(crate) fn update_expires_at(&mut self, k: &K, expires_at: Option<u64>) -> bool {
+        let updated = self.set_expires_at(k, expires_at);
+        if updated {
+            self.notify_updated(k);
+        }
+
+        updated
+    }
+
+    /// Bumps the version of the entry of `k` after it's updated in place, and notifies the
+    /// registered observers.
+    pub(crate) fn notify_updated<Q>(&mut self, k: &Q)
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        let now = self.clock.elapsed_seconds_since_creation();
+        let Some(entry) = self.map.get_mut(k) else {
+            return;
+        };
+
+        entry.bump_version();
+
+        // Observers need the stored key, which can't be borrowed along with the entry.
+        if self.ext.is_some() {
+            if let Some((key, entry)) = self.map.get_key_value(k) {
+                notify_observers(&mut self.ext, key, entry, now);
+            }
+        }
+    }
+
+    /// Bumps the version of the entry of `k` and records it as updated before mutable access
+    /// to its value is handed out.
+    ///
+    /// Hooks are not notified since they would only see the value before it's changed.
+    pub(crate) fn mark_updated<Q>(&mut self, k: &Q)
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        if let Some(entry) = self.map.get_mut(k) {
+            entry.bump_version();
+        }
+
+        if let (Some(ext), Some((key, _))) = (self.ext.as_mut(), self.map.get_key_value(k)) {
+            ext.track_change(key, Change::Updated);
+        }
+    }
+
+    /// Makes the entry of `k` expire after `duration` from now, or constant if it's `None`,
+    /// and returns its previous status.
+    ///
+    /// Returns `None` without changing anything if the entry does not exist or is expired.
+    pub fn update_expiration_status(
+        &mut self,
+        k: &K,
+        duration: Option<Duration>,
+    ) -> Option<EntryStatus> {
+        self.try_update_expiration_status(k, duration).ok()
+    }
+
+    /// Same as `TimedMap::update_expiration_status`, telling apart missing and expired entries
+    /// on failure.
+    ///
+    /// Fails with `TimedMapError::InvalidDuration` if `duration` is too long to expire.
+    pub fn try_update_expiration_status(
+        &mut self,
+        k: &K,
+        duration: Option<Duration>,
+    ) -> Result<EntryStatus, TimedMapError> {
+        let previous = self.status(k).ok_or_else(|| self.lookup_error(k))?;
+        let expires_at = duration
+            .map(|d| expires_at_after(self.now_seconds(), d))
+            .transpose()?;
+        self.update_expires_at(k, expires_at);
+
+        Ok(previous)
+    }
+
+    /// Makes all non-expired expirable entries expire after `duration` in a single pass over
+    /// the expiration index, and returns how many were updated.
+    ///
+    /// Constant entries are kept as they are.
+    pub fn set_ttl_for_all(&mut self, duration: Duration) -> usize {
+        self.set_ttl_where(|_, _| true, duration)
+    }
+
+    /// Makes the non-expired expirable entries matching `predicate` expire after `duration` in
+    /// a single pass over the expiration index, and returns how many were updated.
+    ///
+    /// Constant entries are kept as they are.
+    pub fn set_ttl_where<F>(&mut self, mut predicate: F, duration: Duration) -> usize
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        let now = self.expiration_now();
+        let clock_now = self.clock.elapsed_seconds_since_creation();
+        let expires_at = saturating_after(clock_now, duration);
+
+        let mut updated = BTreeSet::new();
+        for (_, keys) in self.expiries.range_mut(now..) {
+            keys.retain(|k| {
+                let Some(entry) = self.map.get_mut(k) else {
+                    return true;
+                };
+
+                if !predicate(k, entry.value()) {
+                    return true;
+                }
+
+                entry.set_expires_at(Some(expires_at));
+                on_updated(&mut self.ext, k, entry, clock_now);
+                updated.insert(k.clone());
+                false
+            });
+        }
+
+        self.expiries.retain(|_, keys| !keys.is_empty());
+
+        let count = updated.len();
+        if count > 0 {
+            self.expiries
+                .entry(expires_at)
+                .or_default()
+                .append(&mut updated);
+
+            if let Some(histogram) = ttl_histogram(&mut self.ext) {
+                histogram.rebuild(now, &self.expiries);
+            }
+        }
+
+        count
+    }
+
+    /// Extends the lifetime of all non-expired expirable entries by `duration` in a single
+    /// pass over the expiration index, and returns how many were extended.
+    ///
+    /// Constant entries are kept as they are.
+    pub fn extend_all(&mut self, duration: Duration) -> usize {
+        let now = self.expiration_now();
+        let clock_now = self.clock.elapsed_seconds_since_creation();
+        let extension = duration.as_secs();
+        let mut count = 0;
+
+        // Deadlines saturating at the end of the clock's range collapse into the same bucket,
+        // so buckets are merged while the index is rebuilt.
+        let expiries = core::mem::take(&mut self.expiries);
+        for (expires_at, mut keys) in expiries {
+            let expires_at = if expires_at < now {
+                expires_at
+            } else {
+                let extended = expires_at.saturating_add(extension);
+                for k in &keys {
+                    if let Some(entry) = self.map.get_mut(k) {
+                        entry.set_expires_at(Some(extended));
+                        on_updated(&mut self.ext, k, entry, clock_now);
+                    }
+                }
+
+                count += keys.len();
+                extended
+            };
+
+            self.expiries
+                .entry(expires_at)
+                .or_default()
+                .append(&mut keys);
+        }
+
+        if let Some(histogram) = ttl_histogram(&mut self.ext) {
+            histogram.rebuild(now, &self.expiries);
+        }
+
+        count
+    }
+
+    /// Calls `f` on every non-expired entry to mutate or replace its value in place, keeping
+    /// its expiration. The clock is read once for the whole pass.
+    pub fn map_values_in_place<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V),
+    {
+        let _ = self.try_map_values_in_place(|k, v| {
+            f(k, v);
+            Ok::<(), Infallible>(())
+        });
+    }
+
+    /// Calls `f` on every non-expired entry to mutate or replace its value in place, keeping
+    /// its expiration. The clock is read once for the whole pass.
+    ///
+    /// Stops at the first error and returns it, leaving the values already visited as `f`
+    /// left them.
+    pub fn try_map_values_in_place<E, F>(&mut self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&K, &mut V) -> Result<(), E>,
+    {
+        let now = self.expiration_now();
+        let clock_now = self.clock.elapsed_seconds_since_creation();
+        let ext = &mut self.ext;
+        self.map.try_for_each_mut(|k, entry| {
+            if entry.is_expired(now) {
+                return Ok(());
+            }
+
+            f(k, entry.value_mut())?;
+            on_updated(ext, k, entry, clock_now);
+
+            Ok(())
+        })
+    }
+
+    /// Pins the entry of `k` so it doesn't expire, e.g., while a long-running operation
+    /// depends on it, until it's unpinned by `TimedMap::unpin`.
+    ///
+    /// Inserting or removing the entry drops the pin. Returns `false` if there is no
+    /// non-expired entry for `k`.
+    pub fn pin(&mut self, k: &K) -> bool {
+        let now = self.expiration_now();
+        let Some(entry) = self.map.get(k).filter(|v| !v.is_expired(now)) else {
+            return false;
+        };
+
+        if self.is_pinned(k) {
+            return true;
+        }
+
+        let remaining = entry.remaining_duration(now).map(|d| d.as_secs());
+        self.set_expires_at(k, None);
+        self.ext_mut().pinned.insert(k.clone(), remaining);
+
+        true
+    }
+
+    /// Unpins the entry of `k`, which then resumes expiring with the remaining duration it had
+    /// when it was pinned.
+    ///
+    /// Returns `false` if the entry is not pinned.
+    pub fn unpin(&mut self, k: &K) -> bool {
+        let Some(remaining) = self.take_pin(k) else {
+            return false;
+        };
+
+        let now = self.clock.elapsed_seconds_since_creation();
+        self.set_expires_at(k, remaining.map(|remaining| now.saturating_add(remaining)))
+    }
+
+    /// Unpins the entry of `k` and makes it expire after `duration` from now, or makes it
+    /// constant if it's `None`.
+    ///
+    /// Returns `false` if the entry is not pinned.
+    pub fn unpin_with_duration(&mut self, k: &K, duration: Option<Duration>) -> bool {
+        if self.take_pin(k).is_none() {
+            return false;
+        }
+
+        let now = self.clock.elapsed_seconds_since_creation();
+        self.set_expires_at(k, duration.map(|duration| saturating_after(now, duration)))
+    }
+
+    /// Returns `true` if the entry of `k` is pinned by `TimedMap::pin`.
+    pub fn is_pinned(&self, k: &K) -> bool {
+        self.pinned_remaining(k).is_some()
+    }
+
+    /// Returns the remaining lifetime, in seconds, the entry of `k` had when it was pinned, or
+    /// `None` if it's not pinned.
+    #[inline(always)]
+    pub(crate) fn pinned_remaining(&self, k: &K) -> Option<Option<u64>> {
+        self.ext.as_ref().and_then(|ext| ext.pinned.get(k).copied())
+    }
+
+    /// Drops the pin of the entry of `k` and returns what `TimedMap::pinned_remaining` reported.
+    #[inline(always)]
+    fn take_pin<Q>(&mut self, k: &Q) -> Option<Option<u64>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.state().take_pin(k)
+    }
+
+    /// Returns the associated value if present and not expired.
+    ///
+    /// To retrieve the value without checking expiration, use `TimedMap::get_unchecked`.
+    ///
+    /// The key may be any borrowed form of the map's key type (e.g., `&str` for `String`
+    /// keys), like in the standard maps.
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        let now = self.expiration_now();
+        let Some(entry) = self.map.get(k) else {
+            self.record(MetricEvent::Miss);
+            return None;
+        };
+
+        if entry.is_expired(now) {
+            self.record(MetricEvent::ExpiredOnRead);
+            return None;
+        }
+
+        entry.touch(now);
+        self.record(MetricEvent::Hit);
+        Some(entry.value())
+    }
+
+    /// Returns `true` if the map holds a non-expired entry for `k`.
+    ///
+    /// Unlike `TimedMap::get`, the lookup is not counted in `TimedMap::stats`.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        let now = self.expiration_now();
+        self.map.get(k).is_some_and(|v| !v.is_expired(now))
+    }
+
+    /// Same as `TimedMap::get`, telling apart missing and expired entries on failure.
+    pub fn try_get<Q>(&self, k: &Q) -> Result<&V, TimedMapError>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        self.get(k).ok_or_else(|| self.lookup_error(k))
+    }
+
+    /// Returns why the entry of `k` can't be used, assuming it's missing or expired.
+    #[inline(always)]
+    fn lookup_error<Q>(&self, k: &Q) -> TimedMapError
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        match self.map.get(k) {
+            Some(_) => TimedMapError::EntryExpired,
+            None => TimedMapError::EntryNotFound,
+        }
+    }
+
+    /// Same as `TimedMap::get`, without counting the lookup in `TimedMap::stats`.
+    #[inline(always)]
+    fn get_inner<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        let now = self.expiration_now();
+        self.map
+            .get(k)
+            .filter(|v| !v.is_expired(now))
+            .inspect(|v| v.touch(now))
+            .map(|v| v.value())
+    }
+
+    /// Returns the associated value if present and either not expired or expired within the
+    /// grace period configured by `TimedMap::stale_grace_period`.
+    ///
+    /// The returned flag is `true` if the value is stale, i.e., it has expired and should be
+    /// refreshed by the caller.
+    pub fn get_stale(&self, k: &K) -> Option<(&V, bool)> {
+        let entry = self.map.get(k)?;
+        let now = self.expiration_now();
+
+        if !entry.is_expired(now) {
+            entry.touch(now);
+            return Some((entry.value(), false));
+        }
+
+        if !entry.is_expired(now.saturating_sub(self.stale_grace_seconds)) {
+            entry.touch(now);
+            return Some((entry.value(), true));
+        }
+
+        None
+    }
+
+    /// Returns the associated value if present along with its `Freshness`, regardless of
+    /// whether it is expired.
+    ///
+    /// Unlike `TimedMap::get_unchecked`, this tells whether the value is fresh (and for how
+    /// long it stays so) or stale (and for how long it has been), for fallback paths that
+    /// prefer stale data over no data.
+    pub fn get_with_freshness(&self, k: &K) -> Option<(&V, Freshness)> {
+        let now = self.expiration_now();
+        self.map.get(k).map(|v| (v.value(), v.freshness(now)))
+    }
+
+    /// Returns the associated value if present and not expired, otherwise loads it from
+    /// the registered `SecondaryStore` and promotes it into the map.
+    ///
+    /// The registered `StorageHook` is not notified about promotions since the value
+    /// already comes from the secondary store.
+    ///
+    /// Each call is counted once in `TimedMap::stats`, as a hit if the value was in the map
+    /// and as a miss if it had to be loaded.
+    ///
+    /// Behaves like `TimedMap::get` if no `SecondaryStore` is registered.
+    pub fn get_or_load(&mut self, k: &K) -> Option<&V> {
+        if self.get(k).is_none() {
+            let ext = self.ext.as_mut()?;
+            let (v, duration) = ext.secondary_store.as_mut()?.load(k)?;
+
+            let hook = ext.storage_hook.take();
+            match duration {
+                Some(duration) => self.insert_expirable(k.clone(), v, duration),
+                None => self.insert_constant(k.clone(), v),
+            };
+            self.ext_mut().storage_hook = hook;
+        }
+
+        self.get_inner(k)
+    }
+
+    /// Returns the associated value if present and not expired, refreshing it first with the
+    /// registered `Refresher` if it expires within the configured threshold.
+    ///
+    /// Behaves like `TimedMap::get` if no `Refresher` is registered.
+    pub fn get_with_refresh(&mut self, k: &K) -> Option<&V> {
+        let now = self.expiration_now();
+        if let (Some((threshold, refresher)), Some(entry)) = (
+            self.ext.as_mut().and_then(|ext| ext.refresher.as_mut()),
+            self.map.get(k),
+        ) {
+            let expires_soon = matches!(
+                entry.remaining_duration(now),
+                Some(remaining) if remaining <= *threshold
+            );
+
+            if !entry.is_expired(now) && expires_soon {
+                if let Some((v, duration)) = refresher.refresh(k, entry.value()) {
+                    self.insert_expirable(k.clone(), v, duration);
+                }
+            }
+        }
+
+        self.get(k)
+    }
+
+    /// Returns mutable access to the associated value if present and not expired, after
+    /// making it expire after `duration` from now, with a single lookup.
+    ///
+    /// Constant and pinned entries become expirable. The registered hooks are notified about
+    /// the refreshed lifetime before the value is returned, so they don't see changes made
+    /// through the returned reference.
+    pub fn get_mut_and_refresh(&mut self, k: &K, duration: Duration) -> Option<&mut V> {
+        let now = self.expiration_now();
+        let clock_now = self.clock.elapsed_seconds_since_creation();
+        let expires_at = saturating_after(clock_now, duration);
+
+        let entry = self.map.get_mut(k).filter(|v| !v.is_expired(now))?;
+        let previous = *entry.status();
+        entry.set_expires_at(Some(expires_at));
+        if let Some(ext) = self.ext.as_mut() {
+            ext.pinned.remove(k);
+        }
+
+        if previous != EntryStatus::ExpiresAtSeconds(expires_at) {
+            let histogram = ttl_histogram(&mut self.ext);
+            if let EntryStatus::ExpiresAtSeconds(previous) = previous {
+                if unindex(&mut self.expiries, k, previous).is_some() {
+                    self.expirable_len -= 1;
+                    if let Some(histogram) = histogram {
+                        histogram.remove(previous, 1);
+                    }
+                }
+            }
+
+            if self
+                .expiries
+                .entry(expires_at)
+                .or_default()
+                .insert(k.clone())
+            {
+                self.expirable_len += 1;
+                if let Some(histogram) = ttl_histogram(&mut self.ext) {
+                    histogram.add(expires_at, 1);
+                }
+            }
+        }
+
+        on_updated(&mut self.ext, k, entry, clock_now);
+        Some(entry.value_mut())
+    }
+
+    /// Returns mutable access to the associated value if present and not expired, inserting
+    /// `V::default()` which expires after `duration` first otherwise.
+    ///
+    /// Expired entries are replaced, so aggregations always start from an empty value.
+    pub fn get_or_insert_default(&mut self, k: &K, duration: Duration) -> &mut V
+    where
+        V: Default,
+    {
+        self.entry_ref(k)
+            .or_insert_with_expirable(V::default, duration)
+    }
+
+    /// Extends the deadline of the entry of `k` by `duration` and merges `v` into its value
+    /// with `merge` if present and not expired, or inserts `v` which expires after `duration`
+    /// otherwise.
+    ///
+    /// Constant entries stay constant, and pinned entries resume with the extended duration
+    /// once unpinned. Pass `|_, _| {}` as `merge` to keep the existing value. The registered
+    /// hooks are notified once `merge` is done, so they don't see changes made through the
+    /// returned reference.
+    pub fn insert_or_extend<F>(&mut self, k: K, v: V, duration: Duration, merge: F) -> &mut V
+    where
+        F: FnOnce(&mut V, V),
+    {
+        if !self.contains_key(&k) {
+            self.insert_expirable(k.clone(), v, duration);
+            return self
+                .get_unchecked_mut(&k)
+                .expect("entry must exist after insertion");
+        }
+
+        let now = self.clock.elapsed_seconds_since_creation();
+        let extension = duration.as_secs();
+
+        if let Some(EntryStatus::ExpiresAtSeconds(expires_at)) = self.status(&k) {
+            self.set_expires_at(&k, Some(expires_at.saturating_add(extension)));
+        }
+
+        if let Some(Some(remaining)) = self.ext.as_mut().and_then(|ext| ext.pinned.get_mut(&k)) {
+            *remaining = remaining.saturating_add(extension);
+        }
+
+        let entry = self.map.get_mut(&k).expect("entry must exist");
+        merge(entry.value_mut(), v);
+        on_updated(&mut self.ext, &k, entry, now);
+
+        entry.value_mut()
+    }
+
+    /// Returns the associated value if present, regardless of whether it is expired.
+    ///
+    /// If you only want non-expired entries, use `TimedMap::get` instead.
+    #[inline(always)]
+    pub fn get_unchecked<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        self.map.get(k).map(|v| v.value())
+    }
+
+    /// Returns mutable access to the associated value if present, regardless of whether it
+    /// is expired.
+    #[inline(always)]
+    pub(crate) fn get_unchecked_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        self.map.get_mut(k).map(|v| v.value_mut())
+    }
+
+    /// Returns the keys of all non-expired entries in ascending order.
+    ///
+    /// Useful for deterministic output (e.g., snapshots or tests) when the map is backed by
+    /// a hash map implementation.
+    pub fn sorted_keys(&self) -> Vec<K> {
+        self.iter_sorted().map(|(k, _)| k.clone()).collect()
+    }
+
+    /// Returns an iterator over all non-expired entries ordered by their keys.
+    ///
+    /// Entries are collected and sorted once internally, which is skipped when the map is
+    /// already backed by `BTreeMap`.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (&K, &V)> {
+        let now = self.expiration_now();
+        let mut entries: Vec<(&K, &V)> = self
+            .map
+            .iter()
+            .filter(|(_, v)| !v.is_expired(now))
+            .map(|(k, v)| (k, v.value()))
+            .collect();
+
+        if !matches!(self.map, GenericMap::BTreeMap(_)) {
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        }
+
+        entries.into_iter()
+    }
+
+    /// Returns the non-expired entries whose keys start with `prefix`, for string or byte keys.
+    ///
+    /// Maps backed by `MapKind::BTreeMap` only walk the matching key range, in key order, while
+    /// the other implementations scan all entries.
+    pub fn iter_prefix<'a, Q>(&'a self, prefix: &'a Q) -> impl Iterator<Item = (&'a K, &'a V)>
+    where
+        K: Borrow<Q>,
+        Q: AsRef<[u8]> + Ord + ?Sized,
+    {
+        let now = self.expiration_now();
+        let matches = move |k: &K| k.borrow().as_ref().starts_with(prefix.as_ref());
+        let (entries, sorted) = self.map.iter_from(prefix);
+
+        // Keys sharing a prefix are contiguous in sorted maps, so the walk ends at the first
+        // key without it.
+        entries
+            .take_while(move |(k, _)| !sorted || matches(k))
+            .filter(move |(k, v)| matches(k) && !v.is_expired(now))
+            .map(|(k, v)| (k, v.value()))
+    }
+
+    /// Returns an iterator over all non-expired entries along with their remaining durations,
+    /// reading the clock only once.
+    ///
+    /// Remaining duration is `None` for constant entries.
+    pub(crate) fn iter_with_remaining_durations(
+        &self,
+    ) -> impl Iterator<Item = (&K, &V, Option<Duration>)> {
+        self.iter_live_at(self.expiration_now())
+    }
+
+    /// Same as `TimedMap::iter_with_remaining_durations`, relative to `now` (as returned by
+    /// `TimedMap::expiration_now`), so multiple passes can agree on which entries are live.
+    #[inline(always)]
+    pub(crate) fn iter_live_at(
+        &self,
+        now: u64,
+    ) -> impl Iterator<Item = (&K, &V, Option<Duration>)> {
+        self.map
+            .iter()
+            .filter(move |(_, v)| !v.is_expired(now))
+            .map(move |(k, v)| (k, v.value(), v.remaining_duration(now)))
+    }
+
+    /// Returns an iterator over all entries, including the expired ones that aren't dropped
+    /// yet.
+    #[cfg(feature = "serde")]
+    pub(crate) fn iter_entries(&self) -> impl Iterator<Item = (&K, &ExpirableEntry<V>)> {
+        self.map.iter()
+    }
+
+    /// Converts a deadline in seconds of the clock into seconds since `UNIX_EPOCH`.
+    #[cfg(all(feature = "serde", feature = "std"))]
+    pub(crate) fn absolute_deadline(&self, expires_at: u64) -> u64 {
+        self.clock
+            .system_time_at(expires_at)
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    /// Returns an iterator over all non-expired entries with mutable access to their values,
+    /// reading the clock only once.
+    ///
+    /// Entries are marked as updated like in `TimedMap::mark_updated`.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn iter_live_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        let now = self.expiration_now();
+        let ext = &mut self.ext;
+        self.map
+            .iter_mut()
+            .filter(move |(_, v)| !v.is_expired(now))
+            .map(move |(k, v)| {
+                v.bump_version();
+                if let Some(ext) = ext.as_mut() {
+                    ext.track_change(k, Change::Updated);
+                }
+
+                (k, v.value_mut())
+            })
+    }
+
+    /// Returns the number of non-expired expirable entries per remaining lifetime bucket
+    /// configured by `TimedMap::ttl_histogram_buckets`, or `None` if it's not enabled.
+    ///
+    /// The counts are maintained as entries come and go, and only the deadlines that crossed
+    /// a bucket bound since the previous call are visited to account for the time passed,
+    /// which is why this takes `&mut self`.
+    pub fn ttl_histogram(&mut self) -> Option<&[usize]> {
+        let now = self.expiration_now();
+        let histogram = ttl_histogram(&mut self.ext)?;
+        histogram.advance(now, &self.expiries);
+
+        Some(histogram.counts())
+    }
+
+    /// Returns the keys of non-expired entries expiring within `duration` from now, ordered by
+    /// their deadlines.
+    ///
+    /// Computed from the internal expiration index, so only the entries in that window are
+    /// visited.
+    pub fn keys_expiring_within(&self, duration: Duration) -> Vec<K> {
+        let now = self.expiration_now();
+
+        self.expiries
+            .range(now..=saturating_after(now, duration))
+            .flat_map(|(_, keys)| keys.iter().cloned())
+            .collect()
+    }
+
+    /// Returns the non-expired entries whose deadline is the second of `deadline`, e.g. as
+    /// returned by `TimedMap::expires_at`.
+    ///
+    /// Entries sharing a deadline are grouped in the internal expiration index, so this is a
+    /// single lookup.
+    #[cfg(feature = "std")]
+    pub fn entries_expiring_at(&self, deadline: SystemTime) -> impl Iterator<Item = (&K, &V)> {
+        self.entries_expiring_at_seconds(self.clock.seconds_at(deadline))
+    }
+
+    /// Returns the non-expired entries whose deadline is the second of `deadline`, as elapsed
+    /// since the creation of the clock.
+    ///
+    /// Entries sharing a deadline are grouped in the internal expiration index, so this is a
+    /// single lookup.
+    #[cfg(not(feature = "std"))]
+    pub fn entries_expiring_at(&self, deadline: Duration) -> impl Iterator<Item = (&K, &V)> {
+        self.entries_expiring_at_seconds(Some(deadline.as_secs()))
+    }
+
+    /// Returns the non-expired entries whose deadline is `deadline` in seconds of the clock,
+    /// or nothing if it's `None`.
+    fn entries_expiring_at_seconds(&self, deadline: Option<u64>) -> impl Iterator<Item = (&K, &V)> {
+        let now = self.expiration_now();
+
+        deadline
+            .filter(|deadline| *deadline >= now)
+            .and_then(|deadline| self.expiries.get(&deadline))
+            .into_iter()
+            .flatten()
+            .filter_map(|k| self.map.get(k).map(|v| (k, v.value())))
+    }
+
+    /// Returns the shape of the internal expiration index, to diagnose deadline clustering.
+    pub fn expiry_index_stats(&self) -> ExpiryIndexStats {
+        let span = match (
+            self.expiries.first_key_value(),
+            self.expiries.last_key_value(),
+        ) {
+            (Some((first, _)), Some((last, _))) => Duration::from_secs(last - first),
+            _ => Duration::ZERO,
+        };
+
+        ExpiryIndexStats {
+            buckets: self.expiries.len(),
+            entries: self.expiries.values().map(BTreeSet::len).sum(),
+            largest_bucket: self.expiries.values().map(BTreeSet::len).max().unwrap_or(0),
+            span,
+        }
+    }
+
+    /// Returns the number of non-expired entries.
+    ///
+    /// Runs in O(expired) like `TimedMap::len_expired`.
+    pub fn len(&self) -> usize {
+        self.len_unchecked() - self.len_expired()
+    }
+
+    /// Returns `true` if the map holds no non-expired entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries, including the expired ones that aren't dropped yet.
+    ///
+    /// Runs in O(1).
+    pub fn len_unchecked(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the number of expired entries that aren't dropped yet.
+    ///
+    /// Expirations happen as time passes rather than through calls that could count them, so
+    /// the expiration index buckets already past their deadlines are walked. That's
+    /// O(expired), which cleanups keep short.
+    pub fn len_expired(&self) -> usize {
+        let now = self.expiration_now();
+        self.expiries.range(..now).map(|(_, keys)| keys.len()).sum()
+    }
+
+    /// Returns the number of constant, live expirable and expired but not yet dropped entries,
+    /// e.g. for dashboards.
+    ///
+    /// The constant and expirable totals are tracked as entries come and go, and only the
+    /// expired entries are counted by walking the index like `TimedMap::len_expired`, so it
+    /// runs in O(expired).
+    pub fn count_by_status(&self) -> EntryCounts {
+        let expired_pending = self.len_expired();
+
+        EntryCounts {
+            constant: self.map.len() - self.expirable_len,
+            expirable_live: self.expirable_len - expired_pending,
+            expired_pending,
+        }
+    }
+
+    /// Returns the entry counts along with the time left until the next expiration, as a
+    /// compact value to log.
+    ///
+    /// Runs in O(expired) like `TimedMap::count_by_status`.
+    pub fn summary(&self) -> MapSummary {
+        let now = self.expiration_now();
+        let next_expiration = self
+            .expiries
+            .range(now..)
+            .next()
+            .map(|(expires_at, _)| Duration::from_secs(expires_at - now));
+
+        MapSummary {
+            counts: self.count_by_status(),
+            next_expiration,
+        }
+    }
+
+    /// Verifies that the map and its internal expiration index agree: every expirable entry
+    /// is indexed exactly once under its deadline, the index holds no other keys, and the
+    /// tracked number of expirable entries matches the index.
+    ///
+    /// Meant for asserting integrity in tests, as it walks both structures entirely.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        for (k, v) in self.map.iter() {
+            if let EntryStatus::ExpiresAtSeconds(expires_at) = v.status() {
+                if !self
+                    .expiries
+                    .get(expires_at)
+                    .is_some_and(|keys| keys.contains(k))
+                {
+                    return Err(InvariantViolation::MissingFromIndex);
+                }
+            }
+        }
+
+        for (expires_at, keys) in &self.expiries {
+            if keys.is_empty() {
+                return Err(InvariantViolation::EmptyBucket);
+            }
+
+            for k in keys {
+                match self.map.get(k).map(|v| v.status()) {
+                    Some(EntryStatus::ExpiresAtSeconds(t)) if t == expires_at => {}
+                    _ => return Err(InvariantViolation::DanglingIndexKey),
+                }
+            }
+        }
+
+        if self.expirable_len != self.expiries.values().map(BTreeSet::len).sum::<usize>() {
+            return Err(InvariantViolation::ExpirableCountMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Writes a human-readable report of all entries, one per line, ordered by their deadlines
+    /// with constant entries at the end.
+    ///
+    /// Intended for admin endpoints and debug consoles. Expired entries that aren't dropped
+    /// yet are included and marked as such.
+    pub fn dump<W>(&self, writer: &mut W) -> core::fmt::Result
+    where
+        W: core::fmt::Write,
+        K: core::fmt::Debug,
+    {
+        let now = self.expiration_now();
+        let expirable: usize = self.expiries.values().map(BTreeSet::len).sum();
+        let constant = self
+            .map
+            .iter()
+            .filter(|(_, v)| matches!(v.status(), EntryStatus::Constant))
+            .count();
+
+        writeln!(
+            writer,
+            "{} entries ({expirable} expirable, {constant} constant)",
+            expirable + constant
+        )?;
+
+        for (expires_at, keys) in &self.expiries {
+            for k in keys {
+                if now > *expires_at {
+                    let elapsed = Duration::from_secs(now - expires_at);
+                    writeln!(writer, "{k:?}: expired {elapsed:?} ago")?;
+                } else {
+                    let remaining = Duration::from_secs(expires_at - now);
+                    writeln!(writer, "{k:?}: expires in {remaining:?}")?;
+                }
+            }
+        }
+
+        for (k, v) in self.map.iter() {
+            if let EntryStatus::Constant = v.status() {
+                writeln!(writer, "{k:?}: constant")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the associated value if present and not expired, along with its version.
+    ///
+    /// Versions start from 1 and are bumped whenever the value or the lifetime of the entry is
+    /// updated, including replacements and mutable access handed out by the map, so they can
+    /// be passed to `TimedMap::insert_if_version` for optimistic concurrency.
+    #[cfg(feature = "versions")]
+    pub fn get_versioned(&self, k: &K) -> Option<(&V, u64)> {
+        let now = self.expiration_now();
+        self.map
+            .get(k)
+            .filter(|v| !v.is_expired(now))
+            .inspect(|v| v.touch(now))
+            .map(|v| (v.value(), v.version()))
+    }
+
+    /// Inserts a key-value pair like `TimedMap::insert`, only if the current version of the
+    /// entry matches `expected_version`, which is 0 for absent or expired entries.
+    ///
+    /// Returns the new version on success, or the current version if it doesn't match.
+    #[cfg(feature = "versions")]
+    pub fn insert_if_version(&mut self, k: K, v: V, expected_version: u64) -> Result<u64, u64> {
+        let current_version = self.get_versioned(&k).map_or(0, |(_, version)| version);
+        if current_version != expected_version {
+            return Err(current_version);
+        }
+
+        self.insert(k.clone(), v);
+
+        Ok(self.map.get(&k).map_or(0, |v| v.version()))
+    }
+
+    /// Returns when the associated entry was inserted if present and not expired, as elapsed
+    /// since the creation of the clock.
+    #[cfg(feature = "metadata")]
+    pub fn created_at(&self, k: &K) -> Option<Duration> {
+        let now = self.expiration_now();
+        self.map
+            .get(k)
+            .filter(|v| !v.is_expired(now))
+            .map(|v| Duration::from_secs(v.created_at()))
+    }
+
+    /// Returns when the associated entry was last read (or inserted, if it was never read)
+    /// if present and not expired, as elapsed since the creation of the clock.
+    ///
+    /// Reads through `TimedMap::get`, `TimedMap::get_stale`, `TimedMap::get_versioned` and
+    /// the functions built on top of them count as accesses, while `*_unchecked` reads and
+    /// this function itself don't.
+    #[cfg(feature = "metadata")]
+    pub fn last_accessed(&self, k: &K) -> Option<Duration> {
+        let now = self.expiration_now();
+        self.map
+            .get(k)
+            .filter(|v| !v.is_expired(now))
+            .map(|v| Duration::from_secs(v.last_accessed()))
+    }
+
+    /// Returns the non-expired entry which was inserted longest ago, to monitor whether the
+    /// map is turning over.
+    ///
+    /// Walks all entries, and among entries inserted within the same second, any may be
+    /// returned.
+    #[cfg(feature = "metadata")]
+    pub fn oldest_entry(&self) -> Option<(&K, &V)> {
+        let now = self.expiration_now();
+        self.map
+            .iter()
+            .filter(|(_, v)| !v.is_expired(now))
+            .min_by_key(|(_, v)| v.created_at())
+            .map(|(k, v)| (k, v.value()))
+    }
+
+    /// Returns the non-expired entry which was inserted most recently.
+    ///
+    /// Walks all entries, and among entries inserted within the same second, any may be
+    /// returned.
+    #[cfg(feature = "metadata")]
+    pub fn newest_entry(&self) -> Option<(&K, &V)> {
+        let now = self.expiration_now();
+        self.map
+            .iter()
+            .filter(|(_, v)| !v.is_expired(now))
+            .max_by_key(|(_, v)| v.created_at())
+            .map(|(k, v)| (k, v.value()))
+    }
+
+    /// Returns an iterator over all non-expired entries from the most to the least recently
+    /// accessed, e.g. to export the hot set for pre-warming another cache.
+    ///
+    /// Entries are collected and sorted once internally, and iterating doesn't count as an
+    /// access. Access times are tracked in seconds, so entries accessed within the same
+    /// second are returned in no particular order.
+    #[cfg(feature = "metadata")]
+    pub fn iter_by_recency(&self) -> impl Iterator<Item = (&K, &V)> {
+        let now = self.expiration_now();
+        let mut entries: Vec<(u64, &K, &V)> = self
+            .map
+            .iter()
+            .filter(|(_, v)| !v.is_expired(now))
+            .map(|(k, v)| (v.last_accessed(), k, v.value()))
+            .collect();
+
+        entries.sort_unstable_by_key(|(last_accessed, _, _)| core::cmp::Reverse(*last_accessed));
+
+        entries.into_iter().map(|(_, k, v)| (k, v))
+    }
+
+    /// Returns the associated value's `Duration` if present and not expired.
+    ///
+    /// Returns `None` if the entry does not exist or is constant.
+    pub fn get_remaining_duration<Q>(&self, k: &Q) -> Option<Duration>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        match self.map.get(k) {
+            Some(v) => {
+                let now = self.expiration_now();
+                if v.is_expired(now) {
+                    return None;
+                }
+
+                v.remaining_duration(now)
+            }
+            None => None,
+        }
+    }
+
+    /// Returns the status of the entry of `k` if present and not expired, telling whether it
+    /// is constant or when it expires.
+    pub fn status(&self, k: &K) -> Option<EntryStatus> {
+        let now = self.expiration_now();
+
+        self.map
+            .get(k)
+            .filter(|v| !v.is_expired(now))
+            .map(|v| *v.status())
+    }
+
+    /// Returns the absolute deadline of the entry of `k` if present and not expired.
+    ///
+    /// Returns `None` if the entry does not exist or is constant.
+    #[cfg(feature = "std")]
+    pub fn expires_at(&self, k: &K) -> Option<SystemTime> {
+        match self.status(k)? {
+            EntryStatus::Constant => None,
+            EntryStatus::ExpiresAtSeconds(t) => Some(self.clock.system_time_at(t)),
+        }
+    }
+
+    /// Returns the absolute deadline of the entry of `k` in seconds of the map's `Clock`, if
+    /// present and not expired.
+    ///
+    /// Returns `None` if the entry does not exist or is constant.
+    #[cfg(not(feature = "std"))]
+    pub fn expires_at(&self, k: &K) -> Option<u64> {
+        match self.status(k)? {
+            EntryStatus::Constant => None,
+            EntryStatus::ExpiresAtSeconds(t) => Some(t),
+        }
+    }
+
+    /// Inserts a key-value pair with an expiration duration. If duration is `None`,
+    /// entry will be stored in a non-expirable way.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned.
+    #[inline(always)]
+    fn insert_inner(&mut self, k: K, v: V, expires_at: Option<u64>) -> Option<V> {
+        let (map, mut state) = self.split();
+        let (old, _) = state.store(map.entry(k), v, expires_at);
+
+        old.map(|v| v.owned_value())
+    }
+
+    /// Counts a mutation towards `expiration_tick_cap`, and drops the expired entries once
+    /// it's reached.
+    #[inline(always)]
+    pub(crate) fn tick_expiration(&mut self) {
+        self.expiration_tick += 1;
+
+        if self.expiration_tick >= self.expiration_tick_cap {
+            let now = self.clock.elapsed_seconds_since_creation();
+            self.drop_expired_entries_inner(now);
+            self.expiration_tick = 0;
+        }
+    }
+
+    /// Inserts a key-value pair with an expiration duration, and then drops the
+    /// expired entries.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned.
+    ///
+    /// If you don't want to the check expired entries, consider using `TimedMap::insert_expirable_unchecked`
+    /// instead.
     pub fn insert_expirable(&mut self, k: K, v: V, duration: Duration) -> Option<V> {
         self.expiration_tick += 1;
 
-        let now = self.clock.elapsed_seconds_since_creation();
-        let expires_at = now + duration.as_secs();
+        let now = self.clock.elapsed_seconds_since_creation();
+        let expires_at = now + duration.as_secs();
+
+        let res = self.insert_inner(k, v, Some(expires_at));
+
+        if self.expiration_tick >= self.expiration_tick_cap {
+            self.drop_expired_entries_inner(now);
+            self.expiration_tick = 0;
+        }
+
+        res
+    }
+
+    /// Same as `TimedMap::insert_expirable`, failing with `TimedMapError::InvalidDuration`
+    /// instead of inserting if `duration` is too long to expire.
+    pub fn try_insert_expirable(
+        &mut self,
+        k: K,
+        v: V,
+        duration: Duration,
+    ) -> Result<Option<V>, TimedMapError> {
+        let now = self.clock.elapsed_seconds_since_creation();
+        expires_at_after(now, duration)?;
+
+        Ok(self.insert_expirable(k, v, duration))
+    }
+
+    /// Inserts a key-value pair with an expiration duration along with a `callback` which
+    /// runs once the entry is expired, removed or replaced, and then drops the expired entries.
+    ///
+    /// Useful when each entry owns its cleanup logic, e.g., aborting a task whose handle is
+    /// stored in the value. Callbacks don't run when the map is dropped.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned.
+    pub fn insert_expirable_with_callback<F>(
+        &mut self,
+        k: K,
+        v: V,
+        duration: Duration,
+        callback: F,
+    ) -> Option<V>
+    where
+        F: FnOnce(&K, &V) + Send + Sync + 'static,
+    {
+        let old = self.insert_expirable(k.clone(), v, duration);
+        self.ext_mut().callbacks.insert(k, Box::new(callback));
+
+        old
+    }
+
+    /// Inserts a key-value pair with an expiration duration, and then drops the expired
+    /// entries.
+    ///
+    /// Returns the replaced value along with the lifetime the old entry had left, which is
+    /// `None` if it was constant or already expired. Useful for noticing premature
+    /// overwrites.
+    pub fn insert_expirable_full(
+        &mut self,
+        k: K,
+        v: V,
+        duration: Duration,
+    ) -> (Option<V>, Option<Duration>) {
+        let remaining = self.replaced_remaining_duration(&k);
+        (self.insert_expirable(k, v, duration), remaining)
+    }
+
+    /// Returns the remaining `Duration` of the entry about to be replaced, following pinned
+    /// entries by the time they had left when pinned.
+    #[inline(always)]
+    fn replaced_remaining_duration(&self, k: &K) -> Option<Duration> {
+        match self.pinned_remaining(k) {
+            Some(remaining) => remaining.map(Duration::from_secs),
+            _ => self.get_remaining_duration(k),
+        }
+    }
+
+    /// Inserts a key-value pair with an expiration duration, without checking the expired
+    /// entries.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned.
+    ///
+    /// If you want to check the expired entries, consider using `TimedMap::insert_expirable`
+    /// instead.
+    pub fn insert_expirable_unchecked(&mut self, k: K, v: V, duration: Duration) -> Option<V> {
+        let now = self.clock.elapsed_seconds_since_creation();
+        let expires_at = now + duration.as_secs();
+        self.insert_inner(k, v, Some(expires_at))
+    }
+
+    /// Inserts a key-value pair that expires based on the first matching rule registered by
+    /// `TimedMap::ttl_rule`, or else the function configured by `TimedMap::ttl_fn`, and then
+    /// drops the expired entries.
+    ///
+    /// Entries are stored as constant if neither applies or the function returns `None`.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let duration = self.ext.as_ref().and_then(|ext| ext.ttl_for(&k, &v));
+
+        match duration {
+            Some(duration) => self.insert_expirable(k, v, duration),
+            None => self.insert_constant(k, v),
+        }
+    }
+
+    /// Inserts a key-value pair that expires based on the lifetime reported by the value's
+    /// `ExpirableValue` implementation, and then drops the expired entries.
+    ///
+    /// Values reporting no lifetime are stored as constant entries.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned.
+    pub fn insert_auto(&mut self, k: K, v: V) -> Option<V>
+    where
+        V: ExpirableValue,
+    {
+        match v.ttl() {
+            Some(duration) => self.insert_expirable(k, v, duration),
+            None => self.insert_constant(k, v),
+        }
+    }
+
+    /// Inserts all key-value pairs from `iter` with the same expiration duration, without
+    /// checking the expired entries.
+    ///
+    /// The clock is read once for the whole batch, so all entries share the same deadline.
+    /// Useful for migrating existing `HashMap`s or `BTreeMap`s into `TimedMap`.
+    pub fn extend_expirable<I>(&mut self, iter: I, duration: Duration)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let expires_at = saturating_after(self.clock.elapsed_seconds_since_creation(), duration);
+        for (k, v) in iter {
+            self.insert_inner(k, v, Some(expires_at));
+        }
+    }
+
+    /// Inserts a key-value pair with that doesn't expire, and then drops the
+    /// expired entries.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned.
+    ///
+    /// If you don't want to check the expired entries, consider using `TimedMap::insert_constant_unchecked`
+    /// instead.
+    pub fn insert_constant(&mut self, k: K, v: V) -> Option<V> {
+        self.expiration_tick += 1;
+        let res = self.insert_inner(k, v, None);
+
+        let now = self.clock.elapsed_seconds_since_creation();
+        if self.expiration_tick >= self.expiration_tick_cap {
+            self.drop_expired_entries_inner(now);
+            self.expiration_tick = 0;
+        }
+
+        res
+    }
+
+    /// Inserts a key-value pair that doesn't expire, and then drops the expired entries.
+    ///
+    /// Returns the replaced value along with the lifetime the old entry had left, which is
+    /// `None` if it was constant or already expired.
+    pub fn insert_constant_full(&mut self, k: K, v: V) -> (Option<V>, Option<Duration>) {
+        let remaining = self.replaced_remaining_duration(&k);
+        (self.insert_constant(k, v), remaining)
+    }
+
+    /// Inserts a key-value pair expiring at the same deadline as the entry of `template`, or
+    /// never if that one is constant, and then drops the expired entries.
+    ///
+    /// Pinned templates are followed by the deadline they would have if unpinned now.
+    ///
+    /// If a value already exists for the given key, it will be updated and then the old one
+    /// will be returned. Fails without inserting if the template entry does not exist or is
+    /// expired.
+    pub fn insert_like(&mut self, template: &K, k: K, v: V) -> Result<Option<V>, TimedMapError> {
+        let now = self.clock.elapsed_seconds_since_creation();
+        let status = self
+            .status(template)
+            .ok_or_else(|| self.lookup_error(template))?;
+
+        let expires_at = match (self.pinned_remaining(template), status) {
+            (Some(remaining), _) => remaining.map(|remaining| now + remaining),
+            (None, EntryStatus::ExpiresAtSeconds(expires_at)) => Some(expires_at),
+            (None, EntryStatus::Constant) => None,
+        };
+
+        self.expiration_tick += 1;
+        let res = self.insert_inner(k, v, expires_at);
+
+        if self.expiration_tick >= self.expiration_tick_cap {
+            self.drop_expired_entries_inner(now);
+            self.expiration_tick = 0;
+        }
+
+        Ok(res)
+    }
+
+    /// Inserts a key-value pair with that doesn't expire without checking the expired
+    /// entries.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned.
+    ///
+    /// If you want to check the expired entries, consider using `TimedMap::insert_constant`
+    /// instead.
+    pub fn insert_constant_unchecked(&mut self, k: K, v: V) -> Option<V> {
+        self.expiration_tick += 1;
+        self.insert_inner(k, v, None)
+    }
+
+    /// Removes a key-value pair from the map and returns the associated value if present
+    /// and not expired.
+    ///
+    /// If you want to retrieve the entry after removal even if it is expired, consider using
+    /// `TimedMap::remove_unchecked`.
+    #[inline(always)]
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        let now = self.expiration_now();
+        self.remove_inner(k)
+            .filter(|v| !v.is_expired(now))
+            .map(|v| v.owned_value())
+    }
+
+    /// Same as `TimedMap::remove`, telling apart missing and expired entries on failure.
+    ///
+    /// Expired entries are removed as well.
+    pub fn try_remove<Q>(&mut self, k: &Q) -> Result<V, TimedMapError>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        let now = self.expiration_now();
+        let entry = self.remove_inner(k).ok_or(TimedMapError::EntryNotFound)?;
+
+        if entry.is_expired(now) {
+            return Err(TimedMapError::EntryExpired);
+        }
+
+        Ok(entry.owned_value())
+    }
+
+    /// Removes a key-value pair from the map and returns the associated value if present,
+    /// regardless of expiration status.
+    ///
+    /// If you only want the entry when it is not expired, consider using `TimedMap::remove`.
+    #[inline(always)]
+    pub fn remove_unchecked<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        self.remove_inner(k).map(|v| v.owned_value())
+    }
+
+    /// Removes all entries, including the expired ones that were not dropped yet, and resets
+    /// the expiration index and the expiration tick.
+    ///
+    /// Observers see the expired entries as expirations and the others as removals.
+    pub fn clear(&mut self) {
+        self.clear_with(|_, _| {});
+    }
+
+    /// Same as `TimedMap::clear`, returning the non-expired entries along with their remaining
+    /// durations.
+    ///
+    /// Durations are `None` for constant entries.
+    pub fn clear_into(&mut self) -> Vec<(K, V, Option<Duration>)> {
+        let now = self.expiration_now();
+        let mut drained = Vec::new();
+
+        self.clear_with(|k, entry| {
+            if !entry.is_expired(now) {
+                let duration = entry.remaining_duration(now);
+                drained.push((k, entry.owned_value(), duration));
+            }
+        });
+
+        drained
+    }
+
+    /// Removes all expirable entries, including the expired ones, and keeps the constant ones.
+    pub fn clear_expirable(&mut self) {
+        self.remove_expiring_in(..);
+    }
+
+    /// Removes all constant entries and keeps the expirable ones.
+    pub fn clear_constant(&mut self) {
+        let keys: Vec<K> = self
+            .map
+            .iter()
+            .filter(|(_, v)| matches!(v.status(), EntryStatus::Constant))
+            .map(|(k, _)| k.clone())
+            .collect();
+
+        for k in keys {
+            self.remove_inner(&k);
+        }
+        self.compact_if_sparse();
+    }
+
+    /// Releases the memory the map keeps allocated for entries it no longer holds, e.g., after
+    /// a traffic spike.
+    ///
+    /// Maps backed by hash or vector based implementations keep their capacity after entries
+    /// are removed, while `BTreeMap` and the internal expiration index free it right away.
+    /// This is also done automatically by bulk removals and expiration passes once less than a
+    /// quarter of the capacity is in use.
+    pub fn compact(&mut self) {
+        self.map.shrink_to_fit();
+    }
+
+    /// Returns the inner map implementation in use.
+    ///
+    /// Maps created with `MapKind::VecMap` report `MapKind::HashMap` once they are promoted.
+    #[cfg(feature = "std")]
+    pub fn map_kind(&self) -> MapKind {
+        self.map.kind()
+    }
+
+    /// Moves all entries into a new inner map implementation specified by `map_kind`, e.g., to
+    /// switch a map that started as `MapKind::BTreeMap` to a hash map once it grows.
+    ///
+    /// Entries keep their lifetimes and metadata, and the configuration of the map is kept as
+    /// is. Expired entries that aren't dropped yet are moved as well. Like any other
+    /// `MapKind::VecMap`, the new map is promoted to `MapKind::HashMap` if it holds more than
+    /// 16 entries.
+    #[cfg(feature = "std")]
+    pub fn convert_to(&mut self, map_kind: MapKind) {
+        if self.map.kind() == map_kind {
+            return;
+        }
+
+        let map = core::mem::replace(&mut self.map, GenericMap::with_kind(map_kind));
+        for (k, v) in map {
+            self.map.insert(k, v);
+        }
+    }
+
+    /// Compacts the map if only a small part of its capacity is in use.
+    #[inline(always)]
+    fn compact_if_sparse(&mut self) {
+        if self.map.is_sparse() {
+            self.compact();
+        }
+    }
+
+    /// Takes all entries out of the map at once and resets the internal state tracking them,
+    /// and then passes each entry to `f`.
+    ///
+    /// Observers are notified about each entry, with the expired ones reported as expired.
+    fn clear_with<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K, ExpirableEntry<V>),
+    {
+        let now = self.expiration_now();
+        let map = self.map.take();
+
+        // The index and the pins are dropped in bulk, so entries aren't looked up in them.
+        self.expiries.clear();
+        self.expirable_len = 0;
+        if let Some(ext) = self.ext.as_mut() {
+            ext.pinned.clear();
+        }
+        if let Some(histogram) = ttl_histogram(&mut self.ext) {
+            histogram.rebuild(now, &self.expiries);
+        }
+        self.expiration_tick = 0;
+
+        let mut state = self.state();
+        for (k, entry) in map {
+            if entry.is_expired(now) {
+                state.expired(&k, &entry);
+            } else {
+                state.removed(&k, &entry);
+            }
+
+            f(k, entry);
+        }
+    }
+
+    /// Removes the entries whose deadlines are before `duration` from now, including the
+    /// expired ones, and returns how many were removed.
+    ///
+    /// Entries are found through the internal expiration index, so constant entries and
+    /// entries expiring later are not visited.
+    pub fn remove_expiring_before(&mut self, duration: Duration) -> usize {
+        let deadline = saturating_after(self.clock.elapsed_seconds_since_creation(), duration);
+        self.remove_expiring_in(..deadline)
+    }
+
+    /// Removes the entries whose deadlines are at or after `duration` from now, and returns
+    /// how many were removed. Constant entries are kept.
+    ///
+    /// Entries are found through the internal expiration index, so constant entries and
+    /// entries expiring earlier are not visited.
+    pub fn remove_expiring_after(&mut self, duration: Duration) -> usize {
+        let deadline = saturating_after(self.clock.elapsed_seconds_since_creation(), duration);
+        self.remove_expiring_in(deadline..)
+    }
+
+    /// Removes the entries whose deadlines are in `range` and returns how many were removed.
+    fn remove_expiring_in<R>(&mut self, range: R) -> usize
+    where
+        R: core::ops::RangeBounds<u64>,
+    {
+        let keys: Vec<K> = self
+            .expiries
+            .range(range)
+            .flat_map(|(_, keys)| keys.iter().cloned())
+            .collect();
+
+        let removed = keys
+            .iter()
+            .filter(|k| self.remove_inner(k).is_some())
+            .count();
+        self.compact_if_sparse();
+
+        removed
+    }
+
+    /// Removes the entry of `k` from the map and the expiration index, and notifies the
+    /// registered observers.
+    ///
+    /// Only removals of existing entries are recorded, since misses have no owned key to
+    /// record.
+    #[inline(always)]
+    fn remove_inner<Q>(&mut self, k: &Q) -> Option<ExpirableEntry<V>>
+    where
+        K: Borrow<Q>,
+        Q: GenericQuery + ?Sized,
+    {
+        let (key, entry) = self.map.remove_entry(k)?;
+        self.state().removed(&key, &entry);
+
+        Some(entry)
+    }
+
+    /// Consumes the map and returns a `BTreeMap` containing only the non-expired entries.
+    pub fn into_btree_map(self) -> BTreeMap<K, V> {
+        self.into_live_entries().collect()
+    }
+
+    /// Consumes the map and returns a `HashMap` containing only the non-expired entries.
+    #[cfg(feature = "std")]
+    pub fn into_hash_map(self) -> HashMap<K, V> {
+        self.into_live_entries().collect()
+    }
+
+    /// Consumes the map and returns an iterator over the non-expired entries.
+    fn into_live_entries(self) -> impl Iterator<Item = (K, V)> {
+        let now = self.expiration_now();
+        self.map
+            .into_iter()
+            .filter(move |(_, v)| !v.is_expired(now))
+            .map(|(k, v)| (k, v.owned_value()))
+    }
+
+    /// Consumes the map and returns an iterator over the non-expired entries along with their
+    /// remaining durations, which are `None` for constant entries.
+    fn into_live_entries_with_remaining_durations(
+        self,
+    ) -> impl Iterator<Item = (K, V, Option<Duration>)> {
+        let now = self.expiration_now();
+        self.map
+            .into_iter()
+            .filter(move |(_, v)| !v.is_expired(now))
+            .map(move |(k, v)| {
+                let duration = v.remaining_duration(now);
+                (k, v.owned_value(), duration)
+            })
+    }
+
+    /// Moves the non-expired entries of `other` into the map, keeping their remaining
+    /// durations, and then drops the expired entries.
+    ///
+    /// When a key has non-expired entries in both maps, `resolver` gets the key along with
+    /// the current and the incoming values and remaining durations (`None` for constant
+    /// entries), in that order, and returns the value and duration to keep. The current entry
+    /// is updated in place, and observers see it as a single update.
+    ///
+    /// The expired entries of `other` are dropped first, notifying its own observers and
+    /// callbacks. Pinned entries of `other` are merged unpinned with the remaining duration
+    /// they had when pinned. The callbacks of the merged entries are not carried over, and
+    /// are dropped without running like the rest of the configuration of `other`.
+    pub fn merge<C2, F>(&mut self, mut other: TimedMap<C2, K, V>, mut resolver: F)
+    where
+        C2: Clock,
+        F: FnMut(&K, (V, Option<Duration>), (V, Option<Duration>)) -> (V, Option<Duration>),
+    {
+        other.drop_expired_entries();
+        let pinned = other
+            .ext
+            .as_mut()
+            .map(|ext| core::mem::take(&mut ext.pinned))
+            .unwrap_or_default();
+
+        for (k, v, duration) in other.into_live_entries_with_remaining_durations() {
+            let duration = match pinned.get(&k) {
+                Some(remaining) => remaining.map(Duration::from_secs),
+                None => duration,
+            };
+            let now = self.clock.elapsed_seconds_since_creation();
+            let expiration_now = self.expiration_now();
+            let (map, mut state) = self.split();
+
+            let slot = match map.entry(k) {
+                GenericEntry::Occupied(slot) if !slot.get().is_expired(expiration_now) => slot,
+                slot => {
+                    let expires_at = duration.map(|d| saturating_after(now, d));
+                    state.store(slot, v, expires_at);
+                    continue;
+                }
+            };
+
+            // Collisions are updated in place, so the value is only moved out of the map
+            // for the resolver.
+            let (key, entry) = slot.remove_entry();
+            let current_duration = entry.remaining_duration(expiration_now);
+            let previous = *entry.status();
+
+            let mut merged_duration = None;
+            let mut entry = entry.map_value(|current| {
+                let (v, duration) = resolver(&key, (current, current_duration), (v, duration));
+                merged_duration = duration;
+                v
+            });
+
+            let expires_at = merged_duration.map(|d| saturating_after(now, d));
+            entry.set_expires_at(expires_at);
+            state.take_pin(&key);
+            state.reindex(&key, Some(previous), expires_at);
+            on_updated(state.ext, &key, &mut entry, now);
+
+            map.insert(key, entry);
+        }
+
+        self.drop_expired_entries();
+    }
+
+    /// Moves the non-expired entries matching `predicate` into a new map, keeping their
+    /// remaining durations, pins and removal callbacks.
+    ///
+    /// The new map has the same map kind, name, expiration tick cap, stale grace period, audit
+    /// log capacity, change tracking and TTL histogram buckets as this one. The TTL function,
+    /// TTL rules, hooks, secondary store, refresher and metrics sink are boxed and can't be
+    /// cloned, so they stay with this map only.
+    #[cfg(feature = "std")]
+    pub fn partition<F>(&mut self, predicate: F) -> Self
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        let mut other = Self::new_with_map_kind(self.map.kind());
+        self.configure_like(&mut other);
+        self.move_matching_into(&mut other, predicate);
+        other
+    }
+
+    /// Moves the non-expired entries matching `predicate` into a new map using the provided
+    /// `clock`, keeping their remaining durations, pins and removal callbacks.
+    ///
+    /// The new map has the same name, expiration tick cap, stale grace period, audit log
+    /// capacity, change tracking and TTL histogram buckets as this one. The TTL function, TTL
+    /// rules, hooks, secondary store, refresher and metrics sink are boxed and can't be
+    /// cloned, so they stay with this map only.
+    #[cfg(not(feature = "std"))]
+    pub fn partition<F>(&mut self, clock: C, predicate: F) -> Self
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        let mut other = Self::new(clock);
+        self.configure_like(&mut other);
+        self.move_matching_into(&mut other, predicate);
+        other
+    }
+
+    /// Gives the empty map `other` the configuration of this map that can be copied.
+    fn configure_like(&self, other: &mut Self) {
+        other.expiration_tick_cap = self.expiration_tick_cap;
+        other.stale_grace_seconds = self.stale_grace_seconds;
+
+        let Some(ext) = self.ext.as_deref() else {
+            return;
+        };
+
+        let now = other.expiration_now();
+        let other_ext = other.ext_mut();
+        other_ext.name = ext.name;
+        other_ext.audit_log = ext
+            .audit_log
+            .as_ref()
+            .map(|log| AuditLog::new(log.capacity()));
+        other_ext.changes = ext.changes.as_ref().map(|_| BTreeMap::new());
+        other_ext.ttl_histogram = ext.ttl_histogram.as_ref().map(|h| h.cleared(now));
+    }
+
+    /// Moves the non-expired entries matching `predicate` into `other`, converting their
+    /// deadlines to the clock of `other`.
+    fn move_matching_into<F>(&mut self, other: &mut Self, mut predicate: F)
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        let now = self.expiration_now();
+        let keys: Vec<K> = self
+            .map
+            .iter()
+            .filter(|(k, v)| !v.is_expired(now) && predicate(k, v.value()))
+            .map(|(k, _)| k.clone())
+            .collect();
+
+        for k in keys {
+            // Pins and callbacks are detached first so removing the entry neither unpins it
+            // nor runs its callback.
+            let pinned = self.take_pin(&k);
+            let callback = self.ext.as_mut().and_then(|ext| ext.callbacks.remove(&k));
+            let Some(entry) = self.remove_inner(&k) else {
+                continue;
+            };
+
+            match entry.remaining_duration(now) {
+                Some(duration) => {
+                    other.insert_expirable_unchecked(k.clone(), entry.owned_value(), duration)
+                }
+                None => other.insert_constant_unchecked(k.clone(), entry.owned_value()),
+            };
+
+            if let Some(remaining) = pinned {
+                other.ext_mut().pinned.insert(k.clone(), remaining);
+            }
+            if let Some(callback) = callback {
+                other.ext_mut().callbacks.insert(k, callback);
+            }
+        }
+    }
+
+    /// Returns clones of all non-expired entries along with their absolute deadlines.
+    ///
+    /// Deadline is `None` for constant entries. Use `TimedMap::restore` to insert the
+    /// returned entries into another map, possibly in another process.
+    #[cfg(feature = "std")]
+    pub fn export(&self) -> Vec<(K, V, Option<SystemTime>)>
+    where
+        V: Clone,
+    {
+        let now = SystemTime::now();
+        self.iter_with_remaining_durations()
+            .map(|(k, v, duration)| (k.clone(), v.clone(), duration.map(|d| now + d)))
+            .collect()
+    }
+
+    /// Inserts entries captured by `TimedMap::export`.
+    ///
+    /// Entries whose deadline has already passed are dropped, and the rest expire at
+    /// their original deadlines.
+    #[cfg(feature = "std")]
+    pub fn restore<I>(&mut self, entries: I)
+    where
+        I: IntoIterator<Item = (K, V, Option<SystemTime>)>,
+    {
+        let now = SystemTime::now();
+        for (k, v, deadline) in entries {
+            match deadline.map(|deadline| deadline.duration_since(now)) {
+                Some(Ok(duration)) => {
+                    self.insert_expirable_unchecked(k, v, duration);
+                }
+                Some(Err(_)) => {}
+                None => {
+                    self.insert_constant_unchecked(k, v);
+                }
+            }
+        }
+    }
+
+    /// Clears expired entries from the map.
+    ///
+    /// Call this function when using `*_unchecked` inserts, as these do not
+    /// automatically clear expired entries.
+    #[inline(always)]
+    pub fn drop_expired_entries(&mut self) {
+        let now = self.clock.elapsed_seconds_since_creation();
+        self.drop_expired_entries_inner(now);
+    }
+
+    /// Clears expired entries from the map, passing each of them by value to `on_drop` before
+    /// it's dropped.
+    ///
+    /// Entries are visited while they are taken out of the expiration index, without
+    /// collecting them first, which makes this suitable for logging or releasing expired
+    /// entries on allocation-constrained targets.
+    #[inline(always)]
+    pub fn drop_expired_entries_with<F>(&mut self, on_drop: F)
+    where
+        F: FnMut(K, V),
+    {
+        let now = self.clock.elapsed_seconds_since_creation();
+        self.drop_expired_entries_inner_with(now, on_drop);
+    }
+
+    /// Removes every expired entry by scanning the whole map instead of relying on the
+    /// expiration index, then rebuilds the index from the remaining entries, and returns how
+    /// many entries were removed.
+    ///
+    /// This is a recovery path for when the index may be stale, e.g., after restoring from a
+    /// snapshot or when `TimedMap::check_invariants` reports a violation. Entries within the
+    /// stale grace period are kept.
+    pub fn retain_unexpired(&mut self) -> usize {
+        let now = self.expiration_now();
+        let cutoff = now.saturating_sub(self.stale_grace_seconds);
+
+        let expired: Vec<K> = self
+            .map
+            .iter()
+            .filter(|(_, v)| v.is_expired(cutoff))
+            .map(|(k, _)| k.clone())
+            .collect();
+
+        for k in &expired {
+            self.reap(k);
+        }
+
+        let mut expiries: BTreeMap<u64, BTreeSet<K>> = BTreeMap::new();
+        for (k, v) in self.map.iter() {
+            if let EntryStatus::ExpiresAtSeconds(expires_at) = v.status() {
+                expiries.entry(*expires_at).or_default().insert(k.clone());
+            }
+        }
+        self.expirable_len = expiries.values().map(BTreeSet::len).sum();
+        self.expiries = expiries;
+        if let Some(histogram) = ttl_histogram(&mut self.ext) {
+            histogram.rebuild(now, &self.expiries);
+        }
+        self.compact_if_sparse();
+
+        expired.len()
+    }
+
+    #[inline(always)]
+    fn drop_expired_entries_inner(&mut self, now_seconds: u64) {
+        self.drop_expired_entries_inner_with(now_seconds, |_, _| {});
+    }
+
+    fn drop_expired_entries_inner_with<F>(&mut self, now_seconds: u64, mut on_drop: F)
+    where
+        F: FnMut(K, V),
+    {
+        if self.expiration_suspended_at.is_some() {
+            return;
+        }
+
+        #[cfg(feature = "log")]
+        {
+            if now_seconds < self.last_cleanup_seconds {
+                log::warn!(
+                    "[{}] clock went backwards by {}s since the previous cleanup",
+                    self.label(),
+                    self.last_cleanup_seconds - now_seconds
+                );
+            }
+            self.last_cleanup_seconds = now_seconds;
+        }
+
+        // Entries are kept around until their stale grace period ends.
+        let now_seconds = now_seconds.saturating_sub(self.stale_grace_seconds);
+
+        #[cfg(all(feature = "tracing", feature = "std"))]
+        let started_at = Instant::now();
+        #[cfg(any(feature = "tracing", feature = "log"))]
+        let mut removed = 0usize;
+
+        // Iterates through `expiries` in order and drops expired ones.
+        while let Some((exp, _)) = self.expiries.first_key_value() {
+            // It's safe to do early-break here as keys are sorted by expiration.
+            if *exp >= now_seconds {
+                break;
+            }
+
+            if let Some((expires_at, keys)) = self.expiries.pop_first() {
+                self.expirable_len -= keys.len();
+                if let Some(histogram) = ttl_histogram(&mut self.ext) {
+                    histogram.remove(expires_at, keys.len());
+                }
+                for key in keys {
+                    if let Some(v) = self.reap(&key) {
+                        #[cfg(any(feature = "tracing", feature = "log"))]
+                        {
+                            removed += 1;
+                        }
+
+                        on_drop(key, v);
+                    }
+                }
+            }
+        }
+
+        #[cfg(all(feature = "tracing", feature = "std"))]
+        if removed > 0 {
+            tracing::debug!(
+                map = self.label(),
+                removed,
+                elapsed = ?started_at.elapsed(),
+                "expired entries dropped"
+            );
+        }
+
+        #[cfg(all(feature = "tracing", not(feature = "std")))]
+        if removed > 0 {
+            tracing::debug!(map = self.label(), removed, "expired entries dropped");
+        }
+
+        #[cfg(feature = "log")]
+        if removed > LARGE_CLEANUP_BATCH {
+            log::warn!(
+                "[{}] cleanup dropped an unusually large batch of {removed} expired entries",
+                self.label()
+            );
+        }
+
+        self.compact_if_sparse();
+    }
+
+    /// Removes the expired entry of `k` from the map, leaving the expiration index to the
+    /// caller, and notifies the registered observers.
+    fn reap(&mut self, k: &K) -> Option<V> {
+        let entry = self.map.remove(k)?;
+        self.state().expired(k, &entry);
+
+        Some(entry.owned_value())
+    }
+
+    /// Borrows the underlying map apart from everything else, so the bookkeeping of an entry
+    /// can run while the entry is borrowed.
+    #[inline(always)]
+    fn split(&mut self) -> (&mut GenericMap<K, ExpirableEntry<V>>, MapState<'_, K, V>) {
+        let state = MapState {
+            clock: &self.clock,
+            expiries: &mut self.expiries,
+            expirable_len: &mut self.expirable_len,
+            ext: &mut self.ext,
+            stats: &self.stats,
+        };
+
+        (&mut self.map, state)
+    }
+
+    /// Borrows everything but the underlying map.
+    #[inline(always)]
+    fn state(&mut self) -> MapState<'_, K, V> {
+        self.split().1
+    }
+
+    /// Looks up the entry of `k` once for `TimedMap::entry`, along with the state its
+    /// bookkeeping needs.
+    #[inline(always)]
+    pub(crate) fn entry_parts(
+        &mut self,
+        k: K,
+    ) -> (GenericEntry<'_, K, ExpirableEntry<V>>, MapState<'_, K, V>) {
+        let (map, state) = self.split();
+        (map.entry(k), state)
+    }
+
+    /// Reports `event` to the statistics counters and the registered `MetricsSink`.
+    #[inline(always)]
+    fn record(&self, event: MetricEvent) {
+        record_event(&self.stats, self.ext.as_deref(), event);
+    }
+}
+
+/// Everything in `TimedMap` but the underlying map, borrowed apart from it so an entry of the
+/// map can be held while the bookkeeping around it runs.
+pub(crate) struct MapState<'a, K, V> {
+    clock: &'a dyn Clock,
+    expiries: &'a mut BTreeMap<u64, BTreeSet<K>>,
+    expirable_len: &'a mut usize,
+    ext: &'a mut Option<Box<Extensions<K, V>>>,
+    stats: &'a StatsCounters,
+}
+
+impl<K, V> MapState<'_, K, V>
+where
+    K: GenericKey,
+{
+    /// Returns the current time of the clock, in seconds.
+    #[inline(always)]
+    pub(crate) fn now_seconds(&self) -> u64 {
+        self.clock.elapsed_seconds_since_creation()
+    }
+
+    /// Returns the duration `TimedMap::insert` gives to `v` stored for `k`, based on the TTL
+    /// rules and the TTL function, or `None` if it's constant.
+    #[inline(always)]
+    pub(crate) fn ttl_for(&self, k: &K, v: &V) -> Option<Duration> {
+        self.ext.as_ref().and_then(|ext| ext.ttl_for(k, v))
+    }
+
+    /// Stores `v` expiring at `expires_at` in `slot`, replacing the entry it holds if any,
+    /// keeps the expiration index in sync and notifies the registered observers.
+    ///
+    /// Returns the replaced entry along with the stored one.
+    pub(crate) fn store<'m>(
+        &mut self,
+        slot: GenericEntry<'m, K, ExpirableEntry<V>>,
+        v: V,
+        expires_at: Option<u64>,
+    ) -> (Option<ExpirableEntry<V>>, &'m mut ExpirableEntry<V>) {
+        let now = self.now_seconds();
+        let entry = ExpirableEntry::new(v, expires_at);
+
+        #[cfg(feature = "metadata")]
+        let entry = entry.with_created_at(now);
+
+        if let Some(ext) = self.ext.as_mut() {
+            let ttl = expires_at.map(|t| Duration::from_secs(t.saturating_sub(now)));
+
+            if let Some(hook) = ext.storage_hook.as_mut() {
+                hook.on_insert(slot.key(), entry.value(), ttl);
+            }
+
+            if let Some(hook) = ext.insert_hook.as_mut() {
+                hook(slot.key(), entry.value(), Expiry::from_remaining(ttl));
+            }
+        }
+
+        match slot {
+            GenericEntry::Occupied(mut slot) => {
+                // Versions keep increasing across replacements, even if the old entry was expired.
+                let entry = entry.replacing(slot.get());
+                let old = core::mem::replace(slot.get_mut(), entry);
+                self.stored(slot.key(), Some(&old), expires_at);
+
+                (Some(old), slot.into_mut())
+            }
+            GenericEntry::Vacant(slot) => {
+                self.stored(slot.key(), None, expires_at);
+                (None, slot.insert(entry))
+            }
+        }
+    }
+
+    /// Does the bookkeeping of an entry of `k` stored in place of `old`.
+    #[inline(always)]
+    fn stored(&mut self, k: &K, old: Option<&ExpirableEntry<V>>, expires_at: Option<u64>) {
+        if let Some(old) = old {
+            self.take_pin(k);
+            self.run_callback(k, old.value());
+        }
+
+        self.record(MetricEvent::Insert);
+        if old.is_some() {
+            self.record(MetricEvent::Replacement);
+        }
+
+        let outcome = match old {
+            Some(_) => AuditOutcome::Replaced,
+            None => AuditOutcome::Created,
+        };
+        self.audit(AuditOp::Insert, k, outcome);
+
+        let change = match old {
+            Some(_) => Change::Updated,
+            None => Change::Inserted,
+        };
+        self.track_change(k, change);
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            map = self.label(),
+            expires_at,
+            previous_expires_at = ?old.map(|v| v.status()),
+            "entry inserted"
+        );
+
+        self.reindex(k, old.map(|v| *v.status()), expires_at);
+    }
+
+    /// Moves `k` in the expiration index from its `previous` deadline to `expires_at`.
+    ///
+    /// The key indexed for the previous deadline is moved to the new one, so it's only cloned
+    /// when a new or constant entry becomes expirable.
+    #[inline(always)]
+    fn reindex(&mut self, k: &K, previous: Option<EntryStatus>, expires_at: Option<u64>) {
+        let indexed = match previous {
+            Some(EntryStatus::ExpiresAtSeconds(expires_at_seconds)) => {
+                self.take_from_expiries(k, expires_at_seconds)
+            }
+            _ => None,
+        };
+
+        if let Some(expires_at) = expires_at {
+            self.index_expiry(indexed.unwrap_or_else(|| k.clone()), expires_at);
+        }
+    }
+
+    /// Removes the entry of `slot` from the map and the expiration index, and notifies the
+    /// registered observers.
+    pub(crate) fn remove(
+        &mut self,
+        slot: GenericOccupiedEntry<'_, K, ExpirableEntry<V>>,
+    ) -> ExpirableEntry<V> {
+        let (key, entry) = slot.remove_entry();
+        self.removed(&key, &entry);
+
+        entry
+    }
+
+    /// Does the bookkeeping of the entry of `k` removed from the map.
+    #[inline(always)]
+    fn removed(&mut self, k: &K, entry: &ExpirableEntry<V>) {
+        self.take_pin(k);
+        self.run_callback(k, entry.value());
+
+        if let EntryStatus::ExpiresAtSeconds(expires_at_seconds) = entry.status() {
+            self.drop_from_expiries(k, *expires_at_seconds);
+        }
+
+        if let Some(hook) = self.ext.as_mut().and_then(|ext| ext.storage_hook.as_mut()) {
+            hook.on_remove(k, entry.value());
+        }
+
+        self.record(MetricEvent::Removal);
+        self.audit(AuditOp::Remove, k, AuditOutcome::Removed);
+        self.track_change(k, Change::Removed);
+    }
+
+    /// Does the bookkeeping of the expired entry of `k` dropped from the map, leaving the
+    /// expiration index to the caller.
+    #[inline(always)]
+    fn expired(&mut self, k: &K, entry: &ExpirableEntry<V>) {
+        self.record(MetricEvent::Reaped);
+        self.audit(AuditOp::Expire, k, AuditOutcome::Removed);
+        self.track_change(k, Change::Expired);
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(map = self.label(), "expired entry dropped");
+
+        if let Some(hook) = self.ext.as_mut().and_then(|ext| ext.storage_hook.as_mut()) {
+            hook.on_expire(k, entry.value());
+        }
+
+        self.run_callback(k, entry.value());
+    }
+
+    /// Bumps the version of the entry of `slot` and records it as updated before mutable
+    /// access to its value is handed out.
+    #[inline(always)]
+    pub(crate) fn mark_updated(
+        &mut self,
+        slot: &mut GenericOccupiedEntry<'_, K, ExpirableEntry<V>>,
+    ) {
+        slot.get_mut().bump_version();
+        self.track_change(slot.key(), Change::Updated);
+    }
+
+    /// Bumps the version of the entry of `slot` after it's updated in place, and notifies the
+    /// registered observers.
+    #[inline(always)]
+    pub(crate) fn notify_updated(
+        &mut self,
+        slot: &mut GenericOccupiedEntry<'_, K, ExpirableEntry<V>>,
+    ) {
+        let now = self.now_seconds();
+        slot.get_mut().bump_version();
+        notify_observers(self.ext, slot.key(), slot.get(), now);
+    }
+
+    /// Returns the name configured by `TimedMap::name`, or `"unnamed"` if there is none.
+    #[cfg(feature = "tracing")]
+    #[inline(always)]
+    fn label(&self) -> &'static str {
+        self.ext
+            .as_ref()
+            .and_then(|ext| ext.name)
+            .unwrap_or("unnamed")
+    }
+
+    /// Drops the pin of the entry of `k` and returns the remaining lifetime it was pinned with.
+    #[inline(always)]
+    fn take_pin<Q>(&mut self, k: &Q) -> Option<Option<u64>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.ext.as_mut().and_then(|ext| ext.pinned.remove(k))
+    }
+
+    /// Runs the callback attached to the entry of `k`, if any.
+    #[inline(always)]
+    fn run_callback(&mut self, k: &K, v: &V) {
+        if let Some(callback) = self.ext.as_mut().and_then(|ext| ext.callbacks.remove(k)) {
+            callback(k, v);
+        }
+    }
+
+    /// Reports `event` to the statistics counters and the registered `MetricsSink`.
+    #[inline(always)]
+    fn record(&self, event: MetricEvent) {
+        record_event(self.stats, self.ext.as_deref(), event);
+    }
+
+    /// Appends an operation on `k` to the audit log if it's enabled.
+    #[inline(always)]
+    fn audit(&mut self, op: AuditOp, k: &K, outcome: AuditOutcome) {
+        if let Some(log) = self.ext.as_mut().and_then(|ext| ext.audit_log.as_mut()) {
+            log.push(AuditRecord {
+                op,
+                key: k.clone(),
+                at: Duration::from_secs(self.clock.elapsed_seconds_since_creation()),
+                outcome,
+            });
+        }
+    }
+
+    /// Records a change of `k` if change tracking is enabled.
+    #[inline(always)]
+    fn track_change(&mut self, k: &K, change: Change) {
+        if let Some(ext) = self.ext.as_mut() {
+            ext.track_change(k, change);
+        }
+    }
+
+    /// Removes `k` from the `expiries` bucket of `expires_at_seconds`, dropping
+    /// the bucket if it becomes empty.
+    #[inline(always)]
+    fn drop_from_expiries(&mut self, k: &K, expires_at_seconds: u64) {
+        self.take_from_expiries(k, expires_at_seconds);
+    }
+
+    /// Same as `MapState::drop_from_expiries`, returning the key that was indexed.
+    #[inline(always)]
+    fn take_from_expiries(&mut self, k: &K, expires_at_seconds: u64) -> Option<K> {
+        let indexed = unindex(self.expiries, k, expires_at_seconds);
+        if indexed.is_some() {
+            *self.expirable_len -= 1;
+            if let Some(histogram) = ttl_histogram(self.ext) {
+                histogram.remove(expires_at_seconds, 1);
+            }
+        }
+
+        indexed
+    }
+
+    /// Adds `k` to the `expiries` bucket of `expires_at_seconds`.
+    #[inline(always)]
+    fn index_expiry(&mut self, k: K, expires_at_seconds: u64) {
+        if self
+            .expiries
+            .entry(expires_at_seconds)
+            .or_default()
+            .insert(k)
+        {
+            *self.expirable_len += 1;
+            if let Some(histogram) = ttl_histogram(self.ext) {
+                histogram.add(expires_at_seconds, 1);
+            }
+        }
+    }
+}
+
+/// Returns the histogram enabled by `TimedMap::ttl_histogram_buckets`, if any.
+#[inline(always)]
+fn ttl_histogram<K, V>(ext: &mut Option<Box<Extensions<K, V>>>) -> Option<&mut TtlHistogram> {
+    ext.as_mut()?.ttl_histogram.as_mut()
+}
+
+/// Reports `event` to `stats` and the `MetricsSink` registered in `ext`.
+#[inline(always)]
+fn record_event<K, V>(stats: &StatsCounters, ext: Option<&Extensions<K, V>>, event: MetricEvent) {
+    stats.record(event);
+
+    if let Some(ext) = ext {
+        if let Some(sink) = ext.metrics_sink.as_ref() {
+            sink.on_event(ext.name, event);
+        }
+    }
+
+    if let MetricEvent::ExpiredOnRead = event {
+        record_event(stats, ext, MetricEvent::Miss);
+    }
+}
+
+/// Removes `k` from the `expiries` bucket of `expires_at_seconds`, dropping the bucket if it
+/// has no keys left. Returns the key that was indexed there, if any.
+#[inline(always)]
+fn unindex<K: Ord>(
+    expiries: &mut BTreeMap<u64, BTreeSet<K>>,
+    k: &K,
+    expires_at_seconds: u64,
+) -> Option<K> {
+    let keys = expiries.get_mut(&expires_at_seconds)?;
+
+    let removed = keys.take(k);
+    if keys.is_empty() {
+        expiries.remove(&expires_at_seconds);
+    }
+
+    removed
+}
+
+/// Bumps the version of the entry of `k` after it's updated in place, and notifies the
+/// observers registered in `ext` with `notify_observers`.
+#[inline(always)]
+fn on_updated<K: GenericKey, V>(
+    ext: &mut Option<Box<Extensions<K, V>>>,
+    k: &K,
+    entry: &mut ExpirableEntry<V>,
+    now_seconds: u64,
+) {
+    entry.bump_version();
+    notify_observers(ext, k, entry, now_seconds);
+}
+
+/// Notifies the observers registered in `ext` after the entry of `k` is updated in place, with
+/// its lifetime measured from `now_seconds`, and records the update if change tracking is
+/// enabled.
+#[inline(always)]
+fn notify_observers<K: GenericKey, V>(
+    ext: &mut Option<Box<Extensions<K, V>>>,
+    k: &K,
+    entry: &ExpirableEntry<V>,
+    now_seconds: u64,
+) {
+    let Some(ext) = ext.as_mut() else {
+        return;
+    };
+
+    let ttl = entry.remaining_duration(now_seconds);
+    if let Some(hook) = ext.storage_hook.as_mut() {
+        hook.on_insert(k, entry.value(), ttl);
+    }
+
+    if let Some(hook) = ext.insert_hook.as_mut() {
+        hook(k, entry.value(), Expiry::from_remaining(ttl));
+    }
+
+    ext.track_change(k, Change::Updated);
+}
+
+/// Formats an entry's value along with its remaining duration relative to the clock.
+struct DebugEntry<'a, V> {
+    value: &'a V,
+    freshness: Freshness,
+}
+
+impl<V: core::fmt::Debug> core::fmt::Debug for DebugEntry<'_, V> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.freshness {
+            Freshness::Fresh(None) => write!(f, "{:?} (constant)", self.value),
+            Freshness::Fresh(Some(remaining)) => {
+                write!(f, "{:?} (expires in {remaining:?})", self.value)
+            }
+            Freshness::Stale(elapsed) => write!(f, "{:?} (expired {elapsed:?} ago)", self.value),
+        }
+    }
+}
+
+/// Formats the entries along with their remaining durations, including the expired ones
+/// that aren't dropped yet.
+impl<C, K, V> core::fmt::Debug for TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey + core::fmt::Debug,
+    V: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if let Some(name) = self.get_name() {
+            write!(f, "{name} ")?;
+        }
+
+        // Decided the same way as by the accessors, e.g., while expiration is suspended.
+        let now = self.expiration_now();
+        f.debug_map()
+            .entries(self.map.iter().map(|(k, v)| {
+                let entry = DebugEntry {
+                    value: v.value(),
+                    freshness: v.freshness(now),
+                };
+                (k, entry)
+            }))
+            .finish()
+    }
+}
+
+/// Inserts all key-value pairs as constant entries, without checking the expired entries.
+///
+/// Use `TimedMap::extend_expirable` to insert them with an expiration duration instead.
+impl<C, K, V> Extend<(K, V)> for TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (k, v) in iter {
+            self.insert_inner(k, v, None);
+        }
+    }
+}
+
+/// Returns the value of a non-expired entry, like `TimedMap::get`.
+///
+/// # Panics
+///
+/// Panics if the entry does not exist or is expired.
+impl<C, K, V, Q> core::ops::Index<&Q> for TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey + Borrow<Q>,
+    Q: GenericQuery + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, k: &Q) -> &V {
+        self.get(k)
+            .expect("no entry found for key or it is expired")
+    }
+}
+
+/// Creates a `HashMap` backed map holding all entries as constant.
+#[cfg(feature = "std")]
+impl<C, K, V> From<HashMap<K, V>> for TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    fn from(map: HashMap<K, V>) -> Self {
+        let mut timed_map = Self::new_with_map_kind(MapKind::HashMap);
+        timed_map.extend(map);
+        timed_map
+    }
+}
+
+/// Creates a `BTreeMap` backed map holding all entries as constant.
+#[cfg(feature = "std")]
+impl<C, K, V> From<BTreeMap<K, V>> for TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    fn from(map: BTreeMap<K, V>) -> Self {
+        let mut timed_map = Self::new();
+        timed_map.extend(map);
+        timed_map
+    }
+}
+
+/// Negative caching helpers for maps holding `Result`s, where successful and failed
+/// lookups are cached with distinct durations.
+impl<C, K, T, E> TimedMap<C, K, Result<T, E>>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Inserts `v` expiring after `ok_duration` if it's `Ok`, or after `err_duration`
+    /// if it's `Err`.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned.
+    pub fn insert_result(
+        &mut self,
+        k: K,
+        v: Result<T, E>,
+        ok_duration: Duration,
+        err_duration: Duration,
+    ) -> Option<Result<T, E>> {
+        let duration = if v.is_ok() { ok_duration } else { err_duration };
+        self.insert_expirable(k, v, duration)
+    }
+
+    /// Returns the associated result if present and not expired, otherwise computes it with
+    /// `f` and caches it through `TimedMap::insert_result`.
+    ///
+    /// Failures are cached as well (typically with a shorter `err_duration`), so repeated
+    /// lookups of a failing key don't hit the underlying source every time.
+    pub fn get_or_insert_result_with<F>(
+        &mut self,
+        k: K,
+        ok_duration: Duration,
+        err_duration: Duration,
+        f: F,
+    ) -> &Result<T, E>
+    where
+        F: FnOnce(&K) -> Result<T, E>,
+    {
+        if self.get(&k).is_none() {
+            let v = f(&k);
+            self.insert_result(k.clone(), v, ok_duration, err_duration);
+        }
+
+        self.map
+            .get(&k)
+            .map(|v| v.value())
+            .expect("entry must exist after insertion")
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn nostd_insert_and_get_constant_entry() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_constant(1, "constant value");
+
+        assert_eq!(map.get(&1), Some(&"constant value"));
+        assert_eq!(map.get_remaining_duration(&1), None);
+    }
+
+    #[test]
+    fn nostd_insert_and_get_expirable_entry() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+        let duration = Duration::from_secs(60);
+
+        map.insert_expirable(1, "expirable value", duration);
+
+        assert_eq!(map.get(&1), Some(&"expirable value"));
+        assert_eq!(map.get_remaining_duration(&1), Some(duration));
+    }
+
+    #[test]
+    fn nostd_expired_entry() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+        let duration = Duration::from_secs(60);
+
+        // Insert entry that expires in 60 seconds
+        map.insert_expirable(1, "expirable value", duration);
+
+        // Simulate time passage beyond expiration
+        map.clock.set(1070);
+
+        // The entry should be considered expired
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.get_remaining_duration(&1), None);
+    }
+
+    #[test]
+    fn nostd_entry_status() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_constant(1, "constant value");
+        map.insert_expirable(2, "expirable value", Duration::from_secs(60));
+
+        assert_eq!(map.status(&1), Some(EntryStatus::Constant));
+        assert_eq!(map.status(&2), Some(EntryStatus::ExpiresAtSeconds(1060)));
+        assert_eq!(map.status(&3), None);
+
+        assert_eq!(map.expires_at(&1), None);
+        assert_eq!(map.expires_at(&2), Some(1060));
+
+        map.clock.set(1061);
+        assert_eq!(map.status(&2), None);
+        assert_eq!(map.expires_at(&2), None);
+    }
+
+    #[test]
+    fn nostd_update_expiration_status() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_expirable(1, "value", Duration::from_secs(60));
+
+        // Expirable to constant.
+        assert_eq!(
+            map.update_expiration_status(&1, None),
+            Some(EntryStatus::ExpiresAtSeconds(1060))
+        );
+        assert!(map.expiries.is_empty());
+
+        map.clock.set(1061);
+        assert_eq!(map.get(&1), Some(&"value"));
+
+        // Constant back to expirable.
+        assert_eq!(
+            map.update_expiration_status(&1, Some(Duration::from_secs(10))),
+            Some(EntryStatus::Constant)
+        );
+        assert_eq!(map.status(&1), Some(EntryStatus::ExpiresAtSeconds(1071)));
+        assert_eq!(map.expiries.get(&1071).map(|keys| keys.len()), Some(1));
+
+        assert_eq!(map.update_expiration_status(&2, None), None);
+    }
+
+    #[test]
+    fn nostd_try_variants_tell_apart_missing_and_expired_entries() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_expirable_unchecked(1, "expired", Duration::from_secs(10));
+        map.insert_constant(2, "constant");
+        map.clock.set(1011);
+
+        assert_eq!(map.try_get(&1), Err(TimedMapError::EntryExpired));
+        assert_eq!(map.try_get(&2), Ok(&"constant"));
+        assert_eq!(map.try_get(&3), Err(TimedMapError::EntryNotFound));
+
+        assert_eq!(
+            map.try_update_expiration_status(&1, None),
+            Err(TimedMapError::EntryExpired)
+        );
+        assert_eq!(
+            map.try_update_expiration_status(&3, None),
+            Err(TimedMapError::EntryNotFound)
+        );
+
+        assert_eq!(map.try_remove(&1), Err(TimedMapError::EntryExpired));
+        assert_eq!(map.try_remove(&1), Err(TimedMapError::EntryNotFound));
+        assert_eq!(map.try_remove(&2), Ok("constant"));
+
+        assert_eq!(
+            map.try_insert_expirable(4, "forever", Duration::MAX),
+            Err(TimedMapError::InvalidDuration)
+        );
+        assert_eq!(map.try_get(&4), Err(TimedMapError::EntryNotFound));
+        assert_eq!(
+            map.try_insert_expirable(4, "short", Duration::from_secs(10)),
+            Ok(None)
+        );
+        assert_eq!(
+            map.try_update_expiration_status(&4, Some(Duration::MAX)),
+            Err(TimedMapError::InvalidDuration)
+        );
+        assert_eq!(map.status(&4), Some(EntryStatus::ExpiresAtSeconds(1021)));
+    }
+
+    #[test]
+    fn nostd_clear_family() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        let fill = |map: &mut TimedMap<ManualClock, u32, &str>| {
+            map.insert_constant_unchecked(1, "constant");
+            map.insert_expirable_unchecked(2, "expirable", Duration::from_secs(60));
+            map.insert_expirable_unchecked(3, "expired", Duration::from_secs(1));
+        };
+
+        fill(&mut map);
+        map.clock.set(1002);
+        map.clear_expirable();
+        assert!(map.expiries.is_empty());
+        assert_eq!(map.get_unchecked(&1), Some(&"constant"));
+        assert_eq!(map.get_unchecked(&3), None);
+
+        fill(&mut map);
+        map.clear_constant();
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.get(&2), Some(&"expirable"));
+        assert!(map.check_invariants().is_ok());
+
+        fill(&mut map);
+        map.pin(&2);
+        map.clock.set(1004);
+        map.expiration_tick = 3;
+        let mut drained = map.clear_into();
+        drained.sort_by_key(|(k, _, _)| *k);
+
+        // Pinned entries don't expire, just like constant ones.
+        assert_eq!(drained, [(1, "constant", None), (2, "expirable", None)]);
+        assert!(map.map.iter().next().is_none());
+        assert!(map.expiries.is_empty());
+        assert!(!map.is_pinned(&2));
+        assert_eq!(map.expiration_tick, 0);
+    }
+
+    #[test]
+    fn nostd_get_mut_and_refresh() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, u32> = TimedMap::new(clock);
+
+        map.insert_expirable(1, 0, Duration::from_secs(10));
+        map.insert_constant(2, 0);
+        map.insert_expirable_unchecked(3, 0, Duration::from_secs(1));
+
+        map.clock.set(1005);
+        *map.get_mut_and_refresh(&1, Duration::from_secs(10))
+            .unwrap() += 1;
+        assert_eq!(map.get(&1), Some(&1));
+        assert_eq!(map.expires_at(&1), Some(1015));
+
+        *map.get_mut_and_refresh(&2, Duration::from_secs(10))
+            .unwrap() += 1;
+        assert_eq!(map.expires_at(&2), Some(1015));
+
+        assert!(map.get_mut_and_refresh(&2, Duration::MAX).is_some());
+        assert_eq!(map.expires_at(&2), Some(u64::MAX));
+
+        assert_eq!(map.get_mut_and_refresh(&3, Duration::from_secs(10)), None);
+        assert_eq!(map.get_mut_and_refresh(&4, Duration::from_secs(10)), None);
+        assert!(map.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn nostd_get_or_insert_default() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, Vec<u32>> = TimedMap::new(clock);
+
+        map.get_or_insert_default(&1, Duration::from_secs(10))
+            .push(1);
+        map.get_or_insert_default(&1, Duration::from_secs(60))
+            .push(2);
+        assert_eq!(map.get(&1), Some(&Vec::from([1, 2])));
+        assert_eq!(
+            map.get_remaining_duration(&1),
+            Some(Duration::from_secs(10))
+        );
+
+        // Expired buckets start over.
+        map.clock.set(1011);
+        map.get_or_insert_default(&1, Duration::from_secs(10))
+            .push(3);
+        assert_eq!(map.get(&1), Some(&Vec::from([3])));
+    }
+
+    #[test]
+    fn nostd_insert_or_extend() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, u32> = TimedMap::new(clock);
+
+        let add = |old: &mut u32, new: u32| *old += new;
+
+        assert_eq!(*map.insert_or_extend(1, 1, Duration::from_secs(10), add), 1);
+        assert_eq!(*map.insert_or_extend(1, 2, Duration::from_secs(10), add), 3);
+        assert_eq!(map.expires_at(&1), Some(1020));
+        #[cfg(feature = "versions")]
+        assert_eq!(map.get_versioned(&1), Some((&3, 2)));
+
+        // Constant entries stay constant.
+        map.insert_constant(2, 0);
+        map.insert_or_extend(2, 5, Duration::from_secs(10), |_, _| {});
+        assert_eq!(map.get(&2), Some(&0));
+        assert_eq!(map.status(&2), Some(EntryStatus::Constant));
+
+        // Pinned entries resume with the extended duration.
+        map.pin(&1);
+        map.insert_or_extend(1, 0, Duration::from_secs(5), add);
+        map.unpin(&1);
+        assert_eq!(map.expires_at(&1), Some(1025));
+
+        // Expired entries are replaced.
+        map.clock.set(1026);
+        assert_eq!(*map.insert_or_extend(1, 7, Duration::from_secs(10), add), 7);
+        assert_eq!(map.expires_at(&1), Some(1036));
+        assert!(map.check_invariants().is_ok());
+
+        // Far extensions saturate instead of wrapping into the past.
+        assert_eq!(*map.insert_or_extend(1, 1, Duration::MAX, add), 8);
+        assert_eq!(map.expires_at(&1), Some(u64::MAX));
+        assert!(map.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn nostd_insert_like() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_expirable(1, "order", Duration::from_secs(60));
+        map.insert_constant(2, "config");
+
+        map.clock.set(1030);
+        assert_eq!(map.insert_like(&1, 10, "order item"), Ok(None));
+        assert_eq!(map.expires_at(&10), Some(1060));
+
+        assert_eq!(map.insert_like(&2, 20, "config item"), Ok(None));
+        assert_eq!(map.status(&20), Some(EntryStatus::Constant));
+
+        map.pin(&1);
+        assert_eq!(map.insert_like(&1, 11, "order item"), Ok(None));
+        assert_eq!(map.expires_at(&11), Some(1060));
+
+        assert_eq!(
+            map.insert_like(&3, 30, "orphan"),
+            Err(TimedMapError::EntryNotFound)
+        );
+        assert_eq!(map.get(&30), None);
+    }
+
+    #[test]
+    fn nostd_insert_full() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        assert_eq!(
+            map.insert_expirable_full(1, "first", Duration::from_secs(60)),
+            (None, None)
+        );
+
+        map.clock.set(1020);
+        assert_eq!(
+            map.insert_expirable_full(1, "second", Duration::from_secs(60)),
+            (Some("first"), Some(Duration::from_secs(40)))
+        );
+        assert_eq!(
+            map.insert_constant_full(1, "third"),
+            (Some("second"), Some(Duration::from_secs(60)))
+        );
+        assert_eq!(map.insert_constant_full(1, "fourth"), (Some("third"), None));
+
+        map.insert_expirable(2, "pinned", Duration::from_secs(30));
+        map.pin(&2);
+        map.clock.set(1100);
+        assert_eq!(
+            map.insert_constant_full(2, "unpinned"),
+            (Some("pinned"), Some(Duration::from_secs(30)))
+        );
+
+        // Expired entries had no lifetime left.
+        map.insert_expirable(3, "expired", Duration::from_secs(1));
+        map.clock.set(1102);
+        assert_eq!(
+            map.insert_expirable_full(3, "fresh", Duration::from_secs(1)),
+            (Some("expired"), None)
+        );
+    }
+
+    #[test]
+    fn nostd_drop_expired_entries_with() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_expirable_unchecked(1, "lease 1", Duration::from_secs(5));
+        map.insert_expirable_unchecked(2, "lease 2", Duration::from_secs(10));
+        map.insert_expirable_unchecked(3, "lease 3", Duration::from_secs(60));
+        map.insert_constant_unchecked(4, "config");
+
+        map.clock.set(1011);
+        let mut dropped = [(0, ""); 2];
+        let mut count = 0;
+        map.drop_expired_entries_with(|k, v| {
+            dropped[count] = (k, v);
+            count += 1;
+        });
+
+        assert_eq!(dropped, [(1, "lease 1"), (2, "lease 2")]);
+        assert_eq!(map.get_unchecked(&1), None);
+        assert_eq!(map.get(&3), Some(&"lease 3"));
+        assert_eq!(map.get(&4), Some(&"config"));
+    }
+
+    #[test]
+    fn nostd_suspend_expiration() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_expirable(1, "expired", Duration::from_secs(1));
+        map.insert_expirable(2, "overdue", Duration::from_secs(10));
+
+        map.clock.set(1002);
+        map.suspend_expiration();
+        assert!(map.is_expiration_suspended());
+
+        // Deadlines passing during the window don't hide entries or drop them.
+        map.clock.set(1020);
+        map.insert_expirable(3, "new", Duration::from_secs(10));
+        map.drop_expired_entries();
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.get(&2), Some(&"overdue"));
+        assert_eq!(map.get_unchecked(&1), Some(&"expired"));
+        assert_eq!(map.expires_at(&3), Some(1030));
+
+        map.resume_expiration();
+        assert!(!map.is_expiration_suspended());
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map.get_unchecked(&1), None);
+        assert_eq!(map.get_unchecked(&2), None);
+        assert_eq!(map.get(&3), Some(&"new"));
+    }
+
+    #[test]
+    fn nostd_index() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_constant(1, "constant value");
+        assert_eq!(map[&1], "constant value");
+    }
+
+    #[test]
+    #[should_panic(expected = "no entry found for key or it is expired")]
+    fn nostd_index_panics_on_expired_entry() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_expirable(1, "expirable value", Duration::from_secs(10));
+        map.clock.set(1011);
+        let _ = map[&1];
+    }
+
+    #[test]
+    fn nostd_remove_entry() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_constant(1, "constant value");
+
+        assert_eq!(map.remove(&1), Some("constant value"));
+        assert_eq!(map.get(&1), None);
+    }
+
+    #[test]
+    fn nostd_drop_expired_entries() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        // Insert one constant and 2 expirable entries
+        map.insert_expirable(1, "expirable value1", Duration::from_secs(50));
+        map.insert_expirable(2, "expirable value2", Duration::from_secs(70));
+        map.insert_constant(3, "constant value");
+
+        // Simulate time passage beyond the expiration of the first entry
+        map.clock.set(1055);
+
+        // Entry 1 should be removed and entry 2 and 3 should still exist
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.get(&2), Some(&"expirable value2"));
+        assert_eq!(map.get(&3), Some(&"constant value"));
+
+        // Simulate time passage again to expire second expirable entry
+        map.clock.set(1071);
+
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map.get(&3), Some(&"constant value"));
+    }
+
+    #[test]
+    fn nostd_update_existing_entry() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_constant(1, "initial value");
+        assert_eq!(map.get(&1), Some(&"initial value"));
+
+        // Update the value of the existing key and make it expirable
+        map.insert_expirable(1, "updated value", Duration::from_secs(15));
+        assert_eq!(map.get(&1), Some(&"updated value"));
+
+        // Simulate time passage and expire the updated entry
+        map.clock.set(1016);
+
+        assert_eq!(map.get(&1), None);
+    }
+
+    #[test]
+    fn nostd_drop_expired_entries_sharing_deadline() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_expirable_unchecked(1, "expirable value1", Duration::from_secs(10));
+        map.insert_expirable_unchecked(2, "expirable value2", Duration::from_secs(10));
+        map.insert_expirable_unchecked(3, "expirable value3", Duration::from_secs(60));
+
+        // Re-inserting a key must not leave its previous deadline behind.
+        map.insert_expirable_unchecked(3, "expirable value3", Duration::from_secs(5));
+        map.remove(&2);
+
+        map.clock.set(1011);
+        map.drop_expired_entries();
+
+        assert_eq!(map.get_unchecked(&1), None);
+        assert_eq!(map.get_unchecked(&3), None);
+        assert!(map.expiries.is_empty());
+    }
+
+    #[test]
+    fn nostd_storage_hook_mirrors_mutations() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static INSERTS: AtomicUsize = AtomicUsize::new(0);
+        static REMOVALS: AtomicUsize = AtomicUsize::new(0);
+        static EXPIRATIONS: AtomicUsize = AtomicUsize::new(0);
+
+        struct CountingHook;
+
+        impl StorageHook<u32, &str> for CountingHook {
+            fn on_insert(&mut self, k: &u32, _v: &&str, ttl: Option<Duration>) {
+                assert_eq!(ttl.is_some(), *k != 3);
+                INSERTS.fetch_add(1, Ordering::Relaxed);
+            }
+
+            fn on_remove(&mut self, _k: &u32, _v: &&str) {
+                REMOVALS.fetch_add(1, Ordering::Relaxed);
+            }
+
+            fn on_expire(&mut self, k: &u32, _v: &&str) {
+                assert_eq!(*k, 1);
+                EXPIRATIONS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> =
+            TimedMap::new(clock).storage_hook(CountingHook);
+
+        map.insert_expirable(1, "expirable value1", Duration::from_secs(10));
+        map.insert_expirable(2, "expirable value2", Duration::from_secs(60));
+        map.insert_constant(3, "constant value");
+        map.remove(&2);
+
+        map.clock.set(1011);
+        map.drop_expired_entries();
+
+        assert_eq!(INSERTS.load(Ordering::Relaxed), 3);
+        assert_eq!(REMOVALS.load(Ordering::Relaxed), 1);
+        assert_eq!(EXPIRATIONS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn nostd_storage_hook_sees_in_place_updates() {
+        use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+        static INSERTS: AtomicUsize = AtomicUsize::new(0);
+        static LAST_TTL: AtomicU64 = AtomicU64::new(0);
+
+        struct RecordingHook;
+
+        impl StorageHook<u32, u32> for RecordingHook {
+            fn on_insert(&mut self, _k: &u32, _v: &u32, ttl: Option<Duration>) {
+                INSERTS.fetch_add(1, Ordering::Relaxed);
+                LAST_TTL.store(ttl.map_or(u64::MAX, |d| d.as_secs()), Ordering::Relaxed);
+            }
+        }
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock).storage_hook(RecordingHook);
+        let last = || {
+            (
+                INSERTS.load(Ordering::Relaxed),
+                LAST_TTL.load(Ordering::Relaxed),
+            )
+        };
+
+        map.insert_expirable(1, 1, Duration::from_secs(10));
+        map.insert_constant(2, 2);
+        assert_eq!(last(), (2, u64::MAX));
+
+        map.update_expiration_status(&1, Some(Duration::from_secs(20)));
+        assert_eq!(last(), (3, 20));
+
+        // Constant entries are left out of bulk lifetime updates.
+        map.set_ttl_for_all(Duration::from_secs(30));
+        assert_eq!(last(), (4, 30));
+        map.extend_all(Duration::from_secs(5));
+        assert_eq!(last(), (5, 35));
+
+        map.map_values_in_place(|_, v| *v += 1);
+        assert_eq!(last().0, 7);
+
+        map.get_mut_and_refresh(&2, Duration::from_secs(40));
+        assert_eq!(last(), (8, 40));
+
+        map.insert_or_extend(1, 1, Duration::from_secs(10), |v, n| *v += n);
+        assert_eq!(last(), (9, 45));
+
+        map.entry(1).and_modify(|v| *v += 1);
+        assert_eq!(last(), (10, 45));
+
+        drop(map.lease(&1, Duration::from_secs(50)));
+        assert_eq!(last(), (11, 50));
+    }
+
+    #[test]
+    fn nostd_metrics_sink_receives_events() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static EVENTS: [AtomicUsize; 7] = [const { AtomicUsize::new(0) }; 7];
+
+        struct CountingSink;
+
+        impl MetricsSink for CountingSink {
+            fn on_event(&self, _map_name: Option<&'static str>, event: MetricEvent) {
+                EVENTS[event as usize].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> =
+            TimedMap::new(clock).metrics_sink(CountingSink);
+
+        map.insert_expirable_unchecked(1, "expirable value", Duration::from_secs(10));
+        map.insert_constant_unchecked(2, "constant value");
+        map.insert_constant_unchecked(2, "constant value");
+        assert!(map.get(&2).is_some());
+        assert!(map.get(&3).is_none());
+        map.remove(&2);
+
+        map.clock.set(1011);
+        assert!(map.get(&1).is_none());
+        map.drop_expired_entries();
+
+        let count = |event: MetricEvent| EVENTS[event as usize].load(Ordering::Relaxed);
+        assert_eq!(count(MetricEvent::Hit), 1);
+        assert_eq!(count(MetricEvent::Miss), 2);
+        assert_eq!(count(MetricEvent::ExpiredOnRead), 1);
+        assert_eq!(count(MetricEvent::Insert), 3);
+        assert_eq!(count(MetricEvent::Replacement), 1);
+        assert_eq!(count(MetricEvent::Removal), 1);
+        assert_eq!(count(MetricEvent::Reaped), 1);
+    }
+
+    #[test]
+    fn nostd_get_or_load_promotes_from_secondary_store() {
+        struct Store;
+
+        impl SecondaryStore<u32, u32> for Store {
+            fn load(&mut self, k: &u32) -> Option<(u32, Option<Duration>)> {
+                (*k < 10).then(|| (k * 100, Some(Duration::from_secs(30))))
+            }
+        }
+
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, u32> = TimedMap::new(clock).secondary_store(Store);
+
+        map.insert_constant(1, 1);
+
+        assert_eq!(map.get_or_load(&1), Some(&1));
+        assert_eq!(map.get_or_load(&2), Some(&200));
+        assert_eq!(map.get_or_load(&20), None);
+
+        assert_eq!(map.get(&2), Some(&200));
+        assert_eq!(
+            map.get_remaining_duration(&2),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "stats")]
+    fn nostd_get_or_load_counts_a_single_outcome() {
+        struct Store;
+
+        impl SecondaryStore<u32, u32> for Store {
+            fn load(&mut self, k: &u32) -> Option<(u32, Option<Duration>)> {
+                Some((*k, None))
+            }
+        }
+
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, u32> = TimedMap::new(clock).secondary_store(Store);
+
+        map.get_or_load(&1);
+        assert_eq!((map.stats().hits, map.stats().misses), (0, 1));
+
+        map.get_or_load(&1);
+        assert_eq!((map.stats().hits, map.stats().misses), (1, 1));
+        assert_eq!(map.stats().inserts, 1);
+    }
+
+    #[test]
+    fn nostd_get_with_refresh_extends_entries_nearing_expiry() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, u32> = TimedMap::new(clock)
+            .refresh_ahead(Duration::from_secs(10), |_: &u32, v: &u32| {
+                Some((v + 1, Duration::from_secs(60)))
+            });
+
+        map.insert_expirable(1, 1, Duration::from_secs(60));
+
+        // Far from expiring, entry is returned as is.
+        assert_eq!(map.get_with_refresh(&1), Some(&1));
+
+        map.clock.set(1055);
+        assert_eq!(map.get_with_refresh(&1), Some(&2));
+        assert_eq!(
+            map.get_remaining_duration(&1),
+            Some(Duration::from_secs(60))
+        );
+
+        // Expired entries are not refreshed.
+        map.clock.set(1200);
+        assert_eq!(map.get_with_refresh(&1), None);
+    }
+
+    #[test]
+    fn nostd_get_stale_within_grace_period() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> =
+            TimedMap::new(clock).stale_grace_period(Duration::from_secs(30));
+
+        map.insert_expirable(1, "expirable value", Duration::from_secs(60));
+        assert_eq!(map.get_stale(&1), Some((&"expirable value", false)));
+
+        // Expired, but still within the grace period.
+        map.clock.set(1080);
+        map.drop_expired_entries();
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.get_stale(&1), Some((&"expirable value", true)));
+
+        map.clock.set(1091);
+        assert_eq!(map.get_stale(&1), None);
+
+        map.drop_expired_entries();
+        assert_eq!(map.get_unchecked(&1), None);
+    }
+
+    #[test]
+    fn nostd_get_with_freshness() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
+
+        map.insert_constant(1, "constant value");
+        map.insert_expirable(2, "expirable value", Duration::from_secs(60));
+
+        assert_eq!(
+            map.get_with_freshness(&1),
+            Some((&"constant value", Freshness::Fresh(None)))
+        );
+        assert_eq!(
+            map.get_with_freshness(&2),
+            Some((
+                &"expirable value",
+                Freshness::Fresh(Some(Duration::from_secs(60)))
+            ))
+        );
+
+        map.clock.set(1075);
+        assert_eq!(
+            map.get_with_freshness(&2),
+            Some((
+                &"expirable value",
+                Freshness::Stale(Duration::from_secs(15))
+            ))
+        );
+        assert_eq!(map.get_with_freshness(&3), None);
+    }
+
+    #[test]
+    fn nostd_negative_caching_with_distinct_durations() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, Result<u32, &str>> = TimedMap::new(clock);
+
+        let ok_duration = Duration::from_secs(300);
+        let err_duration = Duration::from_secs(5);
+
+        map.insert_result(1, Ok(1), ok_duration, err_duration);
+        assert_eq!(map.get_remaining_duration(&1), Some(ok_duration));
+
+        let mut calls = 0;
+        for _ in 0..2 {
+            let res = map.get_or_insert_result_with(2, ok_duration, err_duration, |_| {
+                calls += 1;
+                Err("lookup failed")
+            });
+            assert_eq!(res, &Err("lookup failed"));
+        }
+        assert_eq!(calls, 1);
+        assert_eq!(map.get_remaining_duration(&2), Some(err_duration));
+
+        // Failure expires quickly and the next lookup succeeds.
+        map.clock.set(1006);
+        let res = map.get_or_insert_result_with(2, ok_duration, err_duration, |k| Ok(k * 10));
+        assert_eq!(res, &Ok(20));
+        assert_eq!(map.get_remaining_duration(&2), Some(ok_duration));
+    }
+
+    #[test]
+    fn nostd_insert_auto_uses_value_ttl() {
+        struct Record {
+            ttl_seconds: Option<u64>,
+        }
+
+        impl ExpirableValue for Record {
+            fn ttl(&self) -> Option<Duration> {
+                self.ttl_seconds.map(Duration::from_secs)
+            }
+        }
+
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, Record> = TimedMap::new(clock);
+
+        map.insert_auto(
+            1,
+            Record {
+                ttl_seconds: Some(30),
+            },
+        );
+        map.insert_auto(2, Record { ttl_seconds: None });
+
+        assert_eq!(
+            map.get_remaining_duration(&1),
+            Some(Duration::from_secs(30))
+        );
+        assert!(map.get(&2).is_some());
+        assert_eq!(map.get_remaining_duration(&2), None);
+    }
+
+    #[test]
+    fn nostd_insert_with_ttl_fn() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> =
+            TimedMap::new(clock).ttl_fn(|k: &u32, _: &&str| match k {
+                0..100 => Some(Duration::from_secs(60)),
+                100..1000 => Some(Duration::from_secs(3600)),
+                _ => None,
+            });
+
+        map.insert(1, "regular session");
+        map.insert(100, "premium session");
+        map.insert(1000, "service session");
+
+        assert_eq!(
+            map.get_remaining_duration(&1),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            map.get_remaining_duration(&100),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(map.get(&1000), Some(&"service session"));
+        assert_eq!(map.get_remaining_duration(&1000), None);
+    }
+
+    #[test]
+    fn nostd_insert_with_ttl_rules() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, &str, u32> = TimedMap::new(clock)
+            .ttl_prefix_rule("img:", Duration::from_secs(3600))
+            .ttl_prefix_rule("api:", Duration::from_secs(30))
+            .ttl_rule(|k: &&str| k.ends_with(":tmp"), Duration::from_secs(5))
+            .ttl_fn(|_: &&str, _: &u32| Some(Duration::from_secs(60)));
+
+        map.insert("img:logo", 1);
+        map.insert("api:tmp", 2);
+        map.insert("user:tmp", 3);
+        map.insert("user:1", 4);
+
+        assert_eq!(
+            map.get_remaining_duration(&"img:logo"),
+            Some(Duration::from_secs(3600))
+        );
+        // The first matching rule wins.
+        assert_eq!(
+            map.get_remaining_duration(&"api:tmp"),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            map.get_remaining_duration(&"user:tmp"),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            map.get_remaining_duration(&"user:1"),
+            Some(Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn nostd_ttl_histogram() {
+        let clock = ManualClock::new(1000);
+        let bounds = [Duration::from_secs(10), Duration::from_secs(60)];
+        let mut map = TimedMap::new(clock).ttl_histogram_buckets(&bounds);
+
+        map.insert_expirable_unchecked(1, "value", Duration::from_secs(5));
+        map.insert_expirable_unchecked(2, "value", Duration::from_secs(10));
+        map.insert_expirable_unchecked(3, "value", Duration::from_secs(30));
+        map.insert_expirable_unchecked(4, "value", Duration::from_secs(3600));
+        map.insert_expirable_unchecked(5, "value", Duration::from_secs(1));
+        map.insert_constant_unchecked(6, "value");
+        assert_eq!(map.ttl_histogram(), Some([3, 1, 1].as_slice()));
+
+        // Expired entries are excluded even if they are not dropped yet, and entries move to
+        // shorter buckets as time passes.
+        map.clock.set(1006);
+        assert_eq!(map.ttl_histogram(), Some([1, 1, 1].as_slice()));
+        map.clock.set(3000);
+        assert_eq!(map.ttl_histogram(), Some([0, 0, 1].as_slice()));
+
+        // Updates, removals and bulk changes are counted as they happen.
+        map.update_expiration_status(&4, Some(Duration::from_secs(5)));
+        map.insert_expirable_unchecked(7, "value", Duration::from_secs(120));
+        assert_eq!(map.ttl_histogram(), Some([1, 0, 1].as_slice()));
+        map.extend_all(Duration::from_secs(30));
+        assert_eq!(map.ttl_histogram(), Some([0, 1, 1].as_slice()));
+        map.remove(&7);
+        map.drop_expired_entries();
+        assert_eq!(map.ttl_histogram(), Some([0, 1, 0].as_slice()));
+        map.clear();
+        assert_eq!(map.ttl_histogram(), Some([0, 0, 0].as_slice()));
+
+        // Clocks going backwards recount the index.
+        map.insert_expirable_unchecked(8, "value", Duration::from_secs(30));
+        map.clock.set(2990);
+        assert_eq!(map.ttl_histogram(), Some([0, 1, 0].as_slice()));
+
+        assert_eq!(
+            TimedMap::<_, u32, u32>::new(ManualClock::new(0)).ttl_histogram(),
+            None
+        );
+    }
+
+    #[test]
+    fn nostd_keys_expiring_within() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        map.insert_expirable_unchecked(1, "value", Duration::from_secs(30));
+        map.insert_expirable_unchecked(2, "value", Duration::from_secs(5));
+        map.insert_expirable_unchecked(3, "value", Duration::from_secs(31));
+        map.insert_expirable_unchecked(4, "value", Duration::from_secs(1));
+        map.insert_constant_unchecked(5, "value");
+
+        assert_eq!(map.keys_expiring_within(Duration::from_secs(30)), [4, 2, 1]);
+
+        // Expired entries are excluded even if they are not dropped yet.
+        map.clock.set(1002);
+        assert_eq!(map.keys_expiring_within(Duration::from_secs(3)), [2]);
+        assert!(map.keys_expiring_within(Duration::ZERO).is_empty());
+        assert_eq!(map.keys_expiring_within(Duration::MAX), [2, 1, 3]);
+    }
+
+    #[test]
+    fn nostd_entries_expiring_at() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        map.insert_expirable_unchecked(1, "a", Duration::from_secs(10));
+        map.insert_expirable_unchecked(2, "b", Duration::from_secs(10));
+        map.insert_expirable_unchecked(3, "c", Duration::from_secs(20));
+        map.insert_constant_unchecked(4, "d");
+
+        let deadline = Duration::from_secs(1010);
+        assert_eq!(
+            map.entries_expiring_at(deadline).collect::<Vec<_>>(),
+            [(&1, &"a"), (&2, &"b")]
+        );
+        assert_eq!(
+            map.entries_expiring_at(Duration::from_secs(1015)).count(),
+            0
+        );
+
+        // Expired entries are excluded even if they are not dropped yet.
+        map.clock.set(1011);
+        assert_eq!(map.entries_expiring_at(deadline).count(), 0);
+    }
+
+    #[test]
+    fn nostd_bulk_ttl_updates() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        map.insert_expirable_unchecked(1, "tenant:1", Duration::from_secs(10));
+        map.insert_expirable_unchecked(2, "tenant:2", Duration::from_secs(20));
+        map.insert_expirable_unchecked(3, "tenant:1", Duration::from_secs(30));
+        map.insert_expirable_unchecked(4, "expired", Duration::from_secs(1));
+        map.insert_constant_unchecked(5, "constant");
+        map.clock.set(1005);
+
+        assert_eq!(map.extend_all(Duration::from_secs(600)), 3);
+        assert_eq!(
+            map.get_remaining_duration(&1),
+            Some(Duration::from_secs(605))
+        );
+        assert_eq!(
+            map.get_remaining_duration(&3),
+            Some(Duration::from_secs(625))
+        );
+        assert_eq!(map.get(&4), None);
+        assert_eq!(map.get_remaining_duration(&5), None);
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        assert_eq!(
+            map.set_ttl_where(|_, v| *v == "tenant:1", Duration::from_secs(60)),
+            2
+        );
+        assert_eq!(
+            map.get_remaining_duration(&1),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            map.get_remaining_duration(&2),
+            Some(Duration::from_secs(615))
+        );
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        assert_eq!(map.set_ttl_for_all(Duration::from_secs(5)), 3);
+        assert_eq!(map.keys_expiring_within(Duration::from_secs(5)), [1, 2, 3]);
+        assert_eq!(map.get(&5), Some(&"constant"));
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        // Far deadlines saturate instead of wrapping into the past.
+        assert_eq!(map.set_ttl_where(|k, _| *k == 1, Duration::MAX), 1);
+        assert_eq!(map.extend_all(Duration::MAX), 3);
+        assert_eq!(map.get(&2), Some(&"tenant:2"));
+        assert_eq!(
+            map.status(&3),
+            Some(EntryStatus::ExpiresAtSeconds(u64::MAX))
+        );
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn nostd_merge_with_resolver() {
+        let mut live = TimedMap::new(ManualClock::new(1000));
+        live.insert_expirable_unchecked(1, 10, Duration::from_secs(30));
+        live.insert_constant_unchecked(2, 20);
+        live.insert_expirable_unchecked(3, 30, Duration::from_secs(1));
+
+        let mut warmed = TimedMap::new(ManualClock::new(5000));
+        warmed.insert_expirable_unchecked(1, 11, Duration::from_secs(60));
+        warmed.insert_expirable_unchecked(3, 31, Duration::from_secs(60));
+        warmed.insert_expirable_unchecked(4, 40, Duration::from_secs(90));
+        warmed.insert_expirable_unchecked(5, 50, Duration::from_secs(1));
+        warmed.clock.set(5005);
+
+        live.clock.set(1005);
+        live.merge(warmed, |_, current, incoming| {
+            (current.0 + incoming.0, current.1.max(incoming.1))
+        });
+
+        // Colliding entries are resolved, the rest keep their remaining durations.
+        assert_eq!(live.get(&1), Some(&21));
+        assert_eq!(
+            live.get_remaining_duration(&1),
+            Some(Duration::from_secs(55))
+        );
+        assert_eq!(live.get(&2), Some(&20));
+        assert_eq!(live.get(&3), Some(&31));
+        assert_eq!(
+            live.get_remaining_duration(&4),
+            Some(Duration::from_secs(85))
+        );
+        assert_eq!(live.get(&5), None);
+        assert_eq!(live.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn nostd_merge_updates_collisions_in_place() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static CALLBACKS: AtomicUsize = AtomicUsize::new(0);
+
+        let mut live = TimedMap::new(ManualClock::new(1000))
+            .track_changes()
+            .audit_log(4);
+        live.insert_expirable_with_callback(1, 10, Duration::from_secs(30), |_, _| {
+            CALLBACKS.fetch_add(1, Ordering::Relaxed);
+        });
+        live.take_changes();
+
+        let mut warmed = TimedMap::new(ManualClock::new(1000));
+        warmed.insert_constant(1, 11);
+
+        live.merge(warmed, |_, current, incoming| {
+            (current.0 + incoming.0, incoming.1)
+        });
+
+        assert_eq!(live.get(&1), Some(&21));
+        assert_eq!(live.get_remaining_duration(&1), None);
+        assert_eq!(
+            live.take_changes().into_iter().collect::<Vec<_>>(),
+            [(1, Change::Updated)]
+        );
+        assert_eq!(live.recent_ops().count(), 1);
+        assert_eq!(CALLBACKS.load(Ordering::Relaxed), 0);
+        assert_eq!(live.check_invariants(), Ok(()));
+
+        live.remove(&1);
+        assert_eq!(CALLBACKS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn nostd_merge_settles_the_other_map() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static CALLBACKS: AtomicUsize = AtomicUsize::new(0);
+
+        let mut live = TimedMap::new(ManualClock::new(1005));
+
+        let mut warmed = TimedMap::new(ManualClock::new(1000));
+        warmed.insert_expirable_with_callback(1, 10, Duration::from_secs(1), |_, _| {
+            CALLBACKS.fetch_add(1, Ordering::Relaxed);
+        });
+        warmed.insert_expirable_unchecked(2, 20, Duration::from_secs(60));
+        warmed.pin(&2);
+        warmed.extend_expirable([(3, 30)], Duration::MAX);
+        warmed.clock.set(1002);
+
+        live.merge(warmed, |_, current, _| current);
+
+        // Expired entries are reported to their callbacks, pins resume and far deadlines
+        // saturate.
+        assert_eq!(CALLBACKS.load(Ordering::Relaxed), 1);
+        assert_eq!(live.get(&1), None);
+        assert_eq!(live.expires_at(&2), Some(1065));
+        assert_eq!(live.expires_at(&3), Some(u64::MAX));
+        assert_eq!(live.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn nostd_partition() {
+        let mut map = TimedMap::new(ManualClock::new(1000));
+        map.insert_expirable_unchecked(1, "tenant:1", Duration::from_secs(60));
+        map.insert_constant_unchecked(2, "tenant:1");
+        map.insert_expirable_unchecked(3, "tenant:2", Duration::from_secs(60));
+        map.insert_expirable_unchecked(4, "tenant:1", Duration::from_secs(1));
+        map.clock.set(1010);
+
+        let tenant = map.partition(ManualClock::new(0), |_, v| *v == "tenant:1");
+
+        assert_eq!(tenant.get(&1), Some(&"tenant:1"));
+        assert_eq!(
+            tenant.get_remaining_duration(&1),
+            Some(Duration::from_secs(50))
+        );
+        assert_eq!(tenant.get(&2), Some(&"tenant:1"));
+        assert_eq!(tenant.get_remaining_duration(&2), None);
+        assert_eq!(tenant.get_unchecked(&4), None);
+        assert_eq!(tenant.check_invariants(), Ok(()));
+
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map.get(&3), Some(&"tenant:2"));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn nostd_partition_keeps_configuration_pins_and_callbacks() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static CALLBACKS: AtomicUsize = AtomicUsize::new(0);
+
+        let mut map = TimedMap::new(ManualClock::new(1000))
+            .name("sessions")
+            .expiration_tick_cap(8)
+            .track_changes()
+            .ttl_histogram_buckets(&[Duration::from_secs(30)]);
+        map.insert_expirable_with_callback(1, "tenant:1", Duration::from_secs(20), |_, _| {
+            CALLBACKS.fetch_add(1, Ordering::Relaxed);
+        });
+        map.insert_expirable(2, "tenant:1", Duration::from_secs(60));
+        map.pin(&2);
+        map.clock.set(1010);
+
+        let mut tenant = map.partition(ManualClock::new(0), |_, v| *v == "tenant:1");
+        assert_eq!(CALLBACKS.load(Ordering::Relaxed), 0);
+        assert!(map.take_pin(&2).is_none());
+
+        assert_eq!(tenant.get_name(), Some("sessions"));
+        assert_eq!(tenant.expiration_tick_cap, 8);
+        assert_eq!(tenant.ttl_histogram(), Some(&[1, 0][..]));
+        assert_eq!(tenant.take_changes().len(), 2);
+
+        assert_eq!(tenant.pinned_remaining(&2), Some(Some(60)));
+        tenant.unpin(&2);
+        assert_eq!(
+            tenant.get_remaining_duration(&2),
+            Some(Duration::from_secs(60))
+        );
+
+        tenant.remove(&1);
+        assert_eq!(CALLBACKS.load(Ordering::Relaxed), 1);
+        assert_eq!(tenant.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn nostd_iter_prefix() {
+        use alloc::string::String;
+
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, String, u32> = TimedMap::new(clock);
+
+        map.insert_constant_unchecked("acme:1".into(), 1);
+        map.insert_constant_unchecked("acme:2".into(), 2);
+        map.insert_expirable_unchecked("acme:3".into(), 3, Duration::from_secs(1));
+        map.insert_constant_unchecked("acmf:1".into(), 4);
+        map.insert_constant_unchecked("globex:1".into(), 5);
+        map.clock.set(1002);
+
+        let values: Vec<u32> = map.iter_prefix("acme:").map(|(_, v)| *v).collect();
+        assert_eq!(values, [1, 2]);
+        assert_eq!(map.iter_prefix("initech:").count(), 0);
+        assert_eq!(map.iter_prefix("").count(), 4);
+    }
+
+    #[test]
+    fn nostd_map_values_in_place_keeps_expiration() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        map.insert_expirable_unchecked(1, 10, Duration::from_secs(60));
+        map.insert_constant_unchecked(2, 20);
+        map.insert_expirable_unchecked(3, 30, Duration::from_secs(1));
+        map.clock.set(1010);
+
+        map.map_values_in_place(|k, v| *v += k);
+        assert_eq!(map.get(&1), Some(&11));
+        assert_eq!(
+            map.get_remaining_duration(&1),
+            Some(Duration::from_secs(50))
+        );
+        assert_eq!(map.get(&2), Some(&22));
+        #[cfg(feature = "versions")]
+        assert_eq!(map.get_versioned(&2), Some((&22, 2)));
+        assert_eq!(map.get_unchecked(&3), Some(&30));
+
+        let res = map.try_map_values_in_place(|_, v| match *v {
+            22 => Err("rejected"),
+            _ => {
+                *v = 0;
+                Ok(())
+            }
+        });
+        assert_eq!(res, Err("rejected"));
+        assert_eq!(map.get(&2), Some(&22));
+    }
+
+    #[test]
+    fn nostd_retain_unexpired_repairs_stale_index() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        map.insert_expirable_unchecked(1, "expired", Duration::from_secs(1));
+        map.insert_expirable_unchecked(2, "live", Duration::from_secs(60));
+        map.insert_constant_unchecked(3, "constant");
+        map.expiries.clear();
+        map.clock.set(1002);
+
+        // The cleanup pass can't see entries missing from the index.
+        map.drop_expired_entries();
+        assert_eq!(map.get_unchecked(&1), Some(&"expired"));
+        assert!(map.check_invariants().is_err());
+
+        assert_eq!(map.retain_unexpired(), 1);
+        assert_eq!(map.get_unchecked(&1), None);
+        assert_eq!(map.get(&2), Some(&"live"));
+        assert_eq!(map.get(&3), Some(&"constant"));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn nostd_pinned_entries_dont_expire() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        map.insert_expirable(1, "state", Duration::from_secs(10));
+        map.insert_expirable(2, "state", Duration::from_secs(10));
+        map.insert_constant(3, "state");
+        assert!(!map.pin(&4));
+
+        map.clock.set(1004);
+        assert!(map.pin(&1));
+        assert!(map.pin(&2));
+        assert!(map.pin(&3));
+        assert!(map.is_pinned(&1));
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        map.clock.set(1100);
+        map.drop_expired_entries();
+        assert_eq!(map.get(&1), Some(&"state"));
+
+        // Unpinning resumes the remaining duration or sets a new one.
+        assert!(map.unpin(&1));
+        assert!(!map.unpin(&1));
+        assert_eq!(map.get_remaining_duration(&1), Some(Duration::from_secs(6)));
+        assert!(map.unpin_with_duration(&2, Some(Duration::from_secs(30))));
+        assert_eq!(
+            map.get_remaining_duration(&2),
+            Some(Duration::from_secs(30))
+        );
+        assert!(map.unpin(&3));
+        assert_eq!(map.get(&3), Some(&"state"));
+        assert_eq!(map.get_remaining_duration(&3), None);
+
+        // Far durations saturate instead of wrapping into the past.
+        map.pin(&2);
+        assert!(map.unpin_with_duration(&2, Some(Duration::MAX)));
+        assert_eq!(map.expires_at(&2), Some(u64::MAX));
+        map.pin(&2);
+        assert!(map.unpin(&2));
+        assert_eq!(map.expires_at(&2), Some(u64::MAX));
+
+        // Re-inserting drops the pin.
+        map.pin(&1);
+        map.insert_expirable(1, "new state", Duration::from_secs(5));
+        assert!(!map.is_pinned(&1));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn nostd_entry_callbacks() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static EXPIRED: AtomicUsize = AtomicUsize::new(0);
+        static REMOVED: AtomicUsize = AtomicUsize::new(0);
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        map.insert_expirable_with_callback(1, "task", Duration::from_secs(10), |_, _| {
+            EXPIRED.fetch_add(1, Ordering::Relaxed);
+        });
+        map.insert_expirable_with_callback(2, "task", Duration::from_secs(60), |k, v| {
+            assert_eq!((*k, *v), (2, "task"));
+            REMOVED.fetch_add(1, Ordering::Relaxed);
+        });
+        map.insert_expirable_with_callback(3, "task", Duration::from_secs(60), |_, _| {
+            REMOVED.fetch_add(1, Ordering::Relaxed);
+        });
+
+        map.clock.set(1011);
+        map.drop_expired_entries();
+        assert_eq!(EXPIRED.load(Ordering::Relaxed), 1);
+
+        map.remove(&2);
+        map.insert_constant(3, "replaced");
+        // Misses are not recorded.
+        map.remove(&3);
+        assert_eq!(REMOVED.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn nostd_insert_hook() {
+        use core::sync::atomic::{AtomicU64, Ordering};
+
+        static TOTAL_SIZE: AtomicU64 = AtomicU64::new(0);
+        static LAST_EXPIRY: AtomicU64 = AtomicU64::new(0);
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock).insert_hook(|_: &u32, v: &u64, expiry| {
+            TOTAL_SIZE.fetch_add(*v, Ordering::Relaxed);
+            if let Expiry::After(duration) = expiry {
+                LAST_EXPIRY.store(duration.as_secs(), Ordering::Relaxed);
+            }
+        });
+
+        map.insert_expirable(1, 10, Duration::from_secs(30));
+        assert_eq!(LAST_EXPIRY.load(Ordering::Relaxed), 30);
+        map.insert_constant(2, 20);
+        map.insert_constant(2, 5);
+        map.map_values_in_place(|_, v| *v = 100);
+
+        assert_eq!(TOTAL_SIZE.load(Ordering::Relaxed), 235);
+    }
+
+    #[test]
+    fn nostd_remove_expiring_before_and_after() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        map.insert_expirable_unchecked(1, "value", Duration::from_secs(5));
+        map.insert_expirable_unchecked(2, "value", Duration::from_secs(600));
+        map.insert_expirable_unchecked(3, "value", Duration::from_secs(601));
+        map.insert_expirable_unchecked(4, "value", Duration::from_secs(3600));
+        map.insert_constant_unchecked(5, "value");
+
+        assert_eq!(map.remove_expiring_before(Duration::from_secs(601)), 2);
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map.get(&3), Some(&"value"));
+
+        assert_eq!(map.remove_expiring_after(Duration::from_secs(3600)), 1);
+        assert_eq!(map.get(&4), None);
+        assert_eq!(map.get(&3), Some(&"value"));
+        assert_eq!(map.get(&5), Some(&"value"));
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        // Far cutoffs saturate instead of wrapping into the past.
+        assert_eq!(map.remove_expiring_after(Duration::MAX), 0);
+        assert_eq!(map.remove_expiring_before(Duration::MAX), 1);
+        assert_eq!(map.get(&3), None);
+        assert_eq!(map.get(&5), Some(&"value"));
+    }
+
+    #[test]
+    fn nostd_expiry_index_stats() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+        assert_eq!(map.expiry_index_stats(), ExpiryIndexStats::default());
+
+        map.insert_expirable_unchecked(1, "value", Duration::from_secs(10));
+        map.insert_expirable_unchecked(2, "value", Duration::from_secs(10));
+        map.insert_expirable_unchecked(3, "value", Duration::from_secs(10));
+        map.insert_expirable_unchecked(4, "value", Duration::from_secs(70));
+        map.insert_constant_unchecked(5, "value");
+
+        assert_eq!(
+            map.expiry_index_stats(),
+            ExpiryIndexStats {
+                buckets: 2,
+                entries: 4,
+                largest_bucket: 3,
+                span: Duration::from_secs(60),
+            }
+        );
+    }
+
+    #[test]
+    fn nostd_count_by_status() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+        assert_eq!(map.count_by_status(), EntryCounts::default());
+        assert_eq!(map.summary(), MapSummary::default());
+
+        map.insert_expirable_unchecked(1, "value", Duration::from_secs(10));
+        map.insert_expirable_unchecked(2, "value", Duration::from_secs(10));
+        map.insert_expirable_unchecked(3, "value", Duration::from_secs(70));
+        map.insert_constant_unchecked(4, "value");
+        // Replacing and re-timing entries keeps the totals in line.
+        map.insert_constant_unchecked(3, "value");
+        map.insert_expirable_unchecked(4, "value", Duration::from_secs(70));
+        map.update_expiration_status(&4, Some(Duration::from_secs(80)));
+        map.insert_constant_unchecked(5, "value");
+        map.remove(&5);
+
+        assert_eq!(
+            map.count_by_status(),
+            EntryCounts {
+                constant: 1,
+                expirable_live: 3,
+                expired_pending: 0,
+            }
+        );
+
+        map.clock.set(1011);
+        assert_eq!(
+            map.count_by_status(),
+            EntryCounts {
+                constant: 1,
+                expirable_live: 1,
+                expired_pending: 2,
+            }
+        );
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.len_expired(), 2);
+        assert_eq!(map.len_unchecked(), 4);
+        assert_eq!(
+            map.summary(),
+            MapSummary {
+                counts: map.count_by_status(),
+                next_expiration: Some(Duration::from_secs(69)),
+            }
+        );
+
+        map.drop_expired_entries();
+        assert_eq!(
+            map.count_by_status(),
+            EntryCounts {
+                constant: 1,
+                expirable_live: 1,
+                expired_pending: 0,
+            }
+        );
+
+        map.clear();
+        assert_eq!(map.count_by_status(), EntryCounts::default());
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn nostd_check_invariants() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        map.insert_expirable_unchecked(1, "value", Duration::from_secs(10));
+        map.insert_expirable(2, "value", Duration::from_secs(10));
+        map.insert_expirable(2, "value", Duration::from_secs(20));
+        map.insert_constant(1, "value");
+        map.insert_expirable(3, "value", Duration::from_secs(5));
+        // Misses are not recorded.
+        map.remove(&3);
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        map.clock.set(1021);
+        map.drop_expired_entries();
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        map.expirable_len += 1;
+        assert_eq!(
+            map.check_invariants(),
+            Err(InvariantViolation::ExpirableCountMismatch)
+        );
+        map.expirable_len -= 1;
+
+        map.expiries.entry(2000).or_default().insert(1);
+        assert_eq!(
+            map.check_invariants(),
+            Err(InvariantViolation::DanglingIndexKey)
+        );
+
+        map.expiries.clear();
+        map.insert_expirable_unchecked(4, "value", Duration::from_secs(10));
+        map.expiries.clear();
+        assert_eq!(
+            map.check_invariants(),
+            Err(InvariantViolation::MissingFromIndex)
+        );
+    }
+
+    #[test]
+    fn nostd_debug_shows_remaining_durations() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        map.insert_expirable_unchecked(1, "expirable", Duration::from_secs(60));
+        map.insert_expirable_unchecked(2, "expired", Duration::from_secs(5));
+        map.insert_constant_unchecked(3, "constant");
+        map.clock.set(1010);
+
+        assert_eq!(
+            alloc::format!("{map:?}"),
+            r#"{1: "expirable" (expires in 50s), 2: "expired" (expired 5s ago), 3: "constant" (constant)}"#
+        );
+
+        map.suspend_expiration();
+        map.clock.set(1070);
+        assert_eq!(map.get(&1), Some(&"expirable"));
+        assert_eq!(
+            alloc::format!("{map:?}"),
+            r#"{1: "expirable" (expires in 50s), 2: "expired" (expired 5s ago), 3: "constant" (constant)}"#
+        );
+    }
+
+    #[test]
+    fn nostd_dump_orders_entries_by_deadline() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        map.insert_constant_unchecked(1, "constant");
+        map.insert_expirable_unchecked(2, "expirable", Duration::from_secs(60));
+        map.insert_expirable_unchecked(3, "expired", Duration::from_secs(5));
+        map.clock.set(1010);
+
+        let mut report = alloc::string::String::new();
+        map.dump(&mut report).unwrap();
+
+        assert_eq!(
+            report,
+            "3 entries (2 expirable, 1 constant)\n\
+             3: expired 5s ago\n\
+             2: expires in 50s\n\
+             1: constant\n"
+        );
+    }
+
+    #[test]
+    fn nostd_named_map() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock).name("dns_cache");
+        map.insert_constant_unchecked(1, "constant");
+
+        assert_eq!(map.get_name(), Some("dns_cache"));
+        assert_eq!(
+            alloc::format!("{map:?}"),
+            r#"dns_cache {1: "constant" (constant)}"#
+        );
+    }
+
+    #[test]
+    fn nostd_audit_log_records_recent_ops() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock).audit_log(4);
+        assert_eq!(
+            TimedMap::<ManualClock, u32, &str>::new(ManualClock::new(0))
+                .recent_ops()
+                .count(),
+            0
+        );
+
+        map.insert_expirable(1, "expirable", Duration::from_secs(10));
+        map.insert_constant(2, "constant");
+        map.insert_constant(2, "constant");
+        // Misses are not recorded.
+        map.remove(&3);
+
+        map.clock.set(1011);
+        map.drop_expired_entries();
+        map.remove(&2);
 
-        let res = self.insert(k.clone(), v, Some(expires_at));
+        let ops: Vec<_> = map
+            .recent_ops()
+            .map(|r| (r.op, r.key, r.at.as_secs(), r.outcome))
+            .collect();
+        assert_eq!(
+            ops,
+            [
+                (AuditOp::Insert, 2, 1000, AuditOutcome::Created),
+                (AuditOp::Insert, 2, 1000, AuditOutcome::Replaced),
+                (AuditOp::Expire, 1, 1011, AuditOutcome::Removed),
+                (AuditOp::Remove, 2, 1011, AuditOutcome::Removed),
+            ]
+        );
+    }
 
-        self.expiries.insert(expires_at, k);
+    #[test]
+    fn nostd_take_changes() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock).track_changes();
 
-        if self.expiration_tick >= self.expiration_tick_cap {
-            self.drop_expired_entries_inner(now);
-            self.expiration_tick = 0;
-        }
+        map.insert_constant(1, "value");
+        map.insert_expirable(2, "value", Duration::from_secs(10));
+        map.insert_constant(3, "value");
+        map.take_changes();
 
-        res
+        map.insert_constant(1, "updated");
+        map.insert_constant(4, "inserted");
+        map.insert_constant(4, "updated");
+        // Misses are not recorded.
+        map.remove(&3);
+        map.clock.set(1011);
+        map.drop_expired_entries();
+
+        let changes: Vec<_> = map.take_changes().into_iter().collect();
+        assert_eq!(
+            changes,
+            [
+                (1, Change::Updated),
+                (2, Change::Expired),
+                (3, Change::Removed),
+                (4, Change::Inserted),
+            ]
+        );
+        assert!(map.take_changes().is_empty());
     }
 
-    /// Inserts a key-value pair with an expiration duration, without checking the expired
-    /// entries.
-    ///
-    /// If a value already exists for the given key, it will be updated and then
-    /// the old one will be returned.
-    ///
-    /// If you want to check the expired entries, consider using `TimedMap::insert_expirable`
-    /// instead.
-    pub fn insert_expirable_unchecked(&mut self, k: K, v: V, duration: Duration) -> Option<V> {
-        let now = self.clock.elapsed_seconds_since_creation();
-        let expires_at = now + duration.as_secs();
-        self.insert(k, v, Some(expires_at))
+    /// Returns a map tracking changes with an expirable entry for 1 and a constant one for 2.
+    fn tracking_map() -> TimedMap<ManualClock, u32, u32> {
+        let mut map = TimedMap::new(ManualClock::new(1000)).track_changes();
+        map.insert_expirable(1, 1, Duration::from_secs(10));
+        map.insert_constant(2, 2);
+        map.take_changes();
+        map
     }
 
-    /// Inserts a key-value pair with that doesn't expire, and then drops the
-    /// expired entries.
-    ///
-    /// If a value already exists for the given key, it will be updated and then
-    /// the old one will be returned.
-    ///
-    /// If you don't want to check the expired entries, consider using `TimedMap::insert_constant_unchecked`
-    /// instead.
-    pub fn insert_constant(&mut self, k: K, v: V) -> Option<V> {
-        self.expiration_tick += 1;
-        let res = self.insert(k, v, None);
+    /// Returns the changes recorded since the previous call.
+    fn changes(map: &mut TimedMap<ManualClock, u32, u32>) -> Vec<(u32, Change)> {
+        map.take_changes().into_iter().collect()
+    }
 
-        let now = self.clock.elapsed_seconds_since_creation();
-        if self.expiration_tick >= self.expiration_tick_cap {
-            self.drop_expired_entries_inner(now);
-            self.expiration_tick = 0;
-        }
+    #[test]
+    fn nostd_clear_reports_expired_entries_as_expired() {
+        let mut map = tracking_map().audit_log(4);
+        map.insert_constant(3, 3);
+        map.take_changes();
 
-        res
+        map.clock.set(1011);
+        map.clear();
+
+        assert_eq!(
+            changes(&mut map),
+            [
+                (1, Change::Expired),
+                (2, Change::Removed),
+                (3, Change::Removed)
+            ]
+        );
+        assert_eq!(
+            map.recent_ops()
+                .skip(1)
+                .map(|r| (r.op, r.key))
+                .collect::<Vec<_>>(),
+            [
+                (AuditOp::Expire, 1),
+                (AuditOp::Remove, 2),
+                (AuditOp::Remove, 3)
+            ]
+        );
+        #[cfg(feature = "stats")]
+        {
+            assert_eq!(map.stats().reaped, 1);
+            assert_eq!(map.stats().removals, 2);
+        }
     }
 
-    /// Inserts a key-value pair with that doesn't expire without checking the expired
-    /// entries.
-    ///
-    /// If a value already exists for the given key, it will be updated and then
-    /// the old one will be returned.
-    ///
-    /// If you want to check the expired entries, consider using `TimedMap::insert_constant`
-    /// instead.
-    pub fn insert_constant_unchecked(&mut self, k: K, v: V) -> Option<V> {
-        self.expiration_tick += 1;
-        self.insert(k, v, None)
+    #[test]
+    fn nostd_update_expiration_status_is_tracked() {
+        let mut map = tracking_map();
+        map.update_expiration_status(&2, Some(Duration::from_secs(10)));
+        assert_eq!(changes(&mut map), [(2, Change::Updated)]);
     }
 
-    /// Removes a key-value pair from the map and returns the associated value if present
-    /// and not expired.
-    ///
-    /// If you want to retrieve the entry after removal even if it is expired, consider using
-    /// `TimedMap::remove_unchecked`.
-    #[inline(always)]
-    pub fn remove(&mut self, k: &K) -> Option<V> {
-        self.map
-            .remove(k)
-            .filter(|v| {
-                if let EntryStatus::ExpiresAtSeconds(expires_at_seconds) = v.status() {
-                    self.expiries.remove(expires_at_seconds);
-                }
+    #[test]
+    fn nostd_set_ttl_where_is_tracked() {
+        let mut map = tracking_map();
+        map.set_ttl_where(|_, v| *v == 1, Duration::from_secs(20));
+        assert_eq!(changes(&mut map), [(1, Change::Updated)]);
+    }
 
-                !v.is_expired(self.clock.elapsed_seconds_since_creation())
-            })
-            .map(|v| v.owned_value())
+    #[test]
+    fn nostd_extend_all_is_tracked() {
+        let mut map = tracking_map();
+        map.extend_all(Duration::from_secs(20));
+        assert_eq!(changes(&mut map), [(1, Change::Updated)]);
     }
 
-    /// Removes a key-value pair from the map and returns the associated value if present,
-    /// regardless of expiration status.
-    ///
-    /// If you only want the entry when it is not expired, consider using `TimedMap::remove`.
-    #[inline(always)]
-    pub fn remove_unchecked(&mut self, k: &K) -> Option<V> {
-        self.map
-            .remove(k)
-            .filter(|v| {
-                if let EntryStatus::ExpiresAtSeconds(expires_at_seconds) = v.status() {
-                    self.expiries.remove(expires_at_seconds);
-                }
+    #[test]
+    fn nostd_map_values_in_place_is_tracked() {
+        let mut map = tracking_map();
+        map.map_values_in_place(|_, v| *v += 1);
+        assert_eq!(
+            changes(&mut map),
+            [(1, Change::Updated), (2, Change::Updated)]
+        );
+    }
 
-                true
-            })
-            .map(|v| v.owned_value())
+    #[test]
+    fn nostd_get_mut_and_refresh_is_tracked() {
+        let mut map = tracking_map();
+        map.get_mut_and_refresh(&2, Duration::from_secs(20));
+        assert_eq!(changes(&mut map), [(2, Change::Updated)]);
     }
 
-    /// Clears expired entries from the map.
-    ///
-    /// Call this function when using `*_unchecked` inserts, as these do not
-    /// automatically clear expired entries.
-    #[inline(always)]
-    pub fn drop_expired_entries(&mut self) {
-        let now = self.clock.elapsed_seconds_since_creation();
-        self.drop_expired_entries_inner(now);
+    #[test]
+    fn nostd_insert_or_extend_is_tracked() {
+        let mut map = tracking_map();
+        map.insert_or_extend(1, 1, Duration::from_secs(10), |v, n| *v += n);
+        map.insert_or_extend(3, 3, Duration::from_secs(10), |v, n| *v += n);
+        assert_eq!(
+            changes(&mut map),
+            [(1, Change::Updated), (3, Change::Inserted)]
+        );
     }
 
-    fn drop_expired_entries_inner(&mut self, now_seconds: u64) {
-        // Iterates through `expiries` in order and drops expired ones.
-        while let Some((exp, key)) = self.expiries.iter().next() {
-            // It's safe to do early-break here as keys are sorted by expiration.
-            if *exp > now_seconds {
-                break;
-            }
+    #[test]
+    fn nostd_and_modify_is_tracked() {
+        let mut map = tracking_map();
+        map.entry(1).and_modify(|v| *v += 1);
+        map.entry_ref(&2).and_modify(|v| *v += 1);
+        assert_eq!(
+            changes(&mut map),
+            [(1, Change::Updated), (2, Change::Updated)]
+        );
+    }
 
-            self.map.remove(key);
-        }
+    #[test]
+    fn nostd_lease_is_tracked() {
+        let mut map = tracking_map();
+        drop(map.lease(&1, Duration::from_secs(20)));
+        assert_eq!(changes(&mut map), [(1, Change::Updated)]);
     }
-}
 
-#[cfg(test)]
-#[cfg(not(feature = "std"))]
-mod tests {
-    use super::*;
+    #[test]
+    #[cfg(feature = "versions")]
+    fn nostd_versioned_inserts() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+
+        assert_eq!(map.insert_if_version(1, "first", 1), Err(0));
+        assert_eq!(map.insert_if_version(1, "first", 0), Ok(1));
+        assert_eq!(map.get_versioned(&1), Some((&"first", 1)));
 
-    struct MockClock {
-        current_time: u64,
+        map.insert_expirable(1, "second", Duration::from_secs(10));
+        assert_eq!(map.get_versioned(&1), Some((&"second", 2)));
+        assert_eq!(map.insert_if_version(1, "stale write", 1), Err(2));
+        assert_eq!(map.insert_if_version(1, "third", 2), Ok(3));
+
+        // Expired entries count as absent, but versions keep increasing.
+        map.insert_expirable_unchecked(2, "expirable", Duration::from_secs(10));
+        map.clock.set(1011);
+        assert_eq!(map.get_versioned(&2), None);
+        assert_eq!(map.insert_if_version(2, "renewed", 0), Ok(2));
     }
 
-    impl Clock for MockClock {
-        fn elapsed_seconds_since_creation(&self) -> u64 {
-            self.current_time
+    #[test]
+    #[cfg(feature = "versions")]
+    fn nostd_in_place_updates_bump_versions() {
+        let mut map = TimedMap::new(ManualClock::new(1000));
+        map.insert_expirable(1, 1, Duration::from_secs(10));
+        let version = |map: &TimedMap<ManualClock, u32, u32>| map.get_versioned(&1).unwrap().1;
+
+        map.update_expiration_status(&1, Some(Duration::from_secs(20)));
+        assert_eq!(version(&map), 2);
+        map.set_ttl_where(|_, _| true, Duration::from_secs(30));
+        assert_eq!(version(&map), 3);
+        map.extend_all(Duration::from_secs(10));
+        assert_eq!(version(&map), 4);
+        map.map_values_in_place(|_, v| *v += 1);
+        assert_eq!(version(&map), 5);
+        map.get_mut_and_refresh(&1, Duration::from_secs(10));
+        assert_eq!(version(&map), 6);
+        map.insert_or_extend(1, 1, Duration::from_secs(10), |v, n| *v += n);
+        assert_eq!(version(&map), 7);
+        drop(map.lease(&1, Duration::from_secs(10)));
+        assert_eq!(version(&map), 8);
+
+        map.entry(1).and_modify(|v| *v += 1);
+        assert_eq!(version(&map), 9);
+        if let Entry::Occupied(mut entry) = map.entry(1) {
+            *entry.get_mut() += 1;
         }
+        assert_eq!(version(&map), 10);
+
+        // Pins only pause the lifetime.
+        map.pin(&1);
+        map.unpin(&1);
+        assert_eq!(version(&map), 10);
     }
 
     #[test]
-    fn nostd_insert_and_get_constant_entry() {
-        let clock = MockClock { current_time: 1000 };
-        let mut map: TimedMap<MockClock, u32, &str> = TimedMap::new(clock);
+    #[cfg(feature = "metadata")]
+    fn nostd_entry_metadata() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
 
-        map.insert_constant(1, "constant value");
+        map.insert_expirable(1, "value", Duration::from_secs(60));
+        assert_eq!(map.created_at(&1), Some(Duration::from_secs(1000)));
+        assert_eq!(map.last_accessed(&1), Some(Duration::from_secs(1000)));
 
-        assert_eq!(map.get(&1), Some(&"constant value"));
-        assert_eq!(map.get_remaining_duration(&1), None);
+        map.clock.set(1010);
+        map.get(&1);
+        map.get_unchecked(&1);
+        assert_eq!(map.created_at(&1), Some(Duration::from_secs(1000)));
+        assert_eq!(map.last_accessed(&1), Some(Duration::from_secs(1010)));
+
+        map.clock.set(1061);
+        assert_eq!(map.created_at(&1), None);
+        assert_eq!(map.last_accessed(&1), None);
     }
 
     #[test]
-    fn nostd_insert_and_get_expirable_entry() {
-        let clock = MockClock { current_time: 1000 };
-        let mut map: TimedMap<MockClock, u32, &str> = TimedMap::new(clock);
-        let duration = Duration::from_secs(60);
+    #[cfg(feature = "metadata")]
+    fn nostd_oldest_and_newest_entries() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
+        assert_eq!(map.oldest_entry(), None);
 
-        map.insert_expirable(1, "expirable value", duration);
+        map.insert_expirable(1, "first", Duration::from_secs(10));
+        map.clock.set(1005);
+        map.insert_constant(2, "second");
+        map.clock.set(1008);
+        map.insert_constant(3, "third");
 
-        assert_eq!(map.get(&1), Some(&"expirable value"));
-        assert_eq!(map.get_remaining_duration(&1), Some(duration));
+        assert_eq!(map.oldest_entry(), Some((&1, &"first")));
+        assert_eq!(map.newest_entry(), Some((&3, &"third")));
+
+        // Expired entries are skipped even if they are not dropped yet.
+        map.clock.set(1011);
+        assert_eq!(map.oldest_entry(), Some((&2, &"second")));
     }
 
     #[test]
-    fn nostd_expired_entry() {
-        let clock = MockClock { current_time: 1000 };
-        let mut map: TimedMap<MockClock, u32, &str> = TimedMap::new(clock);
-        let duration = Duration::from_secs(60);
+    #[cfg(feature = "metadata")]
+    fn nostd_iter_by_recency() {
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(clock);
 
-        // Insert entry that expires in 60 seconds
-        map.insert_expirable(1, "expirable value", duration);
+        map.insert_constant(1, "first");
+        map.insert_expirable(2, "second", Duration::from_secs(30));
+        map.clock.set(1005);
+        map.insert_constant(3, "third");
 
-        // Simulate time passage beyond expiration
-        let clock = MockClock { current_time: 1070 };
-        map.clock = clock;
+        map.clock.set(1010);
+        map.get(&1);
 
-        // The entry should be considered expired
-        assert_eq!(map.get(&1), None);
-        assert_eq!(map.get_remaining_duration(&1), None);
+        assert_eq!(
+            map.iter_by_recency().collect::<Vec<_>>(),
+            [(&1, &"first"), (&3, &"third"), (&2, &"second")]
+        );
+
+        // Iterating is not an access, and expired entries are skipped.
+        map.clock.set(1031);
+        assert_eq!(
+            map.iter_by_recency().collect::<Vec<_>>(),
+            [(&1, &"first"), (&3, &"third")]
+        );
     }
 
     #[test]
-    fn nostd_remove_entry() {
-        let clock = MockClock { current_time: 1000 };
-        let mut map: TimedMap<MockClock, u32, &str> = TimedMap::new(clock);
+    fn nostd_into_btree_map_skips_expired_entries() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
 
         map.insert_constant(1, "constant value");
+        map.insert_expirable(2, "expirable value1", Duration::from_secs(10));
+        map.insert_expirable(3, "expirable value2", Duration::from_secs(60));
 
-        assert_eq!(map.remove(&1), Some("constant value"));
-        assert_eq!(map.get(&1), None);
+        map.clock.set(1020);
+
+        let btree_map = map.into_btree_map();
+        assert_eq!(
+            btree_map.into_iter().collect::<Vec<_>>(),
+            [(1, "constant value"), (3, "expirable value2")]
+        );
     }
 
     #[test]
-    fn nostd_drop_expired_entries() {
-        let clock = MockClock { current_time: 1000 };
-        let mut map: TimedMap<MockClock, u32, &str> = TimedMap::new(clock);
+    fn nostd_sorted_keys_skip_expired_entries() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<ManualClock, u32, &str> = TimedMap::new(clock);
 
-        // Insert one constant and 2 expirable entries
-        map.insert_expirable(1, "expirable value1", Duration::from_secs(50));
-        map.insert_expirable(2, "expirable value2", Duration::from_secs(70));
         map.insert_constant(3, "constant value");
+        map.insert_expirable(2, "expirable value1", Duration::from_secs(10));
+        map.insert_expirable(1, "expirable value2", Duration::from_secs(60));
 
-        // Simulate time passage beyond the expiration of the first entry
-        let clock = MockClock { current_time: 1055 };
-        map.clock = clock;
+        map.clock.set(1011);
 
-        // Entry 1 should be removed and entry 2 and 3 should still exist
-        assert_eq!(map.get(&1), None);
-        assert_eq!(map.get(&2), Some(&"expirable value2"));
-        assert_eq!(map.get(&3), Some(&"constant value"));
+        assert_eq!(map.sorted_keys(), [1, 3]);
+        assert_eq!(map.iter_sorted().count(), 2);
+    }
 
-        // Simulate time passage again to expire second expirable entry
-        let clock = MockClock { current_time: 1071 };
-        map.clock = clock;
+    #[test]
+    fn nostd_const_constructor() {
+        let mut map = const { TimedMap::<ManualClock, u32, &str>::new(ManualClock::new(1000)) };
 
-        assert_eq!(map.get(&1), None);
-        assert_eq!(map.get(&2), None);
-        assert_eq!(map.get(&3), Some(&"constant value"));
+        map.insert_expirable(1, "expirable value", Duration::from_secs(60));
+        assert_eq!(map.get(&1), Some(&"expirable value"));
     }
 
     #[test]
-    fn nostd_update_existing_entry() {
-        let clock = MockClock { current_time: 1000 };
-        let mut map: TimedMap<MockClock, u32, &str> = TimedMap::new(clock);
+    fn nostd_boxed_dyn_clock() {
+        let clock: Box<dyn Clock> = Box::new(ManualClock::new(1000));
+        let mut map: TimedMap<Box<dyn Clock>, u32, &str> = TimedMap::new(clock);
 
-        map.insert_constant(1, "initial value");
-        assert_eq!(map.get(&1), Some(&"initial value"));
+        map.insert_expirable(1, "expirable value", Duration::from_secs(60));
+        assert_eq!(map.get(&1), Some(&"expirable value"));
 
-        // Update the value of the existing key and make it expirable
-        map.insert_expirable(1, "updated value", Duration::from_secs(15));
-        assert_eq!(map.get(&1), Some(&"updated value"));
+        map.clock = Box::new(ManualClock::new(1061));
+        assert_eq!(map.get(&1), None);
+    }
 
-        // Simulate time passage and expire the updated entry
-        let clock = MockClock { current_time: 1016 };
-        map.clock = clock;
+    #[test]
+    fn nostd_borrowed_clock() {
+        let clock = ManualClock::new(1000);
+        let mut map: TimedMap<&ManualClock, u32, &str> = TimedMap::new(&clock);
 
+        map.insert_expirable(1, "expirable value", Duration::from_secs(60));
+        assert_eq!(
+            map.get_remaining_duration(&1),
+            Some(Duration::from_secs(60))
+        );
+
+        clock.advance(61);
         assert_eq!(map.get(&1), None);
     }
 }
@@ -510,6 +5663,64 @@ mod std_tests {
         assert!(map.get_remaining_duration(&2).is_some());
     }
 
+    #[test]
+    fn std_expires_at() {
+        let mut map: TimedMap<StdClock, u32, &str> = TimedMap::new();
+        let before = SystemTime::now();
+
+        map.insert_constant(1, "constant value");
+        map.insert_expirable(2, "expirable value", Duration::from_secs(60));
+
+        assert_eq!(map.expires_at(&1), None);
+        assert_eq!(map.expires_at(&3), None);
+
+        // The deadline stays the same across calls.
+        let deadline = map.expires_at(&2).unwrap();
+        assert!(deadline >= before + Duration::from_secs(59));
+        assert!(deadline <= SystemTime::now() + Duration::from_secs(60));
+        assert_eq!(map.expires_at(&2), Some(deadline));
+
+        assert_eq!(
+            map.entries_expiring_at(deadline).collect::<Vec<_>>(),
+            [(&2, &"expirable value")]
+        );
+        assert_eq!(map.entries_expiring_at(SystemTime::UNIX_EPOCH).count(), 0);
+    }
+
+    #[test]
+    fn std_borrowed_key_lookups() {
+        use std::string::String;
+
+        for map_kind in [MapKind::BTreeMap, MapKind::HashMap, MapKind::VecMap] {
+            let mut map: TimedMap<StdClock, String, u32> = TimedMap::new_with_map_kind(map_kind);
+
+            map.insert_constant(String::from("constant"), 1);
+            map.insert_expirable(String::from("expirable"), 2, Duration::from_secs(60));
+
+            assert_eq!(map.get("constant"), Some(&1));
+            assert!(map.contains_key("expirable"));
+            assert!(!map.contains_key("missing"));
+            assert!(map.get_remaining_duration("expirable").is_some());
+
+            assert_eq!(map.remove("constant"), Some(1));
+            assert_eq!(map.remove_unchecked("constant"), None);
+            assert_eq!(map.get("constant"), None);
+        }
+    }
+
+    #[test]
+    fn std_remove_by_borrowed_form_without_to_owned() {
+        use std::boxed::Box;
+
+        // `str` can't be turned into a `Box<str>` through `ToOwned`.
+        let mut map: TimedMap<StdClock, Box<str>, u32> = TimedMap::new();
+        map.insert_constant("key".into(), 1);
+
+        assert_eq!(map.try_remove("missing"), Err(TimedMapError::EntryNotFound));
+        assert_eq!(map.remove("key"), Some(1));
+        assert_eq!(map.remove_unchecked("key"), None);
+    }
+
     #[test]
     fn std_expired_entry_removal() {
         let mut map: TimedMap<StdClock, u32, &str> = TimedMap::new();
@@ -605,4 +5816,209 @@ mod std_tests {
         assert_eq!(map.get(&1), Some(&"expirable value"));
         assert!(map.get_remaining_duration(&1).unwrap().as_secs() == 1);
     }
+
+    #[test]
+    fn std_vec_map_kind_promotion() {
+        let mut map: TimedMap<StdClock, u32, u32> = TimedMap::new_with_map_kind(MapKind::VecMap);
+
+        for i in 0..VEC_MAP_PROMOTION_THRESHOLD as u32 {
+            map.insert_constant(i, i);
+        }
+        assert!(matches!(map.map, GenericMap::VecMap(_)));
+
+        assert_eq!(map.insert_constant(0, 100), Some(0));
+        assert_eq!(map.remove(&1), Some(1));
+        assert_eq!(map.get(&1), None);
+
+        map.insert_constant(1, 1);
+        map.insert_expirable(100, 100, Duration::from_secs(60));
+        assert!(matches!(map.map, GenericMap::HashMap(_)));
+
+        assert_eq!(map.get(&0), Some(&100));
+        assert_eq!(map.get(&1), Some(&1));
+        assert_eq!(map.get(&100), Some(&100));
+    }
+
+    #[test]
+    fn std_compaction() {
+        let capacity = |map: &TimedMap<StdClock, u32, u32>| match &map.map {
+            GenericMap::HashMap(inner) => inner.capacity(),
+            _ => unreachable!(),
+        };
+
+        let mut map: TimedMap<StdClock, u32, u32> = TimedMap::new_with_map_kind(MapKind::HashMap);
+        for i in 0..10_000 {
+            map.insert_constant_unchecked(i, i);
+            map.insert_expirable_unchecked(i + 10_000, i, Duration::from_secs(60));
+        }
+        let full = capacity(&map);
+
+        // Half of the entries are still in use.
+        map.clear_constant();
+        assert!(capacity(&map) > full * 3 / 4);
+
+        map.clear_expirable();
+        assert!(capacity(&map) < full / 4);
+
+        // Explicit compaction ignores the occupancy threshold.
+        for i in 0..1000 {
+            map.insert_constant_unchecked(i, i);
+        }
+        for i in 0..600 {
+            map.remove(&i);
+        }
+        map.compact();
+        assert!(capacity(&map) < 1000);
+        assert_eq!(map.get(&999), Some(&999));
+    }
+
+    #[test]
+    fn std_convert_to() {
+        let mut map: TimedMap<StdClock, u32, u32> =
+            TimedMap::new_with_map_kind(MapKind::BTreeMap).expiration_tick_cap(10);
+        assert_eq!(map.map_kind(), MapKind::BTreeMap);
+
+        for i in 0..8 {
+            map.insert_constant(i, i);
+            map.insert_expirable(i + 8, i, Duration::from_secs(60));
+        }
+
+        map.convert_to(MapKind::VecMap);
+        assert_eq!(map.map_kind(), MapKind::VecMap);
+
+        map.convert_to(MapKind::HashMap);
+        assert_eq!(map.map_kind(), MapKind::HashMap);
+        assert_eq!(map.expiration_tick_cap, 10);
+        assert_eq!(map.get(&0), Some(&0));
+        assert_eq!(map.get_remaining_duration(&0), None);
+        assert_eq!(map.get(&15), Some(&7));
+        assert!(map
+            .get_remaining_duration(&15)
+            .is_some_and(|remaining| remaining <= Duration::from_secs(60)));
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        // Too many entries for a vector, so it gets promoted right away.
+        map.insert_constant(16, 16);
+        map.convert_to(MapKind::VecMap);
+        assert_eq!(map.map_kind(), MapKind::HashMap);
+        assert_eq!(map.len(), 17);
+    }
+
+    #[test]
+    fn std_export_and_restore() {
+        let mut map: TimedMap<StdClock, u32, &str> = TimedMap::new();
+
+        map.insert_constant(1, "constant value");
+        map.insert_expirable(2, "expirable value", Duration::from_secs(60));
+
+        let mut snapshot = map.export();
+        assert_eq!(snapshot.len(), 2);
+
+        // Entry that expired while the state was stored elsewhere.
+        snapshot.push((
+            3,
+            "expired value",
+            Some(SystemTime::now() - Duration::from_secs(1)),
+        ));
+
+        let mut restored: TimedMap<StdClock, u32, &str> = TimedMap::new();
+        restored.restore(snapshot);
+
+        assert_eq!(restored.get(&1), Some(&"constant value"));
+        assert_eq!(restored.get(&2), Some(&"expirable value"));
+        assert_eq!(restored.get_unchecked(&3), None);
+        assert!(restored.get_remaining_duration(&2).unwrap() > Duration::from_secs(58));
+    }
+
+    #[test]
+    fn std_iter_prefix_with_hash_map() {
+        let mut map: TimedMap<StdClock, Vec<u8>, u32> =
+            TimedMap::new_with_map_kind(MapKind::HashMap);
+
+        map.insert_constant(b"a:1".to_vec(), 1);
+        map.insert_constant(b"b:1".to_vec(), 2);
+        map.insert_constant(b"a:2".to_vec(), 3);
+
+        let mut values: Vec<u32> = map.iter_prefix(b"a:".as_slice()).map(|(_, v)| *v).collect();
+        values.sort();
+        assert_eq!(values, [1, 3]);
+    }
+
+    #[test]
+    fn std_partition() {
+        let mut map: TimedMap<StdClock, u32, &str> = TimedMap::new();
+        map.insert_expirable(1, "tenant:1", Duration::from_secs(60));
+        map.insert_constant(2, "tenant:2");
+
+        let tenant = map.partition(|_, v| *v == "tenant:1");
+        assert_eq!(tenant.get(&1), Some(&"tenant:1"));
+        assert!(tenant.get_remaining_duration(&1).is_some());
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.get(&2), Some(&"tenant:2"));
+
+        let mut map: TimedMap<StdClock, u32, &str> =
+            TimedMap::new_with_map_kind(MapKind::HashMap).name("sessions");
+        map.insert_constant(1, "tenant:1");
+
+        let tenant = map.partition(|_, v| *v == "tenant:1");
+        assert!(matches!(tenant.map, GenericMap::HashMap(_)));
+        assert_eq!(tenant.get_name(), Some("sessions"));
+    }
+
+    #[test]
+    fn std_from_std_maps() {
+        let hash_map = HashMap::from([(1, "a"), (2, "b")]);
+        let map: TimedMap<StdClock, u32, &str> = hash_map.into();
+        assert!(matches!(map.map, GenericMap::HashMap(_)));
+        assert_eq!(map.sorted_keys(), [1, 2]);
+        assert_eq!(map.get_remaining_duration(&1), None);
+
+        let btree_map = BTreeMap::from([(1, "a"), (2, "b")]);
+        let mut map: TimedMap<StdClock, u32, &str> = btree_map.into();
+        assert!(matches!(map.map, GenericMap::BTreeMap(_)));
+        assert_eq!(map.get(&2), Some(&"b"));
+
+        map.extend_expirable([(3, "c"), (4, "d")], Duration::from_secs(60));
+        assert!(map.get_remaining_duration(&3).is_some());
+        assert!(map.get_remaining_duration(&4).is_some());
+
+        // Far deadlines saturate instead of wrapping into the past.
+        map.extend_expirable([(5, "e")], Duration::MAX);
+        assert_eq!(map.get(&5), Some(&"e"));
+        map.remove(&5);
+
+        let hash_map = map.into_hash_map();
+        assert_eq!(hash_map.len(), 4);
+        assert_eq!(hash_map.get(&3), Some(&"c"));
+    }
+
+    #[test]
+    fn std_sorted_keys_with_hash_map() {
+        let mut map: TimedMap<StdClock, u32, &str> = TimedMap::new_with_map_kind(MapKind::HashMap);
+
+        map.insert_constant(3, "c");
+        map.insert_expirable(1, "a", Duration::from_secs(60));
+        map.insert_constant(2, "b");
+
+        assert_eq!(map.sorted_keys(), [1, 2, 3]);
+        assert!(map
+            .iter_sorted()
+            .map(|(_, v)| *v)
+            .eq(["a", "b", "c"].into_iter()));
+    }
+
+    #[test]
+    #[cfg(feature = "ahash")]
+    fn std_ahash_map_kind() {
+        let mut map: TimedMap<StdClock, u32, &str> = TimedMap::new_with_map_kind(MapKind::AHashMap);
+
+        map.insert_constant(1, "constant value");
+        map.insert_expirable(2, "expirable value", Duration::from_secs(60));
+
+        assert_eq!(map.get(&1), Some(&"constant value"));
+        assert_eq!(map.get(&2), Some(&"expirable value"));
+
+        assert_eq!(map.remove(&1), Some("constant value"));
+        assert_eq!(map.get(&1), None);
+    }
 }
@@ -0,0 +1,350 @@
+use super::*;
+use core::marker::PhantomData;
+use map::{GenericEntry, GenericKey, GenericOccupiedEntry, MapState};
+
+/// View into a single entry of `TimedMap`, as returned by `TimedMap::entry`.
+///
+/// The entry is looked up once, and the operations on it don't repeat the lookup.
+/// Expired entries are treated as vacant.
+///
+/// # Example usage:
+/// ```rs
+/// let mut sessions: TimedMap<StdClock, u64, Session> = TimedMap::new();
+///
+/// sessions
+///     .entry(id)
+///     .and_modify(|session| session.hits += 1)
+///     .or_insert_with_expirable(Session::default, Duration::from_secs(60));
+/// ```
+pub enum Entry<'a, C, K, V> {
+    Occupied(OccupiedEntry<'a, C, K, V>),
+    Vacant(VacantEntry<'a, C, K, V>),
+}
+
+/// Non-expired entry of `Entry`.
+pub struct OccupiedEntry<'a, C, K, V> {
+    slot: GenericOccupiedEntry<'a, K, ExpirableEntry<V>>,
+    state: MapState<'a, K, V>,
+    now_seconds: u64,
+    marker: PhantomData<C>,
+}
+
+/// Missing or expired entry of `Entry`.
+pub struct VacantEntry<'a, C, K, V> {
+    // Expired entries stay in their slot until they're replaced.
+    slot: GenericEntry<'a, K, ExpirableEntry<V>>,
+    state: MapState<'a, K, V>,
+    marker: PhantomData<C>,
+}
+
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Returns the entry of `k` for in-place manipulation, and then drops the expired entries.
+    ///
+    /// Expired entries are dropped before the lookup, since the entry keeps the map borrowed
+    /// afterwards. Use `TimedMap::entry_ref` to avoid building owned keys for lookups that
+    /// mostly hit existing entries.
+    pub fn entry(&mut self, k: K) -> Entry<'_, C, K, V> {
+        self.tick_expiration();
+
+        let now = self.expiration_now();
+        let (slot, state) = self.entry_parts(k);
+
+        match slot {
+            GenericEntry::Occupied(slot) if !slot.get().is_expired(now) => {
+                Entry::Occupied(OccupiedEntry {
+                    slot,
+                    state,
+                    now_seconds: now,
+                    marker: PhantomData,
+                })
+            }
+            slot => Entry::Vacant(VacantEntry {
+                slot,
+                state,
+                marker: PhantomData,
+            }),
+        }
+    }
+}
+
+impl<'a, C, K, V> Entry<'a, C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        match self {
+            Self::Occupied(entry) => entry.key(),
+            Self::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Returns the value of the entry, inserting `v` first if the entry is vacant.
+    ///
+    /// The inserted entry expires based on the rules registered by `TimedMap::ttl_rule` or
+    /// the function configured by `TimedMap::ttl_fn`, like `TimedMap::insert`.
+    pub fn or_insert(self, v: V) -> &'a mut V {
+        self.or_insert_with(|| v)
+    }
+
+    /// Same as `Entry::or_insert`, computing the value with `f` only if the entry is vacant.
+    pub fn or_insert_with<F>(self, f: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    /// Returns the value of the entry, inserting `v` with an expiration duration first if
+    /// the entry is vacant.
+    pub fn or_insert_expirable(self, v: V, duration: Duration) -> &'a mut V {
+        self.or_insert_with_expirable(|| v, duration)
+    }
+
+    /// Returns the value of the entry, inserting `v` that doesn't expire first if the entry
+    /// is vacant.
+    pub fn or_insert_constant(self, v: V) -> &'a mut V {
+        self.or_insert_with_constant(|| v)
+    }
+
+    /// Same as `Entry::or_insert_expirable`, computing the value with `f` only if the entry
+    /// is vacant.
+    pub fn or_insert_with_expirable<F>(self, f: F, duration: Duration) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert_expirable(f(), duration),
+        }
+    }
+
+    /// Same as `Entry::or_insert_constant`, computing the value with `f` only if the entry
+    /// is vacant.
+    pub fn or_insert_with_constant<F>(self, f: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert_constant(f()),
+        }
+    }
+
+    /// Calls `f` with the value if the entry is occupied, and then notifies the registered
+    /// observers about the update.
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        if let Self::Occupied(entry) = &mut self {
+            f(entry.slot.get_mut().value_mut());
+            entry.state.notify_updated(&mut entry.slot);
+        }
+
+        self
+    }
+}
+
+impl<'a, C, K, V> OccupiedEntry<'a, C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        self.slot.key()
+    }
+
+    /// Returns the value of the entry.
+    pub fn get(&self) -> &V {
+        self.slot.get().value()
+    }
+
+    /// Returns mutable access to the value of the entry.
+    ///
+    /// The version of the entry is bumped and the entry is recorded as updated, but the
+    /// registered hooks don't see changes made through the returned reference, unlike with
+    /// `and_modify`.
+    pub fn get_mut(&mut self) -> &mut V {
+        self.state.mark_updated(&mut self.slot);
+        self.slot.get_mut().value_mut()
+    }
+
+    /// Converts the entry into mutable access to its value, bound to the map's lifetime.
+    ///
+    /// Marks the entry as updated like `get_mut`.
+    pub fn into_mut(mut self) -> &'a mut V {
+        self.state.mark_updated(&mut self.slot);
+        self.slot.into_mut().value_mut()
+    }
+
+    /// Returns the remaining `Duration` of the entry, or `None` if it is constant.
+    pub fn remaining_duration(&self) -> Option<Duration> {
+        self.slot.get().remaining_duration(self.now_seconds)
+    }
+
+    /// Removes the entry from the map and returns its value.
+    pub fn remove(mut self) -> V {
+        self.state.remove(self.slot).owned_value()
+    }
+}
+
+impl<'a, C, K, V> VacantEntry<'a, C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        self.slot.key()
+    }
+
+    /// Takes ownership of the key.
+    pub fn into_key(self) -> K {
+        match self.slot {
+            GenericEntry::Vacant(slot) => slot.into_key(),
+            // The expired entry keeps the key until it's dropped.
+            GenericEntry::Occupied(slot) => slot.key().clone(),
+        }
+    }
+
+    /// Inserts `v` like `TimedMap::insert`, and returns mutable access to it.
+    pub fn insert(self, v: V) -> &'a mut V {
+        match self.state.ttl_for(self.slot.key(), &v) {
+            Some(duration) => self.insert_expirable(v, duration),
+            None => self.insert_constant(v),
+        }
+    }
+
+    /// Inserts `v` with an expiration duration, and returns mutable access to it.
+    pub fn insert_expirable(mut self, v: V, duration: Duration) -> &'a mut V {
+        let expires_at = self.state.now_seconds() + duration.as_secs();
+        self.state
+            .store(self.slot, v, Some(expires_at))
+            .1
+            .value_mut()
+    }
+
+    /// Inserts `v` that doesn't expire, and returns mutable access to it.
+    pub fn insert_constant(mut self, v: V) -> &'a mut V {
+        self.state.store(self.slot, v, None).1.value_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_entry() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(&clock).ttl_rule(|k| *k >= 100, Duration::from_secs(30));
+
+        *map.entry(1).or_insert_expirable(0, Duration::from_secs(10)) += 1;
+        *map.entry(1).or_insert_expirable(0, Duration::from_secs(10)) += 1;
+        assert_eq!(map.get(&1), Some(&2));
+
+        // `or_insert` follows the TTL rules, and `or_insert_with` only runs for vacant entries.
+        map.entry(100).or_insert(1);
+        map.entry(100).or_insert_with(|| unreachable!());
+        map.entry(2).or_insert_with(|| 2);
+        assert_eq!(
+            map.get_remaining_duration(&100),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(map.get_remaining_duration(&2), None);
+
+        match map.entry(1) {
+            Entry::Occupied(entry) => {
+                assert_eq!(entry.remaining_duration(), Some(Duration::from_secs(10)));
+            }
+            Entry::Vacant(_) => panic!("entry must be occupied"),
+        }
+
+        // Expired entries are vacant.
+        clock.set(1011);
+        map.entry(1).and_modify(|v| *v += 10).or_insert_constant(5);
+        assert_eq!(map.get(&1), Some(&5));
+        assert_eq!(map.get_remaining_duration(&1), None);
+
+        match map.entry(1) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), 5),
+            Entry::Vacant(_) => panic!("entry must be occupied"),
+        }
+        match map.entry(1) {
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), 1),
+            Entry::Occupied(_) => panic!("entry must be vacant"),
+        }
+        assert_eq!(map.get(&1), None);
+
+        // Expired entries which are not dropped yet are replaced in their slot.
+        map.insert_expirable(3, 3, Duration::from_secs(10));
+        clock.set(1022);
+        map.suspend_expiration();
+        match map.entry(3) {
+            Entry::Vacant(entry) => assert_eq!(entry.insert_constant(30), &30),
+            Entry::Occupied(_) => panic!("entry must be vacant"),
+        }
+        map.resume_expiration();
+        assert_eq!(map.get(&3), Some(&30));
+        assert_eq!(map.get_remaining_duration(&3), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_entry_with_map_kinds() {
+        for map_kind in [MapKind::BTreeMap, MapKind::HashMap, MapKind::VecMap] {
+            let mut map: TimedMap<StdClock, u32, u32> = TimedMap::new_with_map_kind(map_kind);
+
+            // Vector maps get promoted along the way.
+            for k in 0..20 {
+                *map.entry(k).or_insert_expirable(k, Duration::from_secs(60)) += 1;
+                map.entry(k).and_modify(|v| *v += 1).or_insert(0);
+            }
+
+            match map.entry(5) {
+                Entry::Occupied(entry) => assert_eq!(entry.remove(), 7),
+                Entry::Vacant(_) => panic!("entry must be occupied"),
+            }
+
+            assert_eq!(map.len(), 19);
+            assert_eq!(map.get(&19), Some(&21));
+            assert_eq!(map.get(&5), None);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_entry_with_ttl_fn() {
+        use std::string::String;
+
+        let mut map: TimedMap<StdClock, String, u32> =
+            TimedMap::new().ttl_fn(|_, v| (*v > 0).then(|| Duration::from_secs(60)));
+
+        map.entry("counter".into()).or_insert(0);
+        map.entry("counter".into())
+            .and_modify(|v| *v += 1)
+            .or_insert(0);
+        assert_eq!(map.get("counter"), Some(&1));
+        assert_eq!(map.get_remaining_duration("counter"), None);
+
+        map.entry("hits".into()).or_insert_with(|| 1);
+        assert_eq!(
+            map.get_remaining_duration("hits"),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(map.entry("hits".into()).key(), "hits");
+    }
+}
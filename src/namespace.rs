@@ -0,0 +1,264 @@
+use super::*;
+use map::GenericKey;
+
+/// Keys and counters of a single namespace of `NamespacedTimedMap`.
+struct Namespace<K> {
+    keys: BTreeSet<K>,
+    stats: StatsCounters,
+}
+
+impl<K> Default for Namespace<K> {
+    fn default() -> Self {
+        Self {
+            keys: BTreeSet::new(),
+            stats: StatsCounters::new(),
+        }
+    }
+}
+
+/// Hosts many logically separate maps, identified by namespaces of type `N`, on top of a
+/// single clock and expiration index, so apps needing dozens of small maps don't pay for a
+/// cleanup pass per map.
+///
+/// Keys only need to be unique within their namespace. Each namespace can be cleared on its
+/// own, and with the `stats` feature, keeps its own counters.
+///
+/// # Example usage:
+/// ```rs
+/// let mut maps: NamespacedTimedMap<StdClock, &str, u64, String> = NamespacedTimedMap::new();
+///
+/// maps.insert_expirable("tokens", user_id, token, Duration::from_secs(60));
+/// maps.insert_expirable("profiles", user_id, profile, Duration::from_secs(3600));
+///
+/// maps.clear(&"tokens");
+/// ```
+pub struct NamespacedTimedMap<C, N, K, V> {
+    inner: TimedMap<C, (N, K), V>,
+    namespaces: BTreeMap<N, Namespace<K>>,
+}
+
+#[cfg(feature = "std")]
+impl<C, N, K, V> Default for NamespacedTimedMap<C, N, K, V> {
+    fn default() -> Self {
+        Self {
+            inner: TimedMap::default(),
+            namespaces: BTreeMap::default(),
+        }
+    }
+}
+
+impl<C, N, K, V> NamespacedTimedMap<C, N, K, V>
+where
+    C: Clock,
+    N: GenericKey,
+    K: GenericKey,
+{
+    /// Creates an empty map.
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty map.
+    ///
+    /// Uses the provided `clock` to handle expiration times.
+    #[cfg(not(feature = "std"))]
+    pub const fn new(clock: C) -> Self {
+        Self {
+            inner: TimedMap::new(clock),
+            namespaces: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the value associated with `k` in `ns` if present and not expired.
+    pub fn get(&self, ns: &N, k: &K) -> Option<&V> {
+        let key = (ns.clone(), k.clone());
+        let v = self.inner.get(&key);
+
+        if let Some(namespace) = self.namespaces.get(ns) {
+            match v {
+                Some(_) => namespace.stats.record(MetricEvent::Hit),
+                None => {
+                    if self.inner.get_unchecked(&key).is_some() {
+                        namespace.stats.record(MetricEvent::ExpiredOnRead);
+                    }
+                    namespace.stats.record(MetricEvent::Miss);
+                }
+            }
+        }
+
+        v
+    }
+
+    /// Returns the remaining `Duration` of the entry of `k` in `ns` if present and not expired.
+    ///
+    /// Returns `None` if the entry does not exist or is constant.
+    pub fn get_remaining_duration(&self, ns: &N, k: &K) -> Option<Duration> {
+        self.inner.get_remaining_duration(&(ns.clone(), k.clone()))
+    }
+
+    /// Returns the number of non-expired entries in `ns`.
+    pub fn len(&self, ns: &N) -> usize {
+        self.namespaces.get(ns).map_or(0, |namespace| {
+            namespace
+                .keys
+                .iter()
+                .filter(|k| self.inner.get(&(ns.clone(), (*k).clone())).is_some())
+                .count()
+        })
+    }
+
+    /// Returns the namespaces that were inserted into, in order.
+    pub fn namespaces(&self) -> impl Iterator<Item = &N> {
+        self.namespaces.keys()
+    }
+
+    /// Inserts a key-value pair into `ns` with an expiration duration, and then drops the
+    /// expired entries of all namespaces.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned.
+    pub fn insert_expirable(&mut self, ns: N, k: K, v: V, duration: Duration) -> Option<V> {
+        self.track_insert(&ns, &k);
+        let old = self.inner.insert_expirable_unchecked((ns, k), v, duration);
+        self.drop_expired_entries();
+
+        old
+    }
+
+    /// Inserts a key-value pair into `ns` that doesn't expire, and then drops the expired
+    /// entries of all namespaces.
+    ///
+    /// If a value already exists for the given key, it will be updated and then
+    /// the old one will be returned.
+    pub fn insert_constant(&mut self, ns: N, k: K, v: V) -> Option<V> {
+        self.track_insert(&ns, &k);
+        let old = self.inner.insert_constant_unchecked((ns, k), v);
+        self.drop_expired_entries();
+
+        old
+    }
+
+    /// Removes the entry of `k` from `ns` and returns its value if present and not expired.
+    pub fn remove(&mut self, ns: &N, k: &K) -> Option<V> {
+        let namespace = self.namespaces.get_mut(ns)?;
+        if !namespace.keys.remove(k) {
+            return None;
+        }
+
+        namespace.stats.record(MetricEvent::Removal);
+        self.inner.remove(&(ns.clone(), k.clone()))
+    }
+
+    /// Removes all entries of `ns`, leaving the other namespaces untouched.
+    pub fn clear(&mut self, ns: &N) {
+        let Some(namespace) = self.namespaces.get_mut(ns) else {
+            return;
+        };
+
+        for k in core::mem::take(&mut namespace.keys) {
+            namespace.stats.record(MetricEvent::Removal);
+            self.inner.remove_unchecked(&(ns.clone(), k));
+        }
+    }
+
+    /// Returns a snapshot of the counters of `ns`, or `None` if nothing was inserted into it.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self, ns: &N) -> Option<Stats> {
+        self.namespaces
+            .get(ns)
+            .map(|namespace| namespace.stats.snapshot())
+    }
+
+    /// Clears expired entries of all namespaces.
+    pub fn drop_expired_entries(&mut self) {
+        let namespaces = &mut self.namespaces;
+        self.inner.drop_expired_entries_with(|(ns, k), _| {
+            if let Some(namespace) = namespaces.get_mut(&ns) {
+                namespace.keys.remove(&k);
+                namespace.stats.record(MetricEvent::Reaped);
+            }
+        });
+    }
+
+    /// Adds `k` to `ns` and counts the insert.
+    fn track_insert(&mut self, ns: &N, k: &K) {
+        let namespace = self.namespaces.entry(ns.clone()).or_default();
+
+        namespace.stats.record(MetricEvent::Insert);
+        if !namespace.keys.insert(k.clone()) {
+            namespace.stats.record(MetricEvent::Replacement);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_namespaces_share_expiration() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut maps = NamespacedTimedMap::new(&clock);
+
+        maps.insert_expirable("tokens", 1, "token", Duration::from_secs(10));
+        maps.insert_constant("tokens", 2, "token");
+        maps.insert_constant("profiles", 1, "profile");
+
+        // Keys are separate per namespace.
+        assert_eq!(maps.get(&"tokens", &1), Some(&"token"));
+        assert_eq!(maps.get(&"profiles", &1), Some(&"profile"));
+        assert_eq!(maps.get(&"other", &1), None);
+        assert_eq!(
+            maps.namespaces().collect::<Vec<_>>(),
+            [&"profiles", &"tokens"]
+        );
+
+        clock.set(1011);
+        assert_eq!(maps.len(&"tokens"), 1);
+        maps.drop_expired_entries();
+        assert_eq!(maps.namespaces[&"tokens"].keys.len(), 1);
+
+        maps.clear(&"tokens");
+        assert_eq!(maps.len(&"tokens"), 0);
+        assert_eq!(maps.get(&"tokens", &2), None);
+        assert_eq!(maps.remove(&"profiles", &1), Some("profile"));
+        assert_eq!(maps.len(&"profiles"), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_clear_namespace() {
+        let mut maps: NamespacedTimedMap<StdClock, &str, u32, &str> = NamespacedTimedMap::new();
+
+        maps.insert_constant("a", 1, "one");
+        maps.insert_constant("a", 1, "uno");
+        maps.insert_constant("b", 1, "one");
+        assert_eq!(maps.get(&"a", &1), Some(&"uno"));
+        assert_eq!(maps.get(&"a", &2), None);
+
+        maps.clear(&"a");
+        assert_eq!(maps.get(&"a", &1), None);
+        assert_eq!(maps.get(&"b", &1), Some(&"one"));
+
+        #[cfg(feature = "stats")]
+        {
+            assert_eq!(
+                maps.stats(&"a"),
+                Some(Stats {
+                    hits: 1,
+                    misses: 2,
+                    inserts: 2,
+                    replacements: 1,
+                    removals: 1,
+                    ..Stats::default()
+                })
+            );
+            assert_eq!(maps.stats(&"b").map(|stats| stats.hits), Some(1));
+            assert_eq!(maps.stats(&"c"), None);
+        }
+    }
+}
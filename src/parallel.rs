@@ -0,0 +1,61 @@
+use super::*;
+use map::GenericKey;
+
+use rayon::prelude::*;
+
+/// Parallel iteration with `rayon`, for CPU-bound processing of every entry of large maps.
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey + Sync,
+{
+    /// Returns a parallel iterator over all non-expired entries, in no particular order.
+    ///
+    /// The clock is read once, and references to the entries are collected up front (like
+    /// `rayon` does for the standard maps) so the work can be split evenly.
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = (&K, &V)>
+    where
+        V: Sync,
+    {
+        let entries: Vec<(&K, &V)> = self
+            .iter_with_remaining_durations()
+            .map(|(k, v, _)| (k, v))
+            .collect();
+
+        entries.into_par_iter()
+    }
+
+    /// Returns a parallel iterator over all non-expired entries with mutable access to their
+    /// values, in no particular order.
+    ///
+    /// The clock is read once, and expirations are kept as they are.
+    pub fn par_iter_mut(&mut self) -> impl IndexedParallelIterator<Item = (&K, &mut V)>
+    where
+        V: Send,
+    {
+        let entries: Vec<(&K, &mut V)> = self.iter_live_mut().collect();
+
+        entries.into_par_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn std_par_iter_skips_expired_entries() {
+        let mut map: TimedMap<StdClock, u32, u32> = TimedMap::new_with_map_kind(MapKind::HashMap);
+        for i in 0..1000 {
+            map.insert_constant(i, i);
+        }
+        map.insert_expirable(1000, 1000, Duration::ZERO);
+
+        std::thread::sleep(Duration::from_secs(1));
+
+        map.par_iter_mut().for_each(|(_, v)| *v *= 2);
+        assert_eq!(map.par_iter().map(|(_, v)| *v as u64).sum::<u64>(), 999_000);
+        assert_eq!(map.par_iter().count(), 1000);
+        assert_eq!(map.get_unchecked(&1000), Some(&1000));
+    }
+}
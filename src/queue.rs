@@ -0,0 +1,182 @@
+use super::*;
+
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::VecDeque;
+
+/// Delay queue whose items become available once their delay has passed.
+///
+/// Items due at the same second are popped in the order they were pushed.
+///
+/// # Example usage:
+/// ```rs
+/// let mut queue: ExpiringQueue<StdClock, &str> = ExpiringQueue::new();
+///
+/// queue.push("retry", Duration::from_secs(5));
+/// assert_eq!(queue.pop_due(), None);
+/// ```
+pub struct ExpiringQueue<C, T> {
+    #[cfg(feature = "std")]
+    clock: StdClock,
+    #[cfg(feature = "std")]
+    marker: PhantomData<C>,
+
+    #[cfg(not(feature = "std"))]
+    clock: C,
+
+    due: BTreeMap<u64, VecDeque<T>>,
+    len: usize,
+}
+
+#[cfg(feature = "std")]
+impl<C, T> Default for ExpiringQueue<C, T> {
+    fn default() -> Self {
+        Self {
+            clock: StdClock::new(),
+            marker: PhantomData,
+            due: BTreeMap::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<C, T> ExpiringQueue<C, T>
+where
+    C: Clock,
+{
+    /// Creates an empty queue.
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty queue.
+    ///
+    /// Uses the provided `clock` to handle delays.
+    #[cfg(not(feature = "std"))]
+    pub const fn new(clock: C) -> Self {
+        Self {
+            clock,
+            due: BTreeMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of items in the queue, including the ones that aren't due yet.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the queue holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Pushes `item` which becomes due after `delay`.
+    pub fn push(&mut self, item: T, delay: Duration) {
+        let due_at = self
+            .clock
+            .elapsed_seconds_since_creation()
+            .saturating_add(delay.as_secs());
+        self.due.entry(due_at).or_default().push_back(item);
+        self.len += 1;
+    }
+
+    /// Pops the earliest item whose delay has passed, or returns `None` if no item is due.
+    pub fn pop_due(&mut self) -> Option<T> {
+        let now = self.clock.elapsed_seconds_since_creation();
+        let mut bucket = self.due.first_entry().filter(|e| *e.key() <= now)?;
+
+        let item = bucket.get_mut().pop_front();
+        if bucket.get().is_empty() {
+            bucket.remove();
+        }
+
+        self.len -= 1;
+        item
+    }
+
+    /// Returns how long until the earliest item becomes due, which is zero if it's already
+    /// due, or `None` if the queue is empty.
+    pub fn next_due_in(&self) -> Option<Duration> {
+        let now = self.clock.elapsed_seconds_since_creation();
+        self.due
+            .first_key_value()
+            .map(|(due_at, _)| Duration::from_secs(due_at.saturating_sub(now)))
+    }
+
+    /// Waits until the earliest item becomes due and pops it, or returns `None` if the queue
+    /// is empty.
+    #[cfg(feature = "tokio")]
+    pub async fn next(&mut self) -> Option<T> {
+        loop {
+            if let Some(item) = self.pop_due() {
+                return Some(item);
+            }
+
+            tokio::time::sleep(self.next_due_in()?).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_pop_due_items_in_order() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut queue = ExpiringQueue::new(&clock);
+
+        queue.push("later", Duration::from_secs(10));
+        queue.push("first", Duration::from_secs(5));
+        queue.push("second", Duration::from_secs(5));
+        queue.push("now", Duration::ZERO);
+
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.pop_due(), Some("now"));
+        assert_eq!(queue.pop_due(), None);
+        assert_eq!(queue.next_due_in(), Some(Duration::from_secs(5)));
+
+        clock.set(1005);
+        assert_eq!(queue.pop_due(), Some("first"));
+        assert_eq!(queue.pop_due(), Some("second"));
+        assert_eq!(queue.pop_due(), None);
+
+        clock.set(1020);
+        assert_eq!(queue.next_due_in(), Some(Duration::ZERO));
+        assert_eq!(queue.pop_due(), Some("later"));
+        assert!(queue.is_empty());
+        assert_eq!(queue.next_due_in(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_pop_due_skips_delayed_items() {
+        let mut queue: ExpiringQueue<StdClock, &str> = ExpiringQueue::new();
+
+        queue.push("delayed", Duration::from_secs(60));
+        queue.push("never", Duration::MAX);
+        queue.push("now", Duration::ZERO);
+
+        assert_eq!(queue.pop_due(), Some("now"));
+        assert_eq!(queue.pop_due(), None);
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "tokio")]
+    async fn std_next_waits_for_due_items() {
+        let mut queue: ExpiringQueue<StdClock, &str> = ExpiringQueue::new();
+
+        queue.push("delayed", Duration::from_secs(1));
+        assert_eq!(queue.pop_due(), None);
+        assert_eq!(queue.next().await, Some("delayed"));
+        assert_eq!(queue.next().await, None);
+    }
+}
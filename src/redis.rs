@@ -0,0 +1,134 @@
+//! Redis-style commands over `TimedMap`, for call-site parity while migrating an in-process
+//! cache off Redis.
+//!
+//! Bring the `Commands` trait into scope to use them:
+//!
+//! ```rs
+//! use timed_map::redis::Commands;
+//!
+//! map.set_ex(key, value, 60);
+//! assert_eq!(map.ttl(&key), 60);
+//!
+//! map.persist(&key);
+//! assert_eq!(map.ttl(&key), -1);
+//! ```
+
+use super::*;
+use map::GenericKey;
+
+/// Familiar Redis commands mapped onto `TimedMap`. Durations are in seconds, like in Redis.
+pub trait Commands<K, V> {
+    /// Sets `k` to `v` which expires after `seconds` (`SETEX`).
+    fn set_ex(&mut self, k: K, v: V, seconds: u64);
+
+    /// Returns the remaining time to live of `k` in seconds (`TTL`).
+    ///
+    /// Returns -2 if the key does not exist and -1 if it has no expiration, like Redis.
+    fn ttl(&self, k: &K) -> i64;
+
+    /// Sets the time to live of `k` to `seconds` (`EXPIRE`). A timeout of 0 deletes the key.
+    ///
+    /// Returns `false` if the key does not exist.
+    fn expire(&mut self, k: &K, seconds: u64) -> bool;
+
+    /// Removes the time to live of `k`, so it never expires (`PERSIST`).
+    ///
+    /// Returns `false` if the key does not exist or has no expiration.
+    fn persist(&mut self, k: &K) -> bool;
+
+    /// Removes `k` and returns its value (`GETDEL`).
+    fn get_del(&mut self, k: &K) -> Option<V>;
+}
+
+impl<C, K, V> Commands<K, V> for TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    fn set_ex(&mut self, k: K, v: V, seconds: u64) {
+        self.insert_expirable(k, v, Duration::from_secs(seconds));
+    }
+
+    fn ttl(&self, k: &K) -> i64 {
+        if self.get(k).is_none() {
+            return -2;
+        }
+
+        self.get_remaining_duration(k)
+            .map_or(-1, |duration| duration.as_secs() as i64)
+    }
+
+    fn expire(&mut self, k: &K, seconds: u64) -> bool {
+        if self.get(k).is_none() {
+            return false;
+        }
+
+        if seconds == 0 {
+            self.remove(k);
+            return true;
+        }
+
+        let expires_at = self.now_seconds() + seconds;
+        self.update_expires_at(k, Some(expires_at))
+    }
+
+    fn persist(&mut self, k: &K) -> bool {
+        if self.get_remaining_duration(k).is_none() {
+            return false;
+        }
+
+        self.update_expires_at(k, None)
+    }
+
+    fn get_del(&mut self, k: &K) -> Option<V> {
+        self.remove(k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_redis_commands() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(&clock);
+
+        map.set_ex("session", 1, 10);
+        map.insert_constant("config", 2);
+        assert_eq!(map.ttl(&"session"), 10);
+        assert_eq!(map.ttl(&"config"), -1);
+        assert_eq!(map.ttl(&"missing"), -2);
+
+        assert!(map.expire(&"config", 30));
+        assert_eq!(map.ttl(&"config"), 30);
+        assert!(!map.expire(&"missing", 30));
+
+        assert!(map.persist(&"session"));
+        assert!(!map.persist(&"session"));
+        clock.set(1011);
+        assert_eq!(map.ttl(&"session"), -1);
+
+        assert!(map.expire(&"session", 0));
+        assert_eq!(map.ttl(&"session"), -2);
+
+        assert_eq!(map.get_del(&"config"), Some(2));
+        assert_eq!(map.get_del(&"config"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_redis_commands() {
+        let mut map: TimedMap<StdClock, u32, &str> = TimedMap::new();
+
+        map.set_ex(1, "value", 60);
+        assert_eq!(map.ttl(&1), 60);
+        assert!(map.persist(&1));
+        assert_eq!(map.ttl(&1), -1);
+        assert_eq!(map.get_del(&1), Some("value"));
+        assert_eq!(map.ttl(&1), -2);
+    }
+}
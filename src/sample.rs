@@ -0,0 +1,162 @@
+use super::*;
+use map::GenericKey;
+
+/// Source of random numbers for `TimedMap::random_entry` and `TimedMap::sample`, so any RNG
+/// can be plugged in, including on `no_std` environments.
+///
+/// Implemented for closures returning random `u64`s.
+pub trait RandomSource {
+    /// Returns a uniformly distributed random number.
+    fn next_u64(&mut self) -> u64;
+}
+
+impl<F> RandomSource for F
+where
+    F: FnMut() -> u64,
+{
+    fn next_u64(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// Returns a uniformly distributed random number in `0..bound`. `bound` must not be 0.
+///
+/// Numbers below `2^64 % bound` are drawn again, so every result is equally likely instead of
+/// favoring the low ones as a plain modulo would.
+#[inline(always)]
+fn random_below<R: RandomSource>(rng: &mut R, bound: usize) -> usize {
+    let bound = bound as u64;
+    let threshold = bound.wrapping_neg() % bound;
+
+    loop {
+        let n = rng.next_u64();
+        if n >= threshold {
+            return (n % bound) as usize;
+        }
+    }
+}
+
+/// Random sampling helpers, e.g., for picking peers from an expiring peer table.
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Returns a uniformly sampled non-expired entry, or `None` if there is none.
+    ///
+    /// Walks the entries twice without allocating, reading the clock only once so both passes
+    /// see the same entries.
+    pub fn random_entry<R>(&self, rng: &mut R) -> Option<(&K, &V)>
+    where
+        R: RandomSource,
+    {
+        let now = self.expiration_now();
+        let count = self.iter_live_at(now).count();
+        if count == 0 {
+            return None;
+        }
+
+        self.iter_live_at(now)
+            .nth(random_below(rng, count))
+            .map(|(k, v, _)| (k, v))
+    }
+
+    /// Returns up to `n` uniformly sampled distinct non-expired entries, in no particular
+    /// order.
+    ///
+    /// Walks the entries once with reservoir sampling, so only the sample is allocated.
+    pub fn sample<R>(&self, n: usize, rng: &mut R) -> Vec<(&K, &V)>
+    where
+        R: RandomSource,
+    {
+        let mut sample = Vec::new();
+        if n == 0 {
+            return sample;
+        }
+
+        for (i, (k, v, _)) in self.iter_with_remaining_durations().enumerate() {
+            if i < n {
+                sample.push((k, v));
+                continue;
+            }
+
+            let j = random_below(rng, i + 1);
+            if j < n {
+                sample[j] = (k, v);
+            }
+        }
+
+        sample
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift generator for the tests.
+    fn xorshift(mut state: u64) -> impl FnMut() -> u64 {
+        move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_random_entry_skips_expired_entries() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(&clock);
+        let mut rng = xorshift(42);
+        assert_eq!(map.random_entry(&mut rng), None);
+
+        map.insert_expirable_unchecked(1, "expired", Duration::from_secs(1));
+        map.insert_expirable_unchecked(2, "expired", Duration::from_secs(1));
+        map.insert_constant_unchecked(3, "peer");
+
+        // Expired entries are never picked, even if they are not dropped yet.
+        clock.set(1002);
+        for _ in 0..10 {
+            assert_eq!(map.random_entry(&mut rng), Some((&3, &"peer")));
+        }
+        assert_eq!(map.sample(3, &mut rng), [(&3, &"peer")]);
+    }
+
+    #[test]
+    fn test_random_below_rejects_biased_numbers() {
+        // 2^64 % 3 == 1, so 0 would make 0 slightly more likely than 1 and 2.
+        let mut draws = [0, 5].into_iter();
+        let mut rng = move || draws.next().unwrap();
+        assert_eq!(random_below(&mut rng, 3), 2);
+
+        let mut rng = || u64::MAX;
+        assert_eq!(random_below(&mut rng, 1), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_sample_is_distinct_and_bounded() {
+        let mut map: TimedMap<StdClock, u32, u32> = TimedMap::new();
+        for i in 0..100 {
+            map.insert_constant(i, i);
+        }
+
+        let mut rng = xorshift(42);
+        let mut sample: Vec<u32> = map
+            .sample(10, &mut rng)
+            .into_iter()
+            .map(|(k, _)| *k)
+            .collect();
+        sample.sort();
+        sample.dedup();
+        assert_eq!(sample.len(), 10);
+
+        assert!(map.random_entry(&mut rng).is_some());
+        assert_eq!(map.sample(0, &mut rng), []);
+        assert_eq!(map.sample(1000, &mut rng).len(), 100);
+    }
+}
@@ -0,0 +1,472 @@
+use super::*;
+use map::GenericKey;
+
+use serde::ser::{Serialize, Serializer};
+
+use serde::de::{Deserialize, Deserializer, SeqAccess, Visitor};
+
+use serde::ser::SerializeSeq;
+
+#[cfg(any(feature = "bincode", feature = "wasm-storage"))]
+use serde::de::DeserializeSeed;
+
+/// Serializes non-expired entries as a sequence of `(key, value, remaining_duration)` tuples.
+///
+/// Remaining duration is `None` for constant entries.
+impl<C, K, V> Serialize for TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey + Serialize,
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.iter_with_remaining_durations())
+    }
+}
+
+/// Deserializes entries produced by the `Serialize` implementation, re-anchoring remaining
+/// durations to the clock of the newly created map.
+#[cfg(feature = "std")]
+impl<'de, C, K, V> Deserialize<'de> for TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey + Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(TimedMapVisitor(TimedMap::new()))
+    }
+}
+
+/// Deserialization for `no_std` environments, where the map can't be created without a clock.
+#[cfg(not(feature = "std"))]
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Deserializes entries produced by the `Serialize` implementation into a new map driven
+    /// by `clock`, re-anchoring remaining durations to it.
+    ///
+    /// This stands in for `Deserialize`, which is only implemented on `std` environments.
+    pub fn deserialize_with_clock<'de, D>(clock: C, deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+    {
+        deserializer.deserialize_seq(TimedMapVisitor(TimedMap::new(clock)))
+    }
+}
+
+/// Fills the wrapped empty map with the deserialized entries.
+struct TimedMapVisitor<C, K, V>(TimedMap<C, K, V>);
+
+impl<'de, C, K, V> Visitor<'de> for TimedMapVisitor<C, K, V>
+where
+    C: Clock,
+    K: GenericKey + Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    type Value = TimedMap<C, K, V>;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("a sequence of (key, value, remaining duration) entries")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut map = self.0;
+
+        while let Some((k, v, duration)) = seq.next_element::<(K, V, Option<Duration>)>()? {
+            match duration {
+                Some(duration) => map.insert_expirable_unchecked(k, v, duration),
+                None => map.insert_constant_unchecked(k, v),
+            };
+        }
+
+        Ok(map)
+    }
+}
+
+/// Format of the lifetimes written by `TimedMap::serialize_with`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TtlFormat {
+    /// Remaining durations, as written by the `Serialize` implementation.
+    #[default]
+    Remaining,
+    /// Absolute deadlines in seconds since `UNIX_EPOCH` on `std` environments, or in seconds of
+    /// the map's `Clock` on `no_std` environments.
+    Deadline,
+}
+
+/// Selects the entries written by `TimedMap::serialize_with`, configured by
+/// `SerializeOptions::filter`.
+type EntryPredicate<'a, K, V> = dyn Fn(&K, &V) -> bool + 'a;
+
+/// Selects the entries written by `TimedMap::serialize_with` and the format of their
+/// lifetimes.
+///
+/// By default, all non-expired entries are written with their remaining durations, like the
+/// `Serialize` implementation does.
+///
+/// # Example usage:
+/// ```rs
+/// let options = SerializeOptions::new()
+///     .filter(|_, v: &Session| v.is_admin)
+///     .ttl_format(TtlFormat::Deadline);
+///
+/// let json = serde_json::to_string(&map.serialize_with(options)).unwrap();
+/// ```
+pub struct SerializeOptions<'a, K, V> {
+    include_expired: bool,
+    constant_only: bool,
+    predicate: Option<Box<EntryPredicate<'a, K, V>>>,
+    ttl_format: TtlFormat,
+}
+
+impl<K, V> Default for SerializeOptions<'_, K, V> {
+    fn default() -> Self {
+        Self {
+            include_expired: false,
+            constant_only: false,
+            predicate: None,
+            ttl_format: TtlFormat::default(),
+        }
+    }
+}
+
+impl<'a, K, V> SerializeOptions<'a, K, V> {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also writes the expired entries that aren't dropped yet, with a remaining duration of
+    /// zero or their past deadline.
+    pub fn include_expired(mut self) -> Self {
+        self.include_expired = true;
+        self
+    }
+
+    /// Only writes the constant entries.
+    pub fn constant_only(mut self) -> Self {
+        self.constant_only = true;
+        self
+    }
+
+    /// Only writes the entries matching `predicate`.
+    pub fn filter<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&K, &V) -> bool + 'a,
+    {
+        self.predicate = Some(Box::new(predicate));
+        self
+    }
+
+    /// Sets the format of the lifetimes, which is `TtlFormat::Remaining` by default.
+    pub fn ttl_format(mut self, ttl_format: TtlFormat) -> Self {
+        self.ttl_format = ttl_format;
+        self
+    }
+}
+
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey + Serialize,
+    V: Serialize,
+{
+    /// Returns a `Serialize` view of the map that writes the entries selected by `options` as
+    /// a sequence of `(key, value, lifetime)` tuples.
+    ///
+    /// Lifetime is `None` for constant entries, and otherwise follows
+    /// `SerializeOptions::ttl_format`. The clock is read once.
+    pub fn serialize_with<'a>(
+        &'a self,
+        options: SerializeOptions<'a, K, V>,
+    ) -> impl Serialize + 'a {
+        FilteredEntries { map: self, options }
+    }
+}
+
+/// Serializes the entries of `map` selected by `options`.
+struct FilteredEntries<'a, C, K, V> {
+    map: &'a TimedMap<C, K, V>,
+    options: SerializeOptions<'a, K, V>,
+}
+
+impl<C, K, V> FilteredEntries<'_, C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Returns the selected entries along with their deadlines in seconds of the clock.
+    fn entries(&self, now: u64) -> impl Iterator<Item = (&K, &V, Option<u64>)> {
+        let options = &self.options;
+        self.map
+            .iter_entries()
+            .filter(move |(_, entry)| options.include_expired || !entry.is_expired(now))
+            .filter(|(_, entry)| !options.constant_only || *entry.status() == EntryStatus::Constant)
+            .filter(|(k, entry)| {
+                options
+                    .predicate
+                    .as_ref()
+                    .is_none_or(|predicate| predicate(k, entry.value()))
+            })
+            .map(|(k, entry)| match entry.status() {
+                EntryStatus::Constant => (k, entry.value(), None),
+                EntryStatus::ExpiresAtSeconds(expires_at) => (k, entry.value(), Some(*expires_at)),
+            })
+    }
+}
+
+impl<C, K, V> Serialize for FilteredEntries<'_, C, K, V>
+where
+    C: Clock,
+    K: GenericKey + Serialize,
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let now = self.map.expiration_now();
+        // Binary formats need the length upfront, count the entries before streaming them.
+        let len = self.entries(now).count();
+
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        for (k, v, expires_at) in self.entries(now) {
+            match self.options.ttl_format {
+                TtlFormat::Remaining => {
+                    let remaining = expires_at
+                        .map(|expires_at| Duration::from_secs(expires_at.saturating_sub(now)));
+                    seq.serialize_element(&(k, v, remaining))?;
+                }
+                #[cfg(feature = "std")]
+                TtlFormat::Deadline => {
+                    let deadline =
+                        expires_at.map(|expires_at| self.map.absolute_deadline(expires_at));
+                    seq.serialize_element(&(k, v, deadline))?;
+                }
+                #[cfg(not(feature = "std"))]
+                TtlFormat::Deadline => seq.serialize_element(&(k, v, expires_at))?,
+            }
+        }
+        seq.end()
+    }
+}
+
+#[cfg(any(feature = "bincode", feature = "wasm-storage"))]
+/// Returns the current time as seconds since `UNIX_EPOCH`.
+#[inline(always)]
+fn unix_now_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(any(feature = "bincode", feature = "wasm-storage"))]
+/// Serializes non-expired entries as `(key, value, deadline)` tuples, where deadline is
+/// the absolute expiration time in seconds since `UNIX_EPOCH` or `None` for constant entries.
+///
+/// Entries are streamed directly from the map without collecting them first.
+pub(crate) struct SnapshotEntries<'a, C, K, V>(pub(crate) &'a TimedMap<C, K, V>);
+
+#[cfg(any(feature = "bincode", feature = "wasm-storage"))]
+impl<C, K, V> Serialize for SnapshotEntries<'_, C, K, V>
+where
+    C: Clock,
+    K: GenericKey + Serialize,
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let now = unix_now_seconds();
+        // Both passes use the same instant, so the length always matches the entries written.
+        let expiration_now = self.0.expiration_now();
+        // Binary formats need the length upfront, count the entries before streaming them.
+        let len = self.0.iter_live_at(expiration_now).count();
+
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        for (k, v, duration) in self.0.iter_live_at(expiration_now) {
+            seq.serialize_element(&(k, v, duration.map(|d| now + d.as_secs())))?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(any(feature = "bincode", feature = "wasm-storage"))]
+/// Inserts the entries produced by `SnapshotEntries` into the wrapped map, dropping the ones
+/// whose deadline has already passed.
+pub(crate) struct SnapshotRestore<'a, C, K, V>(pub(crate) &'a mut TimedMap<C, K, V>);
+
+#[cfg(any(feature = "bincode", feature = "wasm-storage"))]
+impl<'de, C, K, V> DeserializeSeed<'de> for SnapshotRestore<'_, C, K, V>
+where
+    C: Clock,
+    K: GenericKey + Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+#[cfg(any(feature = "bincode", feature = "wasm-storage"))]
+impl<'de, C, K, V> Visitor<'de> for SnapshotRestore<'_, C, K, V>
+where
+    C: Clock,
+    K: GenericKey + Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("a sequence of (key, value, deadline) entries")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let now = unix_now_seconds();
+
+        while let Some((k, v, deadline)) = seq.next_element::<(K, V, Option<u64>)>()? {
+            match deadline {
+                Some(deadline) if deadline < now => {}
+                Some(deadline) => {
+                    self.0
+                        .insert_expirable_unchecked(k, v, Duration::from_secs(deadline - now));
+                }
+                None => {
+                    self.0.insert_constant_unchecked(k, v);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_serde_roundtrip_preserves_ttls() {
+        use std::string::String;
+
+        let mut map: TimedMap<StdClock, u32, &str> = TimedMap::new();
+
+        map.insert_constant(1, "constant value");
+        map.insert_expirable(2, "expirable value", Duration::from_secs(60));
+
+        let json = serde_json::to_string(&map).unwrap();
+        let restored: TimedMap<StdClock, u32, String> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.get(&1).map(|v| v.as_str()), Some("constant value"));
+        assert_eq!(
+            restored.get(&2).map(|v| v.as_str()),
+            Some("expirable value")
+        );
+        assert_eq!(restored.get_remaining_duration(&1), None);
+        assert_eq!(
+            restored.get_remaining_duration(&2),
+            Some(Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_serialize_skips_expired_entries() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(&clock);
+
+        map.insert_constant(1, "constant value");
+        map.insert_expirable(2, "expirable value1", Duration::from_secs(60));
+        map.insert_expirable(3, "expirable value2", Duration::from_secs(10));
+
+        clock.set(1020);
+
+        assert_eq!(
+            serde_json::to_string(&map).unwrap(),
+            r#"[[1,"constant value",null],[2,"expirable value1",{"secs":40,"nanos":0}]]"#
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_deserialize_with_clock() {
+        use crate::clock::ManualClock;
+
+        let json = r#"[[1,"constant value",null],[2,"expirable value",{"secs":40,"nanos":0}]]"#;
+        let clock = ManualClock::new(5000);
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let map: TimedMap<_, u32, &str> =
+            TimedMap::deserialize_with_clock(&clock, &mut deserializer).unwrap();
+
+        assert_eq!(map.get(&1), Some(&"constant value"));
+        assert_eq!(map.get_remaining_duration(&1), None);
+        assert_eq!(
+            map.get_remaining_duration(&2),
+            Some(Duration::from_secs(40))
+        );
+
+        clock.set(5041);
+        assert_eq!(map.get(&2), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_serialize_with_options() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(&clock);
+
+        map.insert_constant(1, "constant");
+        map.insert_expirable(2, "live", Duration::from_secs(60));
+        map.insert_expirable(3, "expired", Duration::from_secs(10));
+
+        clock.set(1020);
+
+        let to_json = |options| serde_json::to_string(&map.serialize_with(options)).unwrap();
+
+        assert_eq!(
+            to_json(SerializeOptions::new().constant_only()),
+            r#"[[1,"constant",null]]"#
+        );
+        assert_eq!(
+            to_json(SerializeOptions::new().filter(|k, _| *k == 2)),
+            r#"[[2,"live",{"secs":40,"nanos":0}]]"#
+        );
+        assert_eq!(
+            to_json(
+                SerializeOptions::new()
+                    .include_expired()
+                    .ttl_format(TtlFormat::Deadline)
+            ),
+            r#"[[1,"constant",null],[2,"live",1060],[3,"expired",1010]]"#
+        );
+    }
+}
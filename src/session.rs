@@ -0,0 +1,185 @@
+use super::*;
+use map::GenericKey;
+
+/// Value of `SessionMap` along with its absolute deadline.
+struct Session<V> {
+    value: V,
+    max_expires_at: u64,
+}
+
+/// Map of sessions that expire after an idle timeout, which is reset on every access, and
+/// after a maximum absolute lifetime at the latest, whichever comes first.
+///
+/// # Example usage:
+/// ```rs
+/// let mut sessions: SessionMap<StdClock, u64, User> =
+///     SessionMap::new(Duration::from_secs(15 * 60), Duration::from_secs(12 * 60 * 60));
+///
+/// sessions.insert(session_id, user);
+/// let user = sessions.get(&session_id);
+/// ```
+pub struct SessionMap<C, K, V> {
+    inner: TimedMap<C, K, Session<V>>,
+    idle_timeout: Duration,
+    max_lifetime: Duration,
+}
+
+impl<C, K, V> SessionMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Creates an empty map with the given idle timeout and maximum lifetime.
+    #[cfg(feature = "std")]
+    pub fn new(idle_timeout: Duration, max_lifetime: Duration) -> Self {
+        Self {
+            inner: TimedMap::new(),
+            idle_timeout,
+            max_lifetime,
+        }
+    }
+
+    /// Creates an empty map with the given idle timeout and maximum lifetime.
+    ///
+    /// Uses the provided `clock` to handle expiration times.
+    #[cfg(not(feature = "std"))]
+    pub const fn new(clock: C, idle_timeout: Duration, max_lifetime: Duration) -> Self {
+        Self {
+            inner: TimedMap::new(clock),
+            idle_timeout,
+            max_lifetime,
+        }
+    }
+
+    /// Returns the deadline of a session accessed at `now`.
+    #[inline(always)]
+    fn next_expires_at(&self, now: u64, max_expires_at: u64) -> u64 {
+        now.saturating_add(self.idle_timeout.as_secs())
+            .min(max_expires_at)
+    }
+
+    /// Starts a new session for `k`, replacing the existing one, and then drops the expired
+    /// sessions.
+    ///
+    /// Returns the value of the replaced session if it was not expired.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let now = self.inner.now_seconds();
+        let max_expires_at = now.saturating_add(self.max_lifetime.as_secs());
+        let duration = Duration::from_secs(self.next_expires_at(now, max_expires_at) - now);
+
+        let live = self.inner.get(&k).is_some();
+        let session = Session {
+            value: v,
+            max_expires_at,
+        };
+
+        self.inner
+            .insert_expirable(k, session, duration)
+            .filter(|_| live)
+            .map(|session| session.value)
+    }
+
+    /// Returns the value of the session if it's not expired, and resets its idle timeout.
+    pub fn get(&mut self, k: &K) -> Option<&V> {
+        let now = self.inner.now_seconds();
+        let max_expires_at = self.inner.get(k)?.max_expires_at;
+
+        let expires_at = self.next_expires_at(now, max_expires_at);
+        self.inner.set_expires_at(k, Some(expires_at));
+
+        self.peek(k)
+    }
+
+    /// Returns the value of the session if it's not expired, without resetting its idle
+    /// timeout.
+    pub fn peek(&self, k: &K) -> Option<&V> {
+        self.inner.get(k).map(|session| &session.value)
+    }
+
+    /// Returns how long the session stays alive without being accessed, if it's not expired.
+    pub fn get_remaining_duration(&self, k: &K) -> Option<Duration> {
+        self.inner.get_remaining_duration(k)
+    }
+
+    /// Ends the session of `k` and returns its value if it was not expired.
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        self.inner.remove(k).map(|session| session.value)
+    }
+
+    /// Clears expired sessions from the map.
+    pub fn drop_expired_entries(&mut self) {
+        self.inner.drop_expired_entries();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_idle_and_absolute_timeouts() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut sessions =
+            SessionMap::new(&clock, Duration::from_secs(10), Duration::from_secs(25));
+
+        sessions.insert(1, "active");
+        sessions.insert(2, "idle");
+
+        // Accessing a session resets its idle timeout.
+        clock.set(1008);
+        assert_eq!(sessions.get(&1), Some(&"active"));
+        assert_eq!(sessions.peek(&2), Some(&"idle"));
+
+        clock.set(1016);
+        assert_eq!(sessions.get(&1), Some(&"active"));
+        assert_eq!(sessions.get(&2), None);
+
+        // Idle timeouts never extend the absolute lifetime.
+        clock.set(1024);
+        assert_eq!(sessions.get(&1), Some(&"active"));
+        assert_eq!(
+            sessions.get_remaining_duration(&1),
+            Some(Duration::from_secs(1))
+        );
+
+        clock.set(1026);
+        assert_eq!(sessions.get(&1), None);
+        assert_eq!(sessions.insert(1, "renewed"), None);
+        assert_eq!(sessions.remove(&1), Some("renewed"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_sessions_without_absolute_limit() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut sessions = SessionMap::new(&clock, Duration::from_secs(10), Duration::MAX);
+
+        sessions.insert(1, "active");
+        clock.set(1_000_000);
+        sessions.insert(2, "idle");
+        clock.set(1_000_008);
+        assert_eq!(sessions.get(&2), Some(&"idle"));
+        assert_eq!(
+            sessions.get_remaining_duration(&2),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(sessions.get(&1), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_session_map() {
+        let mut sessions: SessionMap<StdClock, u32, &str> =
+            SessionMap::new(Duration::from_secs(60), Duration::from_secs(3600));
+
+        assert_eq!(sessions.insert(1, "first"), None);
+        assert_eq!(sessions.insert(1, "second"), Some("first"));
+        assert_eq!(sessions.get(&1), Some(&"second"));
+        assert_eq!(sessions.get(&2), None);
+    }
+}
@@ -0,0 +1,156 @@
+use super::*;
+
+use async_trait::async_trait;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use time::OffsetDateTime;
+use tower_sessions_core::session::{Id, Record};
+use tower_sessions_core::session_store::Result;
+use tower_sessions_core::SessionStore;
+
+/// In-memory `tower-sessions` backend built on top of `TimedMap`.
+///
+/// Session expiry dates are mapped to entry durations, so expired sessions are
+/// dropped by the map itself without a separate cleanup task.
+///
+/// # Example usage:
+/// ```rs
+/// let session_layer = SessionManagerLayer::new(TimedMapSessionStore::new());
+/// ```
+pub struct TimedMapSessionStore {
+    inner: Mutex<TimedMap<StdClock, i128, Record>>,
+}
+
+impl TimedMapSessionStore {
+    /// Creates an empty store which uses `BTreeMap` internally.
+    pub fn new() -> Self {
+        Self::new_with_map_kind(MapKind::BTreeMap)
+    }
+
+    /// Creates an empty store based on the chosen map implementation specified by `MapKind`.
+    pub fn new_with_map_kind(map_kind: MapKind) -> Self {
+        Self {
+            inner: Mutex::new(TimedMap::new_with_map_kind(map_kind)),
+        }
+    }
+
+    /// Locks the inner map, recovering it if the lock was poisoned.
+    fn lock(&self) -> MutexGuard<'_, TimedMap<StdClock, i128, Record>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Stores the record until its expiry date, or removes it if that date has already passed.
+    fn put(map: &mut TimedMap<StdClock, i128, Record>, record: &Record) -> Result<()> {
+        match Duration::try_from(record.expiry_date - OffsetDateTime::now_utc()) {
+            Ok(duration) if !duration.is_zero() => {
+                map.insert_expirable(record.id.0, record.clone(), duration);
+            }
+            Ok(_) | Err(_) => {
+                map.remove(&record.id.0);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for TimedMapSessionStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for TimedMapSessionStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimedMapSessionStore")
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl SessionStore for TimedMapSessionStore {
+    async fn create(&self, record: &mut Record) -> Result<()> {
+        let mut map = self.lock();
+
+        // Regenerate the ID until it doesn't collide with a live session.
+        while map.get(&record.id.0).is_some() {
+            record.id = Id::default();
+        }
+
+        Self::put(&mut map, record)
+    }
+
+    async fn save(&self, record: &Record) -> Result<()> {
+        Self::put(&mut self.lock(), record)
+    }
+
+    async fn load(&self, session_id: &Id) -> Result<Option<Record>> {
+        Ok(self.lock().get(&session_id.0).cloned())
+    }
+
+    async fn delete(&self, session_id: &Id) -> Result<()> {
+        self.lock().remove(&session_id.0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+    use std::collections::HashMap;
+
+    /// Drives a future that never waits, which holds for every operation of this store.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        match future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(output) => output,
+            Poll::Pending => unreachable!("store operations never wait"),
+        }
+    }
+
+    fn record(id: i128, expires_in: time::Duration) -> Record {
+        Record {
+            id: Id(id),
+            data: HashMap::new(),
+            expiry_date: OffsetDateTime::now_utc() + expires_in,
+        }
+    }
+
+    #[test]
+    fn session_lifecycle() {
+        let store = TimedMapSessionStore::new();
+
+        let mut active = record(1, time::Duration::minutes(5));
+        block_on(store.create(&mut active)).unwrap();
+        assert_eq!(
+            block_on(store.load(&active.id)).unwrap(),
+            Some(active.clone())
+        );
+
+        let mut colliding = record(1, time::Duration::minutes(5));
+        block_on(store.create(&mut colliding)).unwrap();
+        assert_ne!(colliding.id, active.id);
+
+        block_on(store.delete(&active.id)).unwrap();
+        assert_eq!(block_on(store.load(&active.id)).unwrap(), None);
+    }
+
+    #[test]
+    fn expired_session_is_dropped_on_save() {
+        let store = TimedMapSessionStore::new();
+
+        let mut session = record(1, time::Duration::minutes(5));
+        block_on(store.create(&mut session)).unwrap();
+
+        session.expiry_date = OffsetDateTime::now_utc() - time::Duration::minutes(1);
+        block_on(store.save(&session)).unwrap();
+        assert_eq!(block_on(store.load(&session.id)).unwrap(), None);
+    }
+}
@@ -0,0 +1,156 @@
+use super::*;
+use map::GenericKey;
+
+/// Set of keys that may optionally expire after a specified duration.
+///
+/// Thin wrapper over `TimedMap<C, K, ()>` with a set-like API, useful for things like
+/// recently seen message IDs.
+///
+/// # Example usage:
+/// ```rs
+/// let mut seen: TimedSet<StdClock, u64> = TimedSet::new();
+///
+/// assert!(seen.insert_expirable(42, Duration::from_secs(60)));
+/// assert!(!seen.insert_expirable(42, Duration::from_secs(60)));
+/// assert!(seen.contains(&42));
+/// ```
+pub struct TimedSet<C, K> {
+    inner: TimedMap<C, K, ()>,
+}
+
+#[cfg(feature = "std")]
+impl<C, K> Default for TimedSet<C, K> {
+    fn default() -> Self {
+        Self {
+            inner: TimedMap::default(),
+        }
+    }
+}
+
+impl<C, K> TimedSet<C, K>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Creates an empty set.
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty set based on the chosen map implementation specified by `MapKind`.
+    #[cfg(feature = "std")]
+    pub fn new_with_map_kind(map_kind: MapKind) -> Self {
+        Self {
+            inner: TimedMap::new_with_map_kind(map_kind),
+        }
+    }
+
+    /// Creates an empty set.
+    ///
+    /// Uses the provided `clock` to handle expiration times.
+    #[cfg(not(feature = "std"))]
+    pub const fn new(clock: C) -> Self {
+        Self {
+            inner: TimedMap::new(clock),
+        }
+    }
+
+    /// Configures `expiration_tick_cap` of the inner map.
+    ///
+    /// See `TimedMap::expiration_tick_cap` for more details.
+    pub fn expiration_tick_cap(mut self, expiration_tick_cap: u16) -> Self {
+        self.inner = self.inner.expiration_tick_cap(expiration_tick_cap);
+        self
+    }
+
+    /// Returns `true` if the set contains `k` and it's not expired.
+    pub fn contains(&self, k: &K) -> bool {
+        self.inner.get(k).is_some()
+    }
+
+    /// Returns the remaining `Duration` of `k` if present and not expired.
+    ///
+    /// Returns `None` if the key does not exist or is constant.
+    pub fn get_remaining_duration(&self, k: &K) -> Option<Duration> {
+        self.inner.get_remaining_duration(k)
+    }
+
+    /// Inserts a key with an expiration duration, and then drops the expired keys.
+    ///
+    /// Returns `true` if the key wasn't present or was expired. Otherwise, its duration is
+    /// updated and `false` is returned.
+    pub fn insert_expirable(&mut self, k: K, duration: Duration) -> bool {
+        let inserted = !self.contains(&k);
+        self.inner.insert_expirable(k, (), duration);
+        inserted
+    }
+
+    /// Inserts a key that doesn't expire, and then drops the expired keys.
+    ///
+    /// Returns `true` if the key wasn't present or was expired.
+    pub fn insert_constant(&mut self, k: K) -> bool {
+        let inserted = !self.contains(&k);
+        self.inner.insert_constant(k, ());
+        inserted
+    }
+
+    /// Removes a key from the set and returns `true` if it was present and not expired.
+    pub fn remove(&mut self, k: &K) -> bool {
+        self.inner.remove(k).is_some()
+    }
+
+    /// Returns an iterator over all non-expired keys in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = &K> {
+        self.inner.iter_sorted().map(|(k, _)| k)
+    }
+
+    /// Clears expired keys from the set.
+    pub fn drop_expired_entries(&mut self) {
+        self.inner.drop_expired_entries();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_timed_set() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut set = TimedSet::new(&clock);
+
+        assert!(set.insert_expirable(1, Duration::from_secs(10)));
+        assert!(!set.insert_expirable(1, Duration::from_secs(10)));
+        assert!(set.insert_constant(2));
+        assert!(set.insert_expirable(3, Duration::from_secs(60)));
+        assert!(set.contains(&1));
+        assert_eq!(
+            set.get_remaining_duration(&3),
+            Some(Duration::from_secs(60))
+        );
+
+        clock.set(1011);
+        assert!(!set.contains(&1));
+        assert!(set.insert_expirable(1, Duration::from_secs(10)));
+
+        assert!(set.remove(&2));
+        assert!(!set.remove(&2));
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), [1, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_timed_set() {
+        let mut set: TimedSet<StdClock, &str> = TimedSet::new_with_map_kind(MapKind::HashMap);
+
+        assert!(set.insert_expirable("b", Duration::from_secs(60)));
+        assert!(set.insert_constant("a"));
+        assert!(set.contains(&"a"));
+        assert!(!set.contains(&"c"));
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), ["a", "b"]);
+    }
+}
@@ -0,0 +1,228 @@
+use super::*;
+use map::GenericKey;
+
+use std::io;
+
+use bincode::de::read::Reader;
+use bincode::error::{DecodeError, EncodeError};
+use bincode::serde::OwnedSerdeDecoder;
+use serde::de::{DeserializeOwned, DeserializeSeed};
+use serde::ser::Serialize;
+use serialization::{SnapshotEntries, SnapshotRestore};
+
+/// Leading bytes of every snapshot, used to reject arbitrary input early.
+const SNAPSHOT_MAGIC: [u8; 4] = *b"TMAP";
+
+/// Current snapshot format version.
+///
+/// Bump this whenever the payload layout changes and keep decoding the older
+/// versions in `TimedMap::from_snapshot_bytes`.
+const SNAPSHOT_VERSION: u8 = 1;
+
+const SNAPSHOT_HEADER_LEN: usize = SNAPSHOT_MAGIC.len() + 1;
+
+/// Errors that can occur while writing or reading binary snapshots.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Input doesn't start with a snapshot header.
+    InvalidHeader,
+    /// Snapshot was written with a format version this crate doesn't know.
+    UnsupportedVersion(u8),
+    /// Encoding the entries failed.
+    Encode(EncodeError),
+    /// Decoding the entries failed.
+    Decode(DecodeError),
+    /// Reading or writing the snapshot header failed.
+    Io(io::Error),
+}
+
+impl core::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidHeader => write!(f, "invalid snapshot header"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version: {version}")
+            }
+            Self::Encode(e) => write!(f, "failed to encode snapshot: {e}"),
+            Self::Decode(e) => write!(f, "failed to decode snapshot: {e}"),
+            Self::Io(e) => write!(f, "snapshot I/O failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<EncodeError> for SnapshotError {
+    fn from(e: EncodeError) -> Self {
+        Self::Encode(e)
+    }
+}
+
+impl From<DecodeError> for SnapshotError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Adapts `io::Read` implementations to bincode's `Reader`.
+struct IoReader<R>(R);
+
+impl<R: io::Read> Reader for IoReader<R> {
+    #[inline(always)]
+    fn read(&mut self, bytes: &mut [u8]) -> Result<(), DecodeError> {
+        self.0.read_exact(bytes).map_err(|inner| DecodeError::Io {
+            inner,
+            additional: bytes.len(),
+        })
+    }
+}
+
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Encodes all non-expired entries into a versioned binary snapshot.
+    ///
+    /// Deadlines are stored as absolute timestamps, so entries keep expiring at their original
+    /// deadlines once restored with `TimedMap::from_snapshot_bytes`.
+    pub fn to_snapshot_bytes(&self) -> Result<Vec<u8>, SnapshotError>
+    where
+        K: Serialize,
+        V: Serialize,
+    {
+        let mut bytes = Vec::new();
+        self.save_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Creates a map from a snapshot produced by `TimedMap::to_snapshot_bytes`.
+    ///
+    /// Entries whose deadline passed since the snapshot was taken are dropped.
+    pub fn from_snapshot_bytes(bytes: &[u8]) -> Result<Self, SnapshotError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        Self::load_from(bytes)
+    }
+
+    /// Streams the binary snapshot of all non-expired entries into `writer`.
+    ///
+    /// Entries are encoded one by one, so checkpointing a large map doesn't require
+    /// materializing them in memory first. Consider wrapping `writer` with `io::BufWriter`.
+    pub fn save_to<W>(&self, mut writer: W) -> Result<(), SnapshotError>
+    where
+        W: io::Write,
+        K: Serialize,
+        V: Serialize,
+    {
+        writer.write_all(&SNAPSHOT_MAGIC)?;
+        writer.write_all(&[SNAPSHOT_VERSION])?;
+
+        bincode::serde::encode_into_std_write(
+            SnapshotEntries(self),
+            &mut writer,
+            bincode::config::standard(),
+        )?;
+
+        Ok(())
+    }
+
+    /// Creates a map by streaming a binary snapshot written by `TimedMap::save_to`
+    /// (or `TimedMap::to_snapshot_bytes`) from `reader`.
+    ///
+    /// Entries whose deadline passed since the snapshot was taken are dropped.
+    /// Consider wrapping `reader` with `io::BufReader`.
+    pub fn load_from<R>(mut reader: R) -> Result<Self, SnapshotError>
+    where
+        R: io::Read,
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        let mut header = [0; SNAPSHOT_HEADER_LEN];
+        reader.read_exact(&mut header).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => SnapshotError::InvalidHeader,
+            _ => SnapshotError::Io(e),
+        })?;
+
+        if header[..SNAPSHOT_MAGIC.len()] != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::InvalidHeader);
+        }
+
+        let mut map = Self::new();
+        match header[SNAPSHOT_MAGIC.len()] {
+            1 => {
+                let mut decoder =
+                    OwnedSerdeDecoder::from_reader(IoReader(reader), bincode::config::standard());
+                SnapshotRestore(&mut map).deserialize(decoder.as_deserializer())?;
+            }
+            version => return Err(SnapshotError::UnsupportedVersion(version)),
+        }
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::{String, ToString};
+
+    #[test]
+    fn snapshot_roundtrip() {
+        let mut map: TimedMap<StdClock, u32, String> = TimedMap::new();
+
+        map.insert_constant(1, "constant value".to_string());
+        map.insert_expirable(2, "expirable value".to_string(), Duration::from_secs(60));
+
+        let bytes = map.to_snapshot_bytes().unwrap();
+        let restored: TimedMap<StdClock, u32, String> =
+            TimedMap::from_snapshot_bytes(&bytes).unwrap();
+
+        assert_eq!(restored.get(&1), Some(&"constant value".to_string()));
+        assert_eq!(restored.get(&2), Some(&"expirable value".to_string()));
+        assert_eq!(restored.get_remaining_duration(&1), None);
+        assert!(restored.get_remaining_duration(&2).is_some());
+    }
+
+    #[test]
+    fn snapshot_save_to_and_load_from_streams() {
+        let mut map: TimedMap<StdClock, u32, u64> = TimedMap::new();
+        for i in 0..1000 {
+            map.insert_expirable_unchecked(i, u64::from(i) * 2, Duration::from_secs(60));
+        }
+
+        let mut buf = Vec::new();
+        map.save_to(io::BufWriter::new(&mut buf)).unwrap();
+        assert_eq!(buf, map.to_snapshot_bytes().unwrap());
+
+        let restored: TimedMap<StdClock, u32, u64> =
+            TimedMap::load_from(io::BufReader::new(buf.as_slice())).unwrap();
+        assert_eq!(restored.sorted_keys(), map.sorted_keys());
+        assert_eq!(restored.get(&999), Some(&1998));
+    }
+
+    #[test]
+    fn snapshot_rejects_invalid_input() {
+        let map: TimedMap<StdClock, u32, u32> = TimedMap::new();
+        let mut bytes = map.to_snapshot_bytes().unwrap();
+
+        assert!(matches!(
+            TimedMap::<StdClock, u32, u32>::from_snapshot_bytes(b"TMA"),
+            Err(SnapshotError::InvalidHeader)
+        ));
+
+        bytes[SNAPSHOT_MAGIC.len()] = SNAPSHOT_VERSION + 1;
+        assert!(matches!(
+            TimedMap::<StdClock, u32, u32>::from_snapshot_bytes(&bytes),
+            Err(SnapshotError::UnsupportedVersion(v)) if v == SNAPSHOT_VERSION + 1
+        ));
+    }
+}
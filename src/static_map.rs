@@ -0,0 +1,95 @@
+use super::*;
+use map::GenericKey;
+
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+
+/// A `TimedMap` wrapper that can be placed in a `static`.
+///
+/// The inner map is created lazily on the first access and guarded by a `Mutex`,
+/// so it can be shared across threads as a global singleton.
+///
+/// # Example usage:
+/// ```rs
+/// static CACHE: StaticTimedMap<u64, &str> = StaticTimedMap::new();
+///
+/// CACHE.lock().insert_expirable(1, "value", Duration::from_secs(60));
+/// assert_eq!(CACHE.lock().get(&1), Some(&"value"));
+/// ```
+pub struct StaticTimedMap<K, V> {
+    inner: OnceLock<Mutex<TimedMap<StdClock, K, V>>>,
+    map_kind: MapKind,
+    expiration_tick_cap: u16,
+}
+
+impl<K, V> StaticTimedMap<K, V>
+where
+    K: GenericKey,
+{
+    /// Creates an uninitialized global map which uses `BTreeMap` internally.
+    pub const fn new() -> Self {
+        Self::new_with_map_kind(MapKind::BTreeMap)
+    }
+
+    /// Creates an uninitialized global map based on the chosen map implementation
+    /// specified by `MapKind`.
+    pub const fn new_with_map_kind(map_kind: MapKind) -> Self {
+        Self {
+            inner: OnceLock::new(),
+            map_kind,
+            expiration_tick_cap: 1,
+        }
+    }
+
+    /// Configures `expiration_tick_cap` of the inner map.
+    ///
+    /// See `TimedMap::expiration_tick_cap` for more details.
+    pub const fn expiration_tick_cap(mut self, expiration_tick_cap: u16) -> Self {
+        self.expiration_tick_cap = expiration_tick_cap;
+        self
+    }
+
+    /// Locks the inner map, initializing it on the first call.
+    ///
+    /// A poisoned lock is recovered since the map itself can't be left in an
+    /// inconsistent state by a panicking caller.
+    pub fn lock(&self) -> MutexGuard<'_, TimedMap<StdClock, K, V>> {
+        self.inner
+            .get_or_init(|| {
+                Mutex::new(
+                    TimedMap::new_with_map_kind(self.map_kind)
+                        .expiration_tick_cap(self.expiration_tick_cap),
+                )
+            })
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K, V> Default for StaticTimedMap<K, V>
+where
+    K: GenericKey,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static MAP: StaticTimedMap<u32, &str> =
+        StaticTimedMap::new_with_map_kind(MapKind::HashMap).expiration_tick_cap(10);
+
+    #[test]
+    fn static_map_lazy_initialization() {
+        MAP.lock().insert_constant(1, "constant value");
+        MAP.lock()
+            .insert_expirable(2, "expirable value", Duration::from_secs(60));
+
+        let handle = std::thread::spawn(|| MAP.lock().get(&1).copied());
+        assert_eq!(handle.join().unwrap(), Some("constant value"));
+
+        assert_eq!(MAP.lock().get(&2), Some(&"expirable value"));
+    }
+}
@@ -0,0 +1,201 @@
+use super::{Duration, MetricEvent};
+
+#[cfg(feature = "stats")]
+use super::{AtomicU64, Ordering};
+
+/// Snapshot of the counters returned by `TimedMap::stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg(feature = "stats")]
+pub struct Stats {
+    /// Lookups that found a non-expired value.
+    pub hits: u64,
+    /// Lookups that found nothing, including the ones that found an expired value.
+    pub misses: u64,
+    /// Lookups that found an expired value which wasn't reaped yet.
+    pub expired_on_read: u64,
+    /// Inserted entries, including replacements.
+    pub inserts: u64,
+    /// Inserts that overwrote an existing entry.
+    pub replacements: u64,
+    /// Entries removed explicitly.
+    pub removals: u64,
+    /// Expired entries removed by the cleanup passes.
+    pub reaped: u64,
+}
+
+#[cfg(feature = "stats")]
+impl Stats {
+    /// Returns the ratio of hits to all lookups, or `None` if there weren't any lookups.
+    pub fn hit_rate(&self) -> Option<f64> {
+        let lookups = self.hits + self.misses;
+        (lookups != 0).then(|| self.hits as f64 / lookups as f64)
+    }
+}
+
+/// Shape of the internal expiration index, as returned by `TimedMap::expiry_index_stats`.
+///
+/// Entries sharing the same deadline (in seconds) are grouped in one bucket, so a few huge
+/// buckets indicate deadline clustering.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ExpiryIndexStats {
+    /// Number of distinct deadlines.
+    pub buckets: usize,
+    /// Number of expirable entries, including expired ones that aren't dropped yet.
+    pub entries: usize,
+    /// Number of entries in the largest bucket.
+    pub largest_bucket: usize,
+    /// Time between the earliest and the latest deadline.
+    pub span: Duration,
+}
+
+/// Number of entries per status, as returned by `TimedMap::count_by_status`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EntryCounts {
+    /// Number of constant entries.
+    pub constant: usize,
+    /// Number of expirable entries that haven't expired yet.
+    pub expirable_live: usize,
+    /// Number of expired entries that aren't dropped yet.
+    pub expired_pending: usize,
+}
+
+/// Compact summary of a map, as returned by `TimedMap::summary`, e.g. for logging the state of
+/// a map over `defmt` on embedded targets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MapSummary {
+    /// Number of entries per status.
+    pub counts: EntryCounts,
+    /// Time left until the next live expirable entry expires, or `None` if there isn't any.
+    pub next_expiration: Option<Duration>,
+}
+
+/// Inconsistency between the map and its expiration index, as reported by
+/// `TimedMap::check_invariants`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InvariantViolation {
+    /// An expirable entry is not indexed under its deadline.
+    MissingFromIndex,
+    /// The index holds a key that is absent, constant or has another deadline in the map.
+    DanglingIndexKey,
+    /// The index holds a deadline without any keys.
+    EmptyBucket,
+    /// The tracked number of expirable entries differs from the number of keys in the index.
+    ExpirableCountMismatch,
+}
+
+impl core::fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::MissingFromIndex => write!(f, "expirable entry is missing from the index"),
+            Self::DanglingIndexKey => write!(f, "index holds a dangling key"),
+            Self::EmptyBucket => write!(f, "index holds an empty deadline bucket"),
+            Self::ExpirableCountMismatch => {
+                write!(f, "expirable entry count doesn't match the index")
+            }
+        }
+    }
+}
+
+impl core::error::Error for InvariantViolation {}
+
+/// Counters backing `TimedMap::stats`, which compile to nothing without the `stats` feature.
+#[derive(Default)]
+pub(crate) struct StatsCounters {
+    #[cfg(feature = "stats")]
+    counters: [AtomicU64; 7],
+}
+
+impl StatsCounters {
+    pub(crate) const fn new() -> Self {
+        Self {
+            #[cfg(feature = "stats")]
+            counters: [const { AtomicU64::new(0) }; 7],
+        }
+    }
+
+    /// Counts `event` once.
+    #[inline(always)]
+    pub(crate) fn record(&self, event: MetricEvent) {
+        #[cfg(feature = "stats")]
+        self.counters[event as usize].fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(not(feature = "stats"))]
+        let _ = event;
+    }
+}
+
+#[cfg(feature = "stats")]
+impl StatsCounters {
+    /// Loads all counters into a `Stats` snapshot.
+    pub(crate) fn snapshot(&self) -> Stats {
+        let [hits, misses, expired_on_read, inserts, replacements, removals, reaped] =
+            self.counters.each_ref().map(|c| c.load(Ordering::Relaxed));
+
+        Stats {
+            hits,
+            misses,
+            expired_on_read,
+            inserts,
+            replacements,
+            removals,
+            reaped,
+        }
+    }
+
+    /// Resets all counters to zero.
+    pub(crate) fn reset(&self) {
+        for counter in &self.counters {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "stats", not(feature = "std")))]
+mod tests {
+    use super::*;
+    use crate::{Duration, TimedMap};
+
+    #[test]
+    fn nostd_stats() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(&clock);
+
+        map.insert_expirable_unchecked(1, "expirable", Duration::from_secs(10));
+        map.insert_expirable_unchecked(2, "expirable", Duration::from_secs(10));
+        map.insert_constant_unchecked(3, "constant");
+        map.insert_constant_unchecked(3, "replaced");
+
+        assert_eq!(map.get(&3), Some(&"replaced"));
+        assert_eq!(map.get(&4), None);
+
+        clock.set(1011);
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.remove(&3), Some("replaced"));
+        map.drop_expired_entries();
+
+        assert_eq!(
+            map.stats(),
+            Stats {
+                hits: 1,
+                misses: 2,
+                expired_on_read: 1,
+                inserts: 4,
+                replacements: 1,
+                removals: 1,
+                reaped: 2,
+            }
+        );
+        assert_eq!(map.stats().hit_rate(), Some(1.0 / 3.0));
+
+        map.reset_stats();
+        assert_eq!(map.stats(), Stats::default());
+        assert_eq!(map.stats().hit_rate(), None);
+    }
+}
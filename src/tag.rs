@@ -0,0 +1,261 @@
+use super::*;
+use map::GenericKey;
+
+/// Value of `TaggedTimedMap` along with its tags.
+struct Tagged<V, T> {
+    value: V,
+    tags: Vec<T>,
+}
+
+/// Map whose entries carry tags of type `T`, so groups of entries can be removed or expired at
+/// once, e.g., everything cached for a tenant. Each entry may optionally expire after a
+/// specified duration.
+///
+/// The tag index always stays consistent with the map: entries leave it as soon as they are
+/// removed, replaced or dropped for being expired.
+///
+/// # Example usage:
+/// ```rs
+/// let mut cache: TaggedTimedMap<StdClock, String, Page, TenantId> = TaggedTimedMap::new();
+///
+/// cache.insert_expirable(url, page, Duration::from_secs(300), [tenant_id]);
+/// cache.remove_by_tag(&tenant_id);
+/// ```
+pub struct TaggedTimedMap<C, K, V, T> {
+    inner: TimedMap<C, K, Tagged<V, T>>,
+    by_tag: BTreeMap<T, BTreeSet<K>>,
+}
+
+#[cfg(feature = "std")]
+impl<C, K, V, T> Default for TaggedTimedMap<C, K, V, T> {
+    fn default() -> Self {
+        Self {
+            inner: TimedMap::default(),
+            by_tag: BTreeMap::default(),
+        }
+    }
+}
+
+impl<C, K, V, T> TaggedTimedMap<C, K, V, T>
+where
+    C: Clock,
+    K: GenericKey,
+    T: Clone + Ord,
+{
+    /// Creates an empty map.
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty map.
+    ///
+    /// Uses the provided `clock` to handle expiration times.
+    #[cfg(not(feature = "std"))]
+    pub const fn new(clock: C) -> Self {
+        Self {
+            inner: TimedMap::new(clock),
+            by_tag: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the associated value if present and not expired.
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.inner.get(k).map(|tagged| &tagged.value)
+    }
+
+    /// Returns the tags of the entry of `k` if present and not expired.
+    pub fn get_tags(&self, k: &K) -> Option<&[T]> {
+        self.inner.get(k).map(|tagged| tagged.tags.as_slice())
+    }
+
+    /// Returns the remaining `Duration` of the entry of `k` if present and not expired.
+    ///
+    /// Returns `None` if the entry does not exist or is constant.
+    pub fn get_remaining_duration(&self, k: &K) -> Option<Duration> {
+        self.inner.get_remaining_duration(k)
+    }
+
+    /// Inserts a key-value pair tagged with `tags` with an expiration duration, and then drops
+    /// the expired entries.
+    ///
+    /// If a value already exists for the given key, it will be updated along with its tags
+    /// and then the old one will be returned if it was not expired.
+    pub fn insert_expirable<I>(&mut self, k: K, v: V, duration: Duration, tags: I) -> Option<V>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let live = self.inner.get(&k).is_some();
+        let tagged = Tagged {
+            value: v,
+            tags: tags.into_iter().collect(),
+        };
+
+        let old = self
+            .inner
+            .insert_expirable_unchecked(k.clone(), tagged, duration);
+        self.retag(k, old, live)
+    }
+
+    /// Inserts a key-value pair tagged with `tags` that doesn't expire, and then drops the
+    /// expired entries.
+    ///
+    /// If a value already exists for the given key, it will be updated along with its tags
+    /// and then the old one will be returned if it was not expired.
+    pub fn insert_constant<I>(&mut self, k: K, v: V, tags: I) -> Option<V>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let live = self.inner.get(&k).is_some();
+        let tagged = Tagged {
+            value: v,
+            tags: tags.into_iter().collect(),
+        };
+
+        let old = self.inner.insert_constant_unchecked(k.clone(), tagged);
+        self.retag(k, old, live)
+    }
+
+    /// Removes a key-value pair from the map and returns the associated value if present
+    /// and not expired.
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        let live = self.inner.get(k).is_some();
+        let tagged = self.inner.remove_unchecked(k)?;
+        untag(&mut self.by_tag, k, &tagged.tags);
+
+        live.then_some(tagged.value)
+    }
+
+    /// Removes all entries tagged with `tag` and returns how many of them were not expired.
+    pub fn remove_by_tag(&mut self, tag: &T) -> usize {
+        let Some(keys) = self.by_tag.get(tag).cloned() else {
+            return 0;
+        };
+
+        keys.iter().filter(|k| self.remove(k).is_some()).count()
+    }
+
+    /// Makes all entries tagged with `tag` expire after `duration`, and returns how many of
+    /// them were not expired.
+    ///
+    /// Expired entries are left as they are.
+    pub fn expire_by_tag(&mut self, tag: &T, duration: Duration) -> usize {
+        let Some(keys) = self.by_tag.get(tag) else {
+            return 0;
+        };
+
+        let expires_at = self.inner.now_seconds().saturating_add(duration.as_secs());
+        let mut expired = 0;
+        for k in keys {
+            if self.inner.get(k).is_some() && self.inner.update_expires_at(k, Some(expires_at)) {
+                expired += 1;
+            }
+        }
+
+        expired
+    }
+
+    /// Clears expired entries from both the map and the tag index.
+    pub fn drop_expired_entries(&mut self) {
+        let by_tag = &mut self.by_tag;
+        self.inner.drop_expired_entries_with(|k, tagged| {
+            untag(by_tag, &k, &tagged.tags);
+        });
+    }
+
+    /// Moves `k` from the tags of its replaced entry to its current ones, and then drops the
+    /// expired entries.
+    fn retag(&mut self, k: K, old: Option<Tagged<V, T>>, live: bool) -> Option<V> {
+        if let Some(old) = old.as_ref() {
+            untag(&mut self.by_tag, &k, &old.tags);
+        }
+
+        if let Some(tagged) = self.inner.get_unchecked(&k) {
+            for tag in &tagged.tags {
+                self.by_tag
+                    .entry(tag.clone())
+                    .or_default()
+                    .insert(k.clone());
+            }
+        }
+
+        self.drop_expired_entries();
+        old.filter(|_| live).map(|old| old.value)
+    }
+}
+
+/// Removes `k` from `tags`, dropping the tags which have no keys left.
+fn untag<K, T>(by_tag: &mut BTreeMap<T, BTreeSet<K>>, k: &K, tags: &[T])
+where
+    K: Ord,
+    T: Ord,
+{
+    for tag in tags {
+        if let Some(keys) = by_tag.get_mut(tag) {
+            keys.remove(k);
+
+            if keys.is_empty() {
+                by_tag.remove(tag);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_remove_and_expire_by_tag() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut cache = TaggedTimedMap::new(&clock);
+
+        cache.insert_constant(1, "a", ["tenant:1", "pages"]);
+        cache.insert_constant(2, "b", ["tenant:1"]);
+        cache.insert_expirable(3, "c", Duration::from_secs(60), ["tenant:2", "pages"]);
+        assert_eq!(cache.get_tags(&1), Some(["tenant:1", "pages"].as_slice()));
+
+        assert_eq!(cache.expire_by_tag(&"pages", Duration::from_secs(10)), 2);
+        assert_eq!(
+            cache.get_remaining_duration(&1),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            cache.get_remaining_duration(&3),
+            Some(Duration::from_secs(10))
+        );
+
+        // Expired entries leave the tag index once dropped.
+        clock.set(1011);
+        cache.drop_expired_entries();
+        assert!(!cache.by_tag.contains_key(&"pages"));
+        assert!(!cache.by_tag.contains_key(&"tenant:2"));
+
+        // Replacing an entry replaces its tags.
+        cache.insert_constant(2, "b", ["tenant:2"]);
+        assert_eq!(cache.expire_by_tag(&"tenant:2", Duration::MAX), 1);
+        assert_eq!(cache.get(&2), Some(&"b"));
+        assert_eq!(cache.remove_by_tag(&"tenant:1"), 0);
+        assert_eq!(cache.remove_by_tag(&"tenant:2"), 1);
+        assert_eq!(cache.get(&2), None);
+        assert!(cache.by_tag.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_remove_by_tag() {
+        let mut cache: TaggedTimedMap<StdClock, u32, &str, &str> = TaggedTimedMap::new();
+
+        cache.insert_expirable(1, "a", Duration::from_secs(60), ["tenant:1"]);
+        cache.insert_constant(2, "b", ["tenant:1"]);
+        cache.insert_constant(3, "c", ["tenant:2"]);
+
+        assert_eq!(cache.remove_by_tag(&"tenant:1"), 2);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&3), Some(&"c"));
+    }
+}
@@ -0,0 +1,280 @@
+//! Proptest strategies for property-testing code built on `TimedMap`.
+//!
+//! `ops` generates random operation sequences, and `check_ops` replays them against a map
+//! driven by a manual clock and a `ReferenceModel`, failing on the first disagreement or
+//! index inconsistency:
+//!
+//! ```rs
+//! use timed_map::testing::{check_ops, ops};
+//!
+//! proptest! {
+//!     #[test]
+//!     fn map_matches_model(ops in ops(0u8..8, any::<u32>(), 0..64)) {
+//!         check_ops(&ops)?;
+//!     }
+//! }
+//! ```
+//!
+//! On `std` builds, where `TimedMap` always uses `StdClock`, `check_ops` drives the map's own
+//! clock the same way instead of a `ManualClock`.
+
+use super::*;
+use core::fmt::Debug;
+use map::GenericKey;
+
+pub use clock::ManualClock;
+
+use proptest::collection::{vec, SizeRange};
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+
+/// Longest duration generated by `op`, in seconds.
+const MAX_OP_SECONDS: u64 = 120;
+
+/// Bucket bounds of the TTL histogram checked by `check_ops`, in seconds.
+const HISTOGRAM_BOUNDS: [u64; 3] = [0, 10, 60];
+
+/// Operation applied by `check_ops` to both the map and the `ReferenceModel`.
+#[derive(Clone, Debug)]
+pub enum Op<K, V> {
+    /// `TimedMap::insert_expirable` with a duration in seconds.
+    InsertExpirable(K, V, u64),
+    /// `TimedMap::insert_constant`.
+    InsertConstant(K, V),
+    /// `TimedMap::remove`.
+    Remove(K),
+    /// `TimedMap::get`.
+    Get(K),
+    /// Moves the clock forward by the given seconds.
+    Advance(u64),
+    /// `TimedMap::drop_expired_entries`.
+    DropExpiredEntries,
+}
+
+/// Returns a strategy generating single operations over keys and values drawn from `key` and
+/// `value`.
+///
+/// Durations are at most two minutes, so entries regularly expire within a sequence.
+pub fn op<K, V>(
+    key: impl Strategy<Value = K> + Clone + 'static,
+    value: impl Strategy<Value = V> + Clone + 'static,
+) -> BoxedStrategy<Op<K, V>>
+where
+    K: Clone + Debug + 'static,
+    V: Clone + Debug + 'static,
+{
+    prop_oneof![
+        4 => (key.clone(), value.clone(), 0..=MAX_OP_SECONDS)
+            .prop_map(|(k, v, seconds)| Op::InsertExpirable(k, v, seconds)),
+        2 => (key.clone(), value).prop_map(|(k, v)| Op::InsertConstant(k, v)),
+        2 => key.clone().prop_map(Op::Remove),
+        2 => key.prop_map(Op::Get),
+        2 => (0..=MAX_OP_SECONDS).prop_map(Op::Advance),
+        1 => Just(Op::DropExpiredEntries),
+    ]
+    .boxed()
+}
+
+/// Returns a strategy generating sequences of `op`s with a length within `len`.
+pub fn ops<K, V>(
+    key: impl Strategy<Value = K> + Clone + 'static,
+    value: impl Strategy<Value = V> + Clone + 'static,
+    len: impl Into<SizeRange>,
+) -> impl Strategy<Value = Vec<Op<K, V>>>
+where
+    K: Clone + Debug + 'static,
+    V: Clone + Debug + 'static,
+{
+    vec(op(key, value), len)
+}
+
+/// Straightforward model of `TimedMap` semantics, keeping every entry with its deadline in a
+/// `BTreeMap` and dropping expired entries at the same points as a map with the default
+/// expiration tick cap.
+#[derive(Clone, Debug)]
+pub struct ReferenceModel<K, V> {
+    now: u64,
+    entries: BTreeMap<K, (V, Option<u64>)>,
+}
+
+impl<K, V> ReferenceModel<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    /// Creates an empty model whose clock reads `seconds`.
+    pub const fn new(seconds: u64) -> Self {
+        Self {
+            now: seconds,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Applies `op` and returns the value the map is expected to return for it.
+    pub fn apply(&mut self, op: &Op<K, V>) -> Option<V> {
+        match op {
+            Op::InsertExpirable(k, v, seconds) => {
+                let old = self.insert(k, v, Some(self.now + seconds));
+                self.drop_expired_entries();
+                old
+            }
+            Op::InsertConstant(k, v) => {
+                let old = self.insert(k, v, None);
+                self.drop_expired_entries();
+                old
+            }
+            Op::Remove(k) => self
+                .entries
+                .remove(k)
+                .filter(|(_, expires_at)| !self.is_expired(*expires_at))
+                .map(|(v, _)| v),
+            Op::Get(k) => self.get(k).cloned(),
+            Op::Advance(seconds) => {
+                self.now += seconds;
+                None
+            }
+            Op::DropExpiredEntries => {
+                self.drop_expired_entries();
+                None
+            }
+        }
+    }
+
+    /// Returns the value of `k` if present and not expired.
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.entries
+            .get(k)
+            .filter(|(_, expires_at)| !self.is_expired(*expires_at))
+            .map(|(v, _)| v)
+    }
+
+    /// Returns the non-expired entries sorted by key, along with their remaining seconds.
+    pub fn live_entries(&self) -> Vec<(K, V, Option<u64>)> {
+        self.entries
+            .iter()
+            .filter(|(_, (_, expires_at))| !self.is_expired(*expires_at))
+            .map(|(k, (v, expires_at))| {
+                let remaining = expires_at.map(|expires_at| expires_at - self.now);
+                (k.clone(), v.clone(), remaining)
+            })
+            .collect()
+    }
+
+    fn insert(&mut self, k: &K, v: &V, expires_at: Option<u64>) -> Option<V> {
+        self.entries
+            .insert(k.clone(), (v.clone(), expires_at))
+            .map(|(old, _)| old)
+    }
+
+    fn drop_expired_entries(&mut self) {
+        let now = self.now;
+        self.entries
+            .retain(|_, (_, expires_at)| expires_at.is_none_or(|expires_at| now <= expires_at));
+    }
+
+    #[inline(always)]
+    fn is_expired(&self, expires_at: Option<u64>) -> bool {
+        expires_at.is_some_and(|expires_at| self.now > expires_at)
+    }
+}
+
+/// Replays `ops` against a fresh `TimedMap` driven by a manual clock and against a
+/// `ReferenceModel`, checking after every operation that both return the same values, hold
+/// the same live entries and remaining durations, that `TimedMap::count_by_status` and
+/// `TimedMap::ttl_histogram` agree with them, and that `TimedMap::check_invariants` passes.
+pub fn check_ops<K, V>(ops: &[Op<K, V>]) -> Result<(), TestCaseError>
+where
+    K: GenericKey + Debug,
+    V: Clone + PartialEq + Debug,
+{
+    #[cfg(not(feature = "std"))]
+    let clock = ManualClock::new(1000);
+    #[cfg(not(feature = "std"))]
+    let map = TimedMap::new(&clock);
+    #[cfg(feature = "std")]
+    let map = TimedMap::<StdClock, K, V>::with_manual_clock(1000);
+    let bounds = HISTOGRAM_BOUNDS.map(Duration::from_secs);
+    let mut map = map.ttl_histogram_buckets(&bounds);
+    let mut model = ReferenceModel::new(1000);
+
+    for (i, op) in ops.iter().enumerate() {
+        let actual = match op.clone() {
+            Op::InsertExpirable(k, v, seconds) => {
+                map.insert_expirable(k, v, Duration::from_secs(seconds))
+            }
+            Op::InsertConstant(k, v) => map.insert_constant(k, v),
+            Op::Remove(k) => map.remove(&k),
+            Op::Get(k) => map.get(&k).cloned(),
+            Op::Advance(seconds) => {
+                #[cfg(not(feature = "std"))]
+                clock.advance(seconds);
+                #[cfg(feature = "std")]
+                map.advance_clock(seconds);
+                None
+            }
+            Op::DropExpiredEntries => {
+                map.drop_expired_entries();
+                None
+            }
+        };
+        let expected = model.apply(op);
+        prop_assert_eq!(actual, expected, "result of op #{} ({:?})", i, op);
+
+        prop_assert_eq!(
+            map.check_invariants(),
+            Ok(()),
+            "invariants after op #{} ({:?})",
+            i,
+            op
+        );
+
+        let mut live: Vec<(K, V, Option<u64>)> = map
+            .iter_with_remaining_durations()
+            .map(|(k, v, remaining)| (k.clone(), v.clone(), remaining.map(|d| d.as_secs())))
+            .collect();
+        live.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let counts = map.count_by_status();
+        prop_assert_eq!(
+            counts.constant + counts.expirable_live,
+            live.len(),
+            "counts after op #{} ({:?})",
+            i,
+            op
+        );
+
+        let mut histogram = [0; HISTOGRAM_BOUNDS.len() + 1];
+        for remaining in live.iter().filter_map(|(_, _, remaining)| *remaining) {
+            histogram[HISTOGRAM_BOUNDS.partition_point(|bound| *bound < remaining)] += 1;
+        }
+        prop_assert_eq!(
+            map.ttl_histogram(),
+            Some(histogram.as_slice()),
+            "histogram after op #{} ({:?})",
+            i,
+            op
+        );
+
+        prop_assert_eq!(
+            live,
+            model.live_entries(),
+            "entries after op #{} ({:?})",
+            i,
+            op
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_map_matches_model(ops in ops(0u8..8, any::<u16>(), 0..128)) {
+            check_ops(&ops)?;
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use super::*;
+use map::GenericKey;
+
+/// Per-key token bucket state used by `TimedMap::try_acquire`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenBucket {
+    tokens: u64,
+    last_refill: u64,
+}
+
+impl TokenBucket {
+    /// Returns the tokens left as of the last acquisition.
+    #[inline(always)]
+    pub fn tokens(&self) -> u64 {
+        self.tokens
+    }
+}
+
+/// Rate limiting helpers where idle buckets expire away on their own.
+impl<C, K> TimedMap<C, K, TokenBucket>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Takes a token from the bucket of `k` and returns `true`, or returns `false` if the
+    /// bucket is empty.
+    ///
+    /// Buckets start full with `capacity` tokens and get `refill_per_second` tokens back every
+    /// second. Each bucket expires once it would be full again, so idle keys don't pile up in
+    /// the map. With a `refill_per_second` of 0, buckets never refill nor expire.
+    pub fn try_acquire(&mut self, k: K, capacity: u64, refill_per_second: u64) -> bool {
+        let now = self.now_seconds();
+        let tokens = self.get(&k).map_or(capacity, |bucket| {
+            let refilled = now
+                .saturating_sub(bucket.last_refill)
+                .saturating_mul(refill_per_second);
+            bucket.tokens.saturating_add(refilled).min(capacity)
+        });
+
+        if tokens == 0 {
+            return false;
+        }
+
+        let bucket = TokenBucket {
+            tokens: tokens - 1,
+            last_refill: now,
+        };
+
+        match refill_per_second {
+            0 => self.insert_constant(k, bucket),
+            refill_per_second => {
+                let seconds_to_full = (capacity - bucket.tokens).div_ceil(refill_per_second);
+                self.insert_expirable(k, bucket, Duration::from_secs(seconds_to_full))
+            }
+        };
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_try_acquire_refills_and_expires() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = TimedMap::new(&clock);
+
+        assert!(map.try_acquire(1, 3, 1));
+        assert!(map.try_acquire(1, 3, 1));
+        assert!(map.try_acquire(1, 3, 1));
+        assert!(!map.try_acquire(1, 3, 1));
+        assert!(map.try_acquire(2, 3, 1));
+
+        clock.set(1001);
+        assert!(map.try_acquire(1, 3, 1));
+        assert!(!map.try_acquire(1, 3, 1));
+        assert_eq!(map.get(&1).map(TokenBucket::tokens), Some(0));
+
+        // Idle buckets expire once they would be full again.
+        clock.set(1005);
+        assert!(map.get(&1).is_none());
+
+        // Buckets without refills never expire.
+        assert!(map.try_acquire(3, 1, 0));
+        clock.set(2000);
+        assert!(!map.try_acquire(3, 1, 0));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_try_acquire_limits_per_key() {
+        let mut map: TimedMap<StdClock, &str, TokenBucket> = TimedMap::new();
+
+        assert!(map.try_acquire("a", 2, 1));
+        assert!(map.try_acquire("a", 2, 1));
+        assert!(!map.try_acquire("a", 2, 1));
+        assert!(map.try_acquire("b", 2, 1));
+    }
+}
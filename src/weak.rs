@@ -0,0 +1,181 @@
+use super::*;
+use map::GenericKey;
+
+#[cfg(not(feature = "std"))]
+use alloc::sync::{Arc, Weak};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Weak};
+
+/// Registry of live shared objects, which holds its values as `Weak` references so it never
+/// keeps them alive on its own. Each entry may optionally expire after a specified duration
+/// as a safety net.
+///
+/// Entries whose objects were dropped everywhere else are treated as expired, and are reaped
+/// along with the expired ones by `drop_expired_entries`.
+///
+/// # Example usage:
+/// ```rs
+/// let mut connections: WeakTimedMap<StdClock, u64, Connection> = WeakTimedMap::new();
+///
+/// let connection = Arc::new(Connection::open(addr));
+/// connections.insert_expirable(id, &connection, Duration::from_secs(300));
+///
+/// drop(connection);
+/// assert!(connections.get(&id).is_none());
+/// ```
+pub struct WeakTimedMap<C, K, T> {
+    inner: TimedMap<C, K, Weak<T>>,
+}
+
+#[cfg(feature = "std")]
+impl<C, K, T> Default for WeakTimedMap<C, K, T> {
+    fn default() -> Self {
+        Self {
+            inner: TimedMap::default(),
+        }
+    }
+}
+
+impl<C, K, T> WeakTimedMap<C, K, T>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Creates an empty map.
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty map.
+    ///
+    /// Uses the provided `clock` to handle expiration times.
+    #[cfg(not(feature = "std"))]
+    pub const fn new(clock: C) -> Self {
+        Self {
+            inner: TimedMap::new(clock),
+        }
+    }
+
+    /// Returns a strong reference to the associated object if it is still alive and its
+    /// entry is not expired.
+    pub fn get(&self, k: &K) -> Option<Arc<T>> {
+        self.inner.get(k).and_then(Weak::upgrade)
+    }
+
+    /// Returns the remaining `Duration` of the entry of `k` if its object is still alive and
+    /// the entry is not expired.
+    ///
+    /// Returns `None` if the entry does not exist or is constant.
+    pub fn get_remaining_duration(&self, k: &K) -> Option<Duration> {
+        self.get(k)?;
+        self.inner.get_remaining_duration(k)
+    }
+
+    /// Inserts a weak reference to `v` with an expiration duration, and then drops the expired
+    /// entries.
+    ///
+    /// If an entry already exists for the given key, it will be updated and then the old
+    /// object will be returned if it is still alive.
+    pub fn insert_expirable(&mut self, k: K, v: &Arc<T>, duration: Duration) -> Option<Arc<T>> {
+        self.inner
+            .insert_expirable(k, Arc::downgrade(v), duration)
+            .and_then(|old| old.upgrade())
+    }
+
+    /// Inserts a weak reference to `v` that doesn't expire, and then drops the expired
+    /// entries.
+    ///
+    /// The entry is only removed once `v` is dropped everywhere else, or explicitly.
+    ///
+    /// If an entry already exists for the given key, it will be updated and then the old
+    /// object will be returned if it is still alive.
+    pub fn insert_constant(&mut self, k: K, v: &Arc<T>) -> Option<Arc<T>> {
+        self.inner
+            .insert_constant(k, Arc::downgrade(v))
+            .and_then(|old| old.upgrade())
+    }
+
+    /// Removes the entry of `k` and returns its object if it is still alive and the entry
+    /// is not expired.
+    pub fn remove(&mut self, k: &K) -> Option<Arc<T>> {
+        self.inner.remove(k).and_then(|v| v.upgrade())
+    }
+
+    /// Clears expired entries and the ones whose objects were dropped.
+    ///
+    /// Unlike expired entries, dropped objects are not tracked by the expiration index, so
+    /// this visits every entry.
+    pub fn drop_expired_entries(&mut self) {
+        self.inner.drop_expired_entries();
+
+        let dead: Vec<K> = self
+            .inner
+            .iter_with_remaining_durations()
+            .filter(|(_, v, _)| v.strong_count() == 0)
+            .map(|(k, _, _)| k.clone())
+            .collect();
+
+        for k in dead {
+            self.inner.remove_unchecked(&k);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_dropped_objects_are_reaped() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let mut map = WeakTimedMap::new(&clock);
+
+        let a = Arc::new("a");
+        let b = Arc::new("b");
+        let c = Arc::new("c");
+        map.insert_constant(1, &a);
+        map.insert_constant(2, &b);
+        map.insert_expirable(3, &c, Duration::from_secs(10));
+        assert_eq!(map.get(&1).as_deref(), Some(&"a"));
+        assert_eq!(
+            map.get_remaining_duration(&3),
+            Some(Duration::from_secs(10))
+        );
+
+        // Dropped objects are treated as expired right away.
+        drop(b);
+        assert_eq!(map.get(&2), None);
+
+        // Expired entries are gone even if their objects are alive.
+        clock.set(1011);
+        assert_eq!(map.get(&3), None);
+        assert_eq!(map.get_remaining_duration(&3), None);
+
+        map.drop_expired_entries();
+        assert!(map.inner.get_unchecked(&2).is_none());
+        assert!(map.inner.get_unchecked(&3).is_none());
+        assert_eq!(map.get(&1).as_deref(), Some(&"a"));
+        assert_eq!(Arc::strong_count(&c), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_get_upgrades_live_objects() {
+        let mut map: WeakTimedMap<StdClock, u32, &str> = WeakTimedMap::new();
+
+        let object = Arc::new("object");
+        assert_eq!(map.insert_constant(1, &object), None);
+        assert!(Arc::ptr_eq(&map.get(&1).unwrap(), &object));
+
+        let replacement = Arc::new("replacement");
+        let old = map.insert_expirable(1, &replacement, Duration::from_secs(60));
+        assert!(Arc::ptr_eq(&old.unwrap(), &object));
+
+        drop(replacement);
+        assert_eq!(map.remove(&1), None);
+    }
+}
@@ -0,0 +1,59 @@
+use super::*;
+use map::GenericKey;
+use serialization::{SnapshotEntries, SnapshotRestore};
+
+use serde::de::{DeserializeOwned, DeserializeSeed};
+use serde::ser::Serialize;
+use std::string::ToString;
+use wasm_bindgen::JsValue;
+use web_sys::Storage;
+
+/// Returns `window.localStorage` of the current browsing context.
+fn local_storage() -> Result<Storage, JsValue> {
+    web_sys::window()
+        .ok_or_else(|| JsValue::from_str("`window` is not available"))?
+        .local_storage()?
+        .ok_or_else(|| JsValue::from_str("`localStorage` is not available"))
+}
+
+impl<C, K, V> TimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+{
+    /// Persists all non-expired entries as JSON into `localStorage` under `storage_key`.
+    ///
+    /// Deadlines are stored as absolute timestamps, so entries keep expiring at their original
+    /// deadlines once restored with `TimedMap::load_from_local_storage` after a page reload.
+    pub fn save_to_local_storage(&self, storage_key: &str) -> Result<(), JsValue>
+    where
+        K: Serialize,
+        V: Serialize,
+    {
+        let json = serde_json::to_string(&SnapshotEntries(self))
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        local_storage()?.set_item(storage_key, &json)
+    }
+
+    /// Creates a map from the entries persisted by `TimedMap::save_to_local_storage` under
+    /// `storage_key`, or returns `None` if there is nothing stored.
+    ///
+    /// Entries whose deadline passed while the page was closed are dropped.
+    pub fn load_from_local_storage(storage_key: &str) -> Result<Option<Self>, JsValue>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        let Some(json) = local_storage()?.get_item(storage_key)? else {
+            return Ok(None);
+        };
+
+        let mut map = Self::new();
+        SnapshotRestore(&mut map)
+            .deserialize(&mut serde_json::Deserializer::from_str(&json))
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        Ok(Some(map))
+    }
+}
@@ -0,0 +1,189 @@
+use super::*;
+use ::zeroize::{Zeroize, Zeroizing};
+use map::GenericKey;
+
+/// Map for secrets such as tokens or keys, which wipes its values with `Zeroize` once they
+/// leave the map. Each entry may optionally expire after a specified duration.
+///
+/// Values are wiped when expired entries are dropped, and when the map is cleared or dropped.
+/// Replaced and removed values are handed back as `Zeroizing`, so they are wiped as soon as
+/// the caller drops them.
+///
+/// Copies left behind by the internal map reallocating are not covered, as with any
+/// `Zeroize`-based container.
+///
+/// # Example usage:
+/// ```rs
+/// let mut tokens: ZeroizingTimedMap<StdClock, u64, String> = ZeroizingTimedMap::new();
+///
+/// tokens.insert_expirable(user_id, token, Duration::from_secs(60));
+/// tokens.clear();
+/// ```
+pub struct ZeroizingTimedMap<C, K, V>
+where
+    V: Zeroize,
+{
+    inner: TimedMap<C, K, Zeroizing<V>>,
+}
+
+#[cfg(feature = "std")]
+impl<C, K, V> Default for ZeroizingTimedMap<C, K, V>
+where
+    V: Zeroize,
+{
+    fn default() -> Self {
+        Self {
+            inner: TimedMap::default(),
+        }
+    }
+}
+
+impl<C, K, V> ZeroizingTimedMap<C, K, V>
+where
+    C: Clock,
+    K: GenericKey,
+    V: Zeroize,
+{
+    /// Creates an empty map.
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty map.
+    ///
+    /// Uses the provided `clock` to handle expiration times.
+    #[cfg(not(feature = "std"))]
+    pub const fn new(clock: C) -> Self {
+        Self {
+            inner: TimedMap::new(clock),
+        }
+    }
+
+    /// Returns the associated value if present and not expired.
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.inner.get(k).map(|v| &**v)
+    }
+
+    /// Returns the remaining `Duration` of the entry of `k` if present and not expired.
+    ///
+    /// Returns `None` if the entry does not exist or is constant.
+    pub fn get_remaining_duration(&self, k: &K) -> Option<Duration> {
+        self.inner.get_remaining_duration(k)
+    }
+
+    /// Inserts a key-value pair with an expiration duration, and then drops the expired
+    /// entries.
+    ///
+    /// If a value already exists for the given key, it will be updated and then the old one
+    /// will be returned, to be wiped once dropped.
+    pub fn insert_expirable(&mut self, k: K, v: V, duration: Duration) -> Option<Zeroizing<V>> {
+        self.inner.insert_expirable(k, Zeroizing::new(v), duration)
+    }
+
+    /// Inserts a key-value pair that doesn't expire, and then drops the expired entries.
+    ///
+    /// If a value already exists for the given key, it will be updated and then the old one
+    /// will be returned, to be wiped once dropped.
+    pub fn insert_constant(&mut self, k: K, v: V) -> Option<Zeroizing<V>> {
+        self.inner.insert_constant(k, Zeroizing::new(v))
+    }
+
+    /// Removes a key-value pair from the map and returns the associated value, to be wiped
+    /// once dropped, if present and not expired.
+    pub fn remove(&mut self, k: &K) -> Option<Zeroizing<V>> {
+        self.inner.remove(k)
+    }
+
+    /// Removes and wipes all entries.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Drops and wipes the expired entries.
+    pub fn drop_expired_entries(&mut self) {
+        self.inner.drop_expired_entries();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    /// Secret which counts how many times it was wiped.
+    struct Secret<'a> {
+        value: u32,
+        wipes: &'a Cell<usize>,
+    }
+
+    impl Zeroize for Secret<'_> {
+        fn zeroize(&mut self) {
+            self.value.zeroize();
+            self.wipes.set(self.wipes.get() + 1);
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn nostd_values_are_wiped() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(1000);
+        let wipes = Cell::new(0);
+        let secret = |value| Secret {
+            value,
+            wipes: &wipes,
+        };
+
+        let mut map = ZeroizingTimedMap::new(&clock);
+        map.insert_expirable(1, secret(1), Duration::from_secs(10));
+        map.insert_constant(2, secret(2));
+        assert_eq!(map.get(&2).map(|s| s.value), Some(2));
+
+        // Expired.
+        clock.set(1011);
+        map.drop_expired_entries();
+        assert_eq!(wipes.get(), 1);
+
+        // Replaced.
+        let old = map.insert_constant(2, secret(3));
+        assert_eq!(old.as_ref().map(|s| s.value), Some(2));
+        drop(old);
+        assert_eq!(wipes.get(), 2);
+
+        // Cleared.
+        map.insert_constant(3, secret(4));
+        map.clear();
+        assert_eq!(wipes.get(), 4);
+        assert_eq!(map.get(&2).map(|s| s.value), None);
+
+        // Dropped along with the map.
+        map.insert_constant(4, secret(5));
+        drop(map);
+        assert_eq!(wipes.get(), 5);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_removed_values_are_wiped() {
+        let wipes = Cell::new(0);
+        let mut map: ZeroizingTimedMap<StdClock, u32, Secret> = ZeroizingTimedMap::new();
+
+        map.insert_expirable(
+            1,
+            Secret {
+                value: 1,
+                wipes: &wipes,
+            },
+            Duration::from_secs(60),
+        );
+        assert_eq!(map.get(&1).map(|s| s.value), Some(1));
+
+        let removed = map.remove(&1);
+        assert_eq!(wipes.get(), 0);
+        drop(removed);
+        assert_eq!(wipes.get(), 1);
+        assert!(map.remove(&1).is_none());
+    }
+}